@@ -0,0 +1,304 @@
+//! Parsing of CSS color values into 8-bit sRGB
+
+use std::fmt;
+
+/// A named 8-bit sRGB color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Swatch {
+	pub name: String,
+	pub rgb: [u8; 3],
+}
+
+impl Swatch {
+	/// Parses either `color` or `name=color`. Without a name, the color is
+	/// named after its hex notation.
+	pub fn parse(s: &str) -> Option<Self> {
+		let (name, value) = match s.split_once('=') {
+			Some((name, value)) => (name.trim().to_owned(), value),
+			None => (String::new(), s),
+		};
+		let rgb = parse_color(value)?;
+		let name = if name.is_empty() { hex(rgb) } else { name };
+		Some(Self { name, rgb })
+	}
+}
+
+impl fmt::Display for Swatch {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let hex = hex(self.rgb);
+		if self.name == hex {
+			f.write_str(&hex)
+		} else {
+			write!(f, "{} ({})", self.name, hex)
+		}
+	}
+}
+
+/// Formats a color in lowercase `#rrggbb` notation
+pub fn hex(rgb: [u8; 3]) -> String {
+	format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+/// Parses an opaque CSS color: hex notation with or without a leading `#`,
+/// the `rgb()`/`rgba()` functions, or a named color. Alpha is ignored.
+pub fn parse_color(s: &str) -> Option<[u8; 3]> {
+	let s = s.trim().trim_matches(|c| c == '"' || c == '\'');
+	let lower = s.to_ascii_lowercase();
+	if let Some(args) = lower
+		.strip_prefix("rgba(")
+		.or_else(|| lower.strip_prefix("rgb("))
+	{
+		return parse_rgb_function(args.strip_suffix(')')?);
+	}
+	if let Some(rgb) = named_color(&lower) {
+		return Some(rgb);
+	}
+	parse_hex(lower.strip_prefix('#').unwrap_or(&lower))
+}
+
+fn parse_hex(digits: &str) -> Option<[u8; 3]> {
+	if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+		return None;
+	}
+	let nibble = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).ok();
+	let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+	match digits.len() {
+		3 | 4 => Some([nibble(0)? * 17, nibble(1)? * 17, nibble(2)? * 17]),
+		6 | 8 => Some([byte(0)?, byte(2)?, byte(4)?]),
+		_ => None,
+	}
+}
+
+/// Parses the arguments of `rgb()`, separated by either commas or spaces,
+/// with channels as numbers in `0..=255` or percentages
+fn parse_rgb_function(args: &str) -> Option<[u8; 3]> {
+	// drop the alpha channel of both `rgba(r, g, b, a)` and `rgb(r g b / a)`
+	let args = args.split('/').next()?;
+	let channels: Vec<&str> = args
+		.split(|c: char| c == ',' || c.is_whitespace())
+		.filter(|s| !s.is_empty())
+		.collect();
+	if channels.len() < 3 || channels.len() > 4 {
+		return None;
+	}
+
+	let channel = |s: &str| -> Option<u8> {
+		let value = match s.strip_suffix('%') {
+			Some(percent) => percent.parse::<f32>().ok()? * 2.55,
+			None => s.parse::<f32>().ok()?,
+		};
+		Some(value.round().clamp(0.0, 255.0) as u8)
+	};
+	Some([
+		channel(channels[0])?,
+		channel(channels[1])?,
+		channel(channels[2])?,
+	])
+}
+
+/// Looks up a lowercase CSS named color
+fn named_color(name: &str) -> Option<[u8; 3]> {
+	NAMED_COLORS
+		.binary_search_by_key(&name, |&(n, _)| n)
+		.ok()
+		.map(|i| NAMED_COLORS[i].1)
+}
+
+/// The named colors of CSS Color Module Level 4, sorted by name
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+	("aliceblue", [240, 248, 255]),
+	("antiquewhite", [250, 235, 215]),
+	("aqua", [0, 255, 255]),
+	("aquamarine", [127, 255, 212]),
+	("azure", [240, 255, 255]),
+	("beige", [245, 245, 220]),
+	("bisque", [255, 228, 196]),
+	("black", [0, 0, 0]),
+	("blanchedalmond", [255, 235, 205]),
+	("blue", [0, 0, 255]),
+	("blueviolet", [138, 43, 226]),
+	("brown", [165, 42, 42]),
+	("burlywood", [222, 184, 135]),
+	("cadetblue", [95, 158, 160]),
+	("chartreuse", [127, 255, 0]),
+	("chocolate", [210, 105, 30]),
+	("coral", [255, 127, 80]),
+	("cornflowerblue", [100, 149, 237]),
+	("cornsilk", [255, 248, 220]),
+	("crimson", [220, 20, 60]),
+	("cyan", [0, 255, 255]),
+	("darkblue", [0, 0, 139]),
+	("darkcyan", [0, 139, 139]),
+	("darkgoldenrod", [184, 134, 11]),
+	("darkgray", [169, 169, 169]),
+	("darkgreen", [0, 100, 0]),
+	("darkgrey", [169, 169, 169]),
+	("darkkhaki", [189, 183, 107]),
+	("darkmagenta", [139, 0, 139]),
+	("darkolivegreen", [85, 107, 47]),
+	("darkorange", [255, 140, 0]),
+	("darkorchid", [153, 50, 204]),
+	("darkred", [139, 0, 0]),
+	("darksalmon", [233, 150, 122]),
+	("darkseagreen", [143, 188, 143]),
+	("darkslateblue", [72, 61, 139]),
+	("darkslategray", [47, 79, 79]),
+	("darkslategrey", [47, 79, 79]),
+	("darkturquoise", [0, 206, 209]),
+	("darkviolet", [148, 0, 211]),
+	("deeppink", [255, 20, 147]),
+	("deepskyblue", [0, 191, 255]),
+	("dimgray", [105, 105, 105]),
+	("dimgrey", [105, 105, 105]),
+	("dodgerblue", [30, 144, 255]),
+	("firebrick", [178, 34, 34]),
+	("floralwhite", [255, 250, 240]),
+	("forestgreen", [34, 139, 34]),
+	("fuchsia", [255, 0, 255]),
+	("gainsboro", [220, 220, 220]),
+	("ghostwhite", [248, 248, 255]),
+	("gold", [255, 215, 0]),
+	("goldenrod", [218, 165, 32]),
+	("gray", [128, 128, 128]),
+	("green", [0, 128, 0]),
+	("greenyellow", [173, 255, 47]),
+	("grey", [128, 128, 128]),
+	("honeydew", [240, 255, 240]),
+	("hotpink", [255, 105, 180]),
+	("indianred", [205, 92, 92]),
+	("indigo", [75, 0, 130]),
+	("ivory", [255, 255, 240]),
+	("khaki", [240, 230, 140]),
+	("lavender", [230, 230, 250]),
+	("lavenderblush", [255, 240, 245]),
+	("lawngreen", [124, 252, 0]),
+	("lemonchiffon", [255, 250, 205]),
+	("lightblue", [173, 216, 230]),
+	("lightcoral", [240, 128, 128]),
+	("lightcyan", [224, 255, 255]),
+	("lightgoldenrodyellow", [250, 250, 210]),
+	("lightgray", [211, 211, 211]),
+	("lightgreen", [144, 238, 144]),
+	("lightgrey", [211, 211, 211]),
+	("lightpink", [255, 182, 193]),
+	("lightsalmon", [255, 160, 122]),
+	("lightseagreen", [32, 178, 170]),
+	("lightskyblue", [135, 206, 250]),
+	("lightslategray", [119, 136, 153]),
+	("lightslategrey", [119, 136, 153]),
+	("lightsteelblue", [176, 196, 222]),
+	("lightyellow", [255, 255, 224]),
+	("lime", [0, 255, 0]),
+	("limegreen", [50, 205, 50]),
+	("linen", [250, 240, 230]),
+	("magenta", [255, 0, 255]),
+	("maroon", [128, 0, 0]),
+	("mediumaquamarine", [102, 205, 170]),
+	("mediumblue", [0, 0, 205]),
+	("mediumorchid", [186, 85, 211]),
+	("mediumpurple", [147, 112, 219]),
+	("mediumseagreen", [60, 179, 113]),
+	("mediumslateblue", [123, 104, 238]),
+	("mediumspringgreen", [0, 250, 154]),
+	("mediumturquoise", [72, 209, 204]),
+	("mediumvioletred", [199, 21, 133]),
+	("midnightblue", [25, 25, 112]),
+	("mintcream", [245, 255, 250]),
+	("mistyrose", [255, 228, 225]),
+	("moccasin", [255, 228, 181]),
+	("navajowhite", [255, 222, 173]),
+	("navy", [0, 0, 128]),
+	("oldlace", [253, 245, 230]),
+	("olive", [128, 128, 0]),
+	("olivedrab", [107, 142, 35]),
+	("orange", [255, 165, 0]),
+	("orangered", [255, 69, 0]),
+	("orchid", [218, 112, 214]),
+	("palegoldenrod", [238, 232, 170]),
+	("palegreen", [152, 251, 152]),
+	("paleturquoise", [175, 238, 238]),
+	("palevioletred", [219, 112, 147]),
+	("papayawhip", [255, 239, 213]),
+	("peachpuff", [255, 218, 185]),
+	("peru", [205, 133, 63]),
+	("pink", [255, 192, 203]),
+	("plum", [221, 160, 221]),
+	("powderblue", [176, 224, 230]),
+	("purple", [128, 0, 128]),
+	("rebeccapurple", [102, 51, 153]),
+	("red", [255, 0, 0]),
+	("rosybrown", [188, 143, 143]),
+	("royalblue", [65, 105, 225]),
+	("saddlebrown", [139, 69, 19]),
+	("salmon", [250, 128, 114]),
+	("sandybrown", [244, 164, 96]),
+	("seagreen", [46, 139, 87]),
+	("seashell", [255, 245, 238]),
+	("sienna", [160, 82, 45]),
+	("silver", [192, 192, 192]),
+	("skyblue", [135, 206, 235]),
+	("slateblue", [106, 90, 205]),
+	("slategray", [112, 128, 144]),
+	("slategrey", [112, 128, 144]),
+	("snow", [255, 250, 250]),
+	("springgreen", [0, 255, 127]),
+	("steelblue", [70, 130, 180]),
+	("tan", [210, 180, 140]),
+	("teal", [0, 128, 128]),
+	("thistle", [216, 191, 216]),
+	("tomato", [255, 99, 71]),
+	("turquoise", [64, 224, 208]),
+	("violet", [238, 130, 238]),
+	("wheat", [245, 222, 179]),
+	("white", [255, 255, 255]),
+	("whitesmoke", [245, 245, 245]),
+	("yellow", [255, 255, 0]),
+	("yellowgreen", [154, 205, 50]),
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_named_colors_sorted() {
+		assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+	}
+
+	#[test]
+	fn test_parse_hex() {
+		assert_eq!(parse_color("#ff8000"), Some([255, 128, 0]));
+		assert_eq!(parse_color("FF8000"), Some([255, 128, 0]));
+		assert_eq!(parse_color("#f80"), Some([255, 136, 0]));
+		assert_eq!(parse_color("#f80c"), Some([255, 136, 0]));
+		assert_eq!(parse_color("#ff800080"), Some([255, 128, 0]));
+		assert_eq!(parse_color("#ff80"), Some([255, 255, 136]));
+		assert_eq!(parse_color("#ff80g0"), None);
+		assert_eq!(parse_color("#ff80000"), None);
+	}
+
+	#[test]
+	fn test_parse_rgb_function() {
+		assert_eq!(parse_color("rgb(255, 128, 0)"), Some([255, 128, 0]));
+		assert_eq!(parse_color("rgba(255,128,0,0.5)"), Some([255, 128, 0]));
+		assert_eq!(parse_color("rgb(100% 50% 0% / 50%)"), Some([255, 128, 0]));
+		assert_eq!(parse_color("rgb(1, 2)"), None);
+	}
+
+	#[test]
+	fn test_parse_named() {
+		assert_eq!(parse_color("RebeccaPurple"), Some([102, 51, 153]));
+		assert_eq!(parse_color("\"red\""), Some([255, 0, 0]));
+		assert_eq!(parse_color("transparent"), None);
+	}
+
+	#[test]
+	fn test_swatch_parse() {
+		let swatch = Swatch::parse("danger=#d00").unwrap();
+		assert_eq!(swatch.name, "danger");
+		assert_eq!(swatch.to_string(), "danger (#dd0000)");
+
+		let swatch = Swatch::parse("#00ff00").unwrap();
+		assert_eq!(swatch.to_string(), "#00ff00");
+	}
+}
@@ -0,0 +1,292 @@
+//! A forgiving parser for the declarations of CSS style rules
+//!
+//! This only understands enough of CSS to find declarations and the
+//! selector of the rule they belong to. Rules nested in at-rules such
+//! as `@media` are flattened.
+
+use crate::color::parse_color;
+use std::collections::HashMap;
+
+/// The maximum depth of `var()` references which are resolved
+const MAX_VAR_DEPTH: usize = 16;
+
+/// A single `property: value` declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+	/// The property name, in lowercase unless it's a custom property
+	pub property: String,
+	pub value: String,
+	/// The 1-based line the declaration starts on
+	pub line: usize,
+}
+
+/// A style rule with its declarations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+	pub selector: String,
+	/// The 1-based line the selector starts on
+	pub line: usize,
+	pub declarations: Vec<Declaration>,
+}
+
+impl Rule {
+	/// The last declaration of a property, which wins the cascade
+	pub fn get(&self, property: &str) -> Option<&Declaration> {
+		self.declarations
+			.iter()
+			.rev()
+			.find(|decl| decl.property == property)
+	}
+}
+
+/// A parsed stylesheet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stylesheet {
+	pub rules: Vec<Rule>,
+}
+
+impl Stylesheet {
+	/// Parses a stylesheet, skipping anything that isn't understood
+	pub fn parse(source: &str) -> Self {
+		let source = strip_comments(source);
+		let mut rules = Vec::new();
+		let mut stack: Vec<Rule> = Vec::new();
+		let mut buffer = String::new();
+		let mut buffer_line = 1;
+		let mut line = 1;
+		let mut quote = None;
+		let mut parens = 0usize;
+
+		for c in source.chars() {
+			if buffer.trim().is_empty() {
+				buffer_line = line;
+			}
+			if c == '\n' {
+				line += 1;
+			}
+
+			match (quote, c) {
+				(Some(q), c) if c == q => quote = None,
+				(Some(_), _) => {}
+				(None, '"' | '\'') => quote = Some(c),
+				(None, '(') => parens += 1,
+				(None, ')') => parens = parens.saturating_sub(1),
+				(None, '{') if parens == 0 => {
+					stack.push(Rule {
+						selector: collapse_whitespace(&buffer),
+						line: buffer_line,
+						declarations: Vec::new(),
+					});
+					buffer.clear();
+					continue;
+				}
+				(None, ';' | '}') if parens == 0 => {
+					if let Some(rule) = stack.last_mut() {
+						if let Some(decl) =
+							parse_declaration(&buffer, buffer_line)
+						{
+							rule.declarations.push(decl);
+						}
+					}
+					buffer.clear();
+					if c == '}' {
+						if let Some(rule) = stack.pop() {
+							if !rule.declarations.is_empty() {
+								rules.push(rule);
+							}
+						}
+					}
+					continue;
+				}
+				_ => {}
+			}
+			buffer.push(c);
+		}
+
+		// recover rules left unclosed at the end of the file
+		while let Some(rule) = stack.pop() {
+			if !rule.declarations.is_empty() {
+				rules.push(rule);
+			}
+		}
+		rules.sort_by_key(|rule| rule.line);
+		Self { rules }
+	}
+
+	/// All custom properties declared in the stylesheet, where later
+	/// declarations override earlier ones regardless of selector
+	pub fn custom_properties(&self) -> HashMap<&str, &str> {
+		self.rules
+			.iter()
+			.flat_map(|rule| &rule.declarations)
+			.filter(|decl| decl.property.starts_with("--"))
+			.map(|decl| (decl.property.as_str(), decl.value.as_str()))
+			.collect()
+	}
+}
+
+fn parse_declaration(buffer: &str, line: usize) -> Option<Declaration> {
+	let (property, value) = buffer.split_once(':')?;
+	let property = property.trim();
+	if property.is_empty()
+		|| !property
+			.chars()
+			.all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+	{
+		return None;
+	}
+	let property = if property.starts_with("--") {
+		property.to_owned()
+	} else {
+		property.to_ascii_lowercase()
+	};
+	let value = value.trim().trim_end_matches("!important").trim();
+	Some(Declaration {
+		property,
+		value: value.to_owned(),
+		line,
+	})
+}
+
+/// Replaces comments with spaces, keeping newlines so lines still line up
+fn strip_comments(source: &str) -> String {
+	let mut output = String::with_capacity(source.len());
+	let mut rest = source;
+	while let Some(start) = rest.find("/*") {
+		output.push_str(&rest[..start]);
+		let end = rest[start + 2..]
+			.find("*/")
+			.map_or(rest.len(), |end| start + 2 + end + 2);
+		output.extend(rest[start..end]
+			.chars()
+			.map(|c| if c == '\n' { '\n' } else { ' ' }));
+		rest = &rest[end..];
+	}
+	output.push_str(rest);
+	output
+}
+
+fn collapse_whitespace(s: &str) -> String {
+	s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Substitutes `var(--name)` and `var(--name, fallback)` references
+/// with the values of custom properties
+pub fn resolve_vars(value: &str, properties: &HashMap<&str, &str>) -> Option<String> {
+	let mut value = value.to_owned();
+	for _ in 0..MAX_VAR_DEPTH {
+		let Some(start) = value.find("var(") else {
+			return Some(value);
+		};
+
+		// find the matching closing parenthesis
+		let mut depth = 0;
+		let mut end = None;
+		for (i, c) in value[start..].char_indices() {
+			match c {
+				'(' => depth += 1,
+				')' => {
+					depth -= 1;
+					if depth == 0 {
+						end = Some(start + i);
+						break;
+					}
+				}
+				_ => {}
+			}
+		}
+		let end = end?;
+
+		let args = &value[start + 4..end];
+		let (name, fallback) = match args.split_once(',') {
+			Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+			None => (args.trim(), None),
+		};
+		let replacement = properties.get(name).copied().or(fallback)?;
+		value = format!("{}{}{}", &value[..start], replacement, &value[end + 1..]);
+	}
+	None
+}
+
+/// Finds the first color among the space-separated components of a value,
+/// as in the `background` shorthand
+pub fn find_color(value: &str) -> Option<[u8; 3]> {
+	if let Some(rgb) = parse_color(value) {
+		return Some(rgb);
+	}
+
+	let mut depth = 0usize;
+	let mut start = 0;
+	let mut components = Vec::new();
+	for (i, c) in value.char_indices() {
+		match c {
+			'(' => depth += 1,
+			')' => depth = depth.saturating_sub(1),
+			c if c.is_whitespace() && depth == 0 => {
+				components.push(&value[start..i]);
+				start = i + 1;
+			}
+			_ => {}
+		}
+	}
+	components.push(&value[start..]);
+	components.into_iter().find_map(parse_color)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_rules() {
+		let css = "/* header\n comment */\n.a, .b {\n  color: red;\n  BACKGROUND: url(\"x;y.png\") #fff;\n}\n\n@media (min-width: 10px) {\n  .c { color: blue !important }\n}\n";
+		let sheet = Stylesheet::parse(css);
+		assert_eq!(sheet.rules.len(), 2);
+
+		let a = &sheet.rules[0];
+		assert_eq!(a.selector, ".a, .b");
+		assert_eq!(a.line, 3);
+		assert_eq!(a.get("color").unwrap().line, 4);
+		assert_eq!(a.get("background").unwrap().value, "url(\"x;y.png\") #fff");
+
+		let c = &sheet.rules[1];
+		assert_eq!(c.selector, ".c");
+		assert_eq!(c.line, 9);
+		assert_eq!(c.get("color").unwrap().value, "blue");
+	}
+
+	#[test]
+	fn test_custom_properties() {
+		let sheet = Stylesheet::parse(":root { --Text: #111; } .dark { --Text: #eee; }");
+		let properties = sheet.custom_properties();
+		assert_eq!(properties.get("--Text"), Some(&"#eee"));
+	}
+
+	#[test]
+	fn test_resolve_vars() {
+		let sheet = Stylesheet::parse(
+			":root { --a: var(--b); --b: #123456; --loop: var(--loop); }",
+		);
+		let properties = sheet.custom_properties();
+		assert_eq!(
+			resolve_vars("var(--a)", &properties).as_deref(),
+			Some("#123456")
+		);
+		assert_eq!(
+			resolve_vars("var(--missing, rgb(1, 2, 3))", &properties).as_deref(),
+			Some("rgb(1, 2, 3)")
+		);
+		assert_eq!(resolve_vars("var(--missing)", &properties), None);
+		assert_eq!(resolve_vars("var(--loop)", &properties), None);
+	}
+
+	#[test]
+	fn test_find_color() {
+		assert_eq!(find_color("#fff"), Some([255, 255, 255]));
+		assert_eq!(
+			find_color("url(a.png) no-repeat rgb(0, 0, 255)"),
+			Some([0, 0, 255])
+		);
+		assert_eq!(find_color("none"), None);
+	}
+}
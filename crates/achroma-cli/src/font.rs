@@ -0,0 +1,120 @@
+//! A minimal 5×7 bitmap font for labeling images
+
+use image::{Rgba, RgbaImage};
+
+/// Width of a glyph in pixels, before scaling
+pub const GLYPH_WIDTH: u32 = 5;
+/// Height of a glyph in pixels, before scaling
+pub const GLYPH_HEIGHT: u32 = 7;
+/// Horizontal space between glyphs in pixels, before scaling
+pub const GLYPH_SPACING: u32 = 1;
+
+/// Rows of a glyph from top to bottom, where the lowest 5 bits of each row
+/// are its pixels from left to right. Unknown characters render as blank.
+fn glyph(c: char) -> [u8; 7] {
+	match c.to_ascii_uppercase() {
+		'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+		'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+		'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+		'D' => [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E],
+		'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+		'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+		'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+		'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+		'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+		'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+		'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+		'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+		'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+		'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+		'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+		'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+		'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+		'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+		'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+		'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+		'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+		'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+		'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+		'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+		'Y' => [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04],
+		'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+		'0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+		'1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+		'2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+		'3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+		'4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+		'5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+		'6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+		'7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+		'8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+		'9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+		'-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+		'.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+		'%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+		_ => [0; 7],
+	}
+}
+
+/// The width of a line of text in pixels, at a given scale
+pub fn text_width(text: &str, scale: u32) -> u32 {
+	let len = text.chars().count() as u32;
+	if len == 0 {
+		return 0;
+	}
+	(len * (GLYPH_WIDTH + GLYPH_SPACING) - GLYPH_SPACING) * scale
+}
+
+/// Draws a line of text with its top-left corner at `(x, y)`, clipping
+/// anything outside of the image
+pub fn draw_text(image: &mut RgbaImage, text: &str, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
+	let advance = (GLYPH_WIDTH + GLYPH_SPACING) * scale;
+	for (i, c) in text.chars().enumerate() {
+		let origin_x = x + i as u32 * advance;
+		for (row, bits) in glyph(c).iter().enumerate() {
+			for col in 0..GLYPH_WIDTH {
+				if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+					continue;
+				}
+				for dy in 0..scale {
+					for dx in 0..scale {
+						let px = origin_x + col * scale + dx;
+						let py = y + row as u32 * scale + dy;
+						if px < image.width() && py < image.height() {
+							image.put_pixel(px, py, color);
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_text_width() {
+		assert_eq!(text_width("", 1), 0);
+		assert_eq!(text_width("A", 1), 5);
+		assert_eq!(text_width("AB", 2), 22);
+	}
+
+	#[test]
+	fn test_glyph_case_insensitive() {
+		assert_eq!(glyph('a'), glyph('A'));
+		assert_eq!(glyph('?'), [0; 7]);
+	}
+
+	#[test]
+	fn test_draw_text_clips() {
+		let white = Rgba([255, 255, 255, 255]);
+		let mut image = RgbaImage::new(4, 4);
+		draw_text(&mut image, "T", 0, 0, 1, white);
+
+		// the top bar of `T` spans the first row, clipped at the edge
+		assert!((0..4).all(|x| image.get_pixel(x, 0) == &white));
+		assert_eq!(image.get_pixel(0, 1), &Rgba([0, 0, 0, 0]));
+	}
+}
@@ -0,0 +1,320 @@
+//! The `lint` subcommand, which checks the text and background colors
+//! of stylesheets for low contrast with each type of color vision
+
+use crate::css::{find_color, resolve_vars, Rule, Stylesheet};
+use crate::palette::{serialize_vision, OutputFormat};
+use crate::{color::hex, parse_vision, SimulationOptions};
+use achroma::contrast::{contrast_ratio_linear, WCAG_AA};
+use achroma::difference::Lab;
+use achroma::simulation::decode_srgb8;
+use achroma::ColorVision;
+use clap::Args;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Debug, Args)]
+pub struct LintArgs {
+	/// Stylesheets, or directories to search for `.css` files
+	#[arg(required = true)]
+	pub paths: Vec<PathBuf>,
+
+	/// Types of color vision to check, separated by commas [default: all]
+	#[arg(
+		short,
+		long = "vision",
+		value_name = "VISION",
+		value_delimiter = ',',
+		value_parser = parse_vision,
+	)]
+	pub visions: Vec<ColorVision>,
+
+	#[command(flatten)]
+	pub options: SimulationOptions,
+
+	/// Minimum WCAG contrast ratio between text and its background
+	#[arg(short = 'c', long, default_value_t = WCAG_AA)]
+	pub min_contrast: f32,
+
+	/// Minimum CIEDE2000 difference for text to be distinguishable from its background
+	#[arg(short, long, default_value_t = 10.0)]
+	pub threshold: f32,
+
+	/// Format of the report
+	#[arg(long, value_enum, default_value_t)]
+	pub format: OutputFormat,
+}
+
+/// Limits that a pair of colors is checked against
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+	pub min_contrast: f32,
+	pub threshold: f32,
+}
+
+/// A pair of colors which is hard to read with some types of color vision
+#[derive(Debug, Serialize)]
+pub struct Issue {
+	pub path: String,
+	pub line: usize,
+	pub selector: String,
+	pub kind: IssueKind,
+	pub foreground: String,
+	pub background: String,
+	/// The types of color vision affected by the issue, from worst to best
+	pub visions: Vec<Measurement>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+	/// The contrast ratio is below the minimum
+	LowContrast,
+	/// The color difference is below the threshold
+	Indistinguishable,
+}
+
+/// A contrast ratio or color difference measured with a type of color vision
+#[derive(Debug, Serialize)]
+pub struct Measurement {
+	#[serde(serialize_with = "serialize_vision")]
+	pub vision: ColorVision,
+	pub value: f32,
+}
+
+/// Runs the `lint` subcommand, failing when any issue is found
+pub fn run(args: &LintArgs) -> Result<ExitCode, Box<dyn Error>> {
+	let mut files = Vec::new();
+	for path in &args.paths {
+		collect_stylesheets(path, &mut files)
+			.map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+	}
+
+	let visions = if args.visions.is_empty() {
+		&ColorVision::ALL[..]
+	} else {
+		&args.visions[..]
+	};
+	let limits = Limits {
+		min_contrast: args.min_contrast,
+		threshold: args.threshold,
+	};
+
+	let mut issues = Vec::new();
+	for file in &files {
+		let source = std::fs::read_to_string(file)
+			.map_err(|err| format!("failed to read {}: {}", file.display(), err))?;
+		let sheet = Stylesheet::parse(&source);
+		issues.extend(lint(
+			&file.display().to_string(),
+			&sheet,
+			visions,
+			args.options,
+			limits,
+		));
+	}
+
+	let mut stdout = io::stdout().lock();
+	match args.format {
+		OutputFormat::Table => {
+			for issue in &issues {
+				writeln!(stdout, "{}", describe(issue, limits))?;
+			}
+			if !issues.is_empty() {
+				writeln!(
+					stdout,
+					"\n{} issue(s) in {} stylesheet(s)",
+					issues.len(),
+					files.len()
+				)?;
+			}
+		}
+		OutputFormat::Json => {
+			serde_json::to_writer_pretty(&mut stdout, &issues)?;
+			writeln!(stdout)?;
+		}
+	}
+
+	Ok(if issues.is_empty() {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::FAILURE
+	})
+}
+
+/// Collects `.css` files, searching directories recursively
+fn collect_stylesheets(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+	if !path.is_dir() {
+		files.push(path.to_path_buf());
+		return Ok(());
+	}
+
+	let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+		.map(|entry| entry.map(|e| e.path()))
+		.collect::<io::Result<_>>()?;
+	entries.sort();
+	for entry in entries {
+		if entry.is_dir() {
+			collect_stylesheets(&entry, files)?;
+		} else if entry.extension().is_some_and(|ext| ext == "css") {
+			files.push(entry);
+		}
+	}
+	Ok(())
+}
+
+/// Finds issues between the text and background colors of each rule
+pub fn lint(
+	path: &str,
+	sheet: &Stylesheet,
+	visions: &[ColorVision],
+	options: SimulationOptions,
+	limits: Limits,
+) -> Vec<Issue> {
+	let properties = sheet.custom_properties();
+	let mut issues = Vec::new();
+
+	for rule in &sheet.rules {
+		let Some((line, foreground, background)) = color_pair(rule, &properties) else {
+			continue;
+		};
+
+		let mut low_contrast = Vec::new();
+		let mut indistinguishable = Vec::new();
+		for &vision in visions {
+			let simulation = options.simulation(vision);
+			let fg = simulation.simulate_linear(decode_srgb8(foreground));
+			let bg = simulation.simulate_linear(decode_srgb8(background));
+
+			let ratio = contrast_ratio_linear(fg, bg);
+			if ratio < limits.min_contrast {
+				low_contrast.push(Measurement {
+					vision,
+					value: ratio,
+				});
+			}
+			let delta_e =
+				Lab::from_linear_srgb(fg).delta_e_2000(&Lab::from_linear_srgb(bg));
+			if delta_e < limits.threshold {
+				indistinguishable.push(Measurement {
+					vision,
+					value: delta_e,
+				});
+			}
+		}
+
+		for (kind, mut visions) in [
+			(IssueKind::LowContrast, low_contrast),
+			(IssueKind::Indistinguishable, indistinguishable),
+		] {
+			if visions.is_empty() {
+				continue;
+			}
+			visions.sort_by(|a, b| a.value.total_cmp(&b.value));
+			issues.push(Issue {
+				path: path.to_owned(),
+				line,
+				selector: rule.selector.clone(),
+				kind,
+				foreground: hex(foreground),
+				background: hex(background),
+				visions,
+			});
+		}
+	}
+	issues
+}
+
+/// The text and background colors of a rule, along with the line of the
+/// text color, when both are declared and resolve to opaque colors
+fn color_pair(rule: &Rule, properties: &HashMap<&str, &str>) -> Option<(usize, [u8; 3], [u8; 3])> {
+	let color = rule.get("color")?;
+	let background = rule.declarations.iter().rev().find(|decl| {
+		decl.property == "background-color" || decl.property == "background"
+	})?;
+
+	let foreground = find_color(&resolve_vars(&color.value, properties)?)?;
+	let background = find_color(&resolve_vars(&background.value, properties)?)?;
+	Some((color.line, foreground, background))
+}
+
+/// Describes an issue on a single line, prefixed by its location
+fn describe(issue: &Issue, limits: Limits) -> String {
+	let names: Vec<&str> = issue.visions.iter().map(|m| m.vision.name()).collect();
+	let worst = &issue.visions[0];
+	let detail = match issue.kind {
+		IssueKind::LowContrast => format!(
+			"has low contrast with {} (worst {:.2}:1 with {}, minimum {:.1}:1)",
+			names.join(", "),
+			worst.value,
+			worst.vision,
+			limits.min_contrast
+		),
+		IssueKind::Indistinguishable => format!(
+			"is indistinguishable with {} (worst ΔE {:.2} with {}, minimum {:.1})",
+			names.join(", "),
+			worst.value,
+			worst.vision,
+			limits.threshold
+		),
+	};
+	format!(
+		"{}:{}: {}: {} on {} {}",
+		issue.path, issue.line, issue.selector, issue.foreground, issue.background, detail
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const LIMITS: Limits = Limits {
+		min_contrast: WCAG_AA,
+		threshold: 10.0,
+	};
+
+	#[test]
+	fn test_lint_red_on_green() {
+		let css = ":root {\n  --danger: #d62728;\n}\n\n.alert {\n  color: var(--danger);\n  background: #2ca02c;\n}\n";
+		let sheet = Stylesheet::parse(css);
+		let issues = lint(
+			"app.css",
+			&sheet,
+			&[ColorVision::Normal, ColorVision::Deuteranopia],
+			SimulationOptions::default(),
+			LIMITS,
+		);
+
+		assert_eq!(issues.len(), 2);
+		assert_eq!(issues[0].kind, IssueKind::LowContrast);
+		assert_eq!(issues[0].line, 6);
+		assert_eq!(issues[0].selector, ".alert");
+		assert_eq!(issues[0].foreground, "#d62728");
+
+		// only deuteranopia makes the colors indistinguishable
+		assert_eq!(issues[1].kind, IssueKind::Indistinguishable);
+		assert_eq!(issues[1].visions.len(), 1);
+		assert_eq!(issues[1].visions[0].vision, ColorVision::Deuteranopia);
+
+		let description = describe(&issues[1], LIMITS);
+		assert!(description
+			.starts_with("app.css:6: .alert: #d62728 on #2ca02c is indistinguishable"));
+	}
+
+	#[test]
+	fn test_lint_skips_incomplete_pairs() {
+		let css = ".a { color: #000; }\n.b { color: inherit; background: #fff; }\n.c { color: #fff; background-color: #000; }";
+		let sheet = Stylesheet::parse(css);
+		let issues = lint(
+			"app.css",
+			&sheet,
+			&ColorVision::ALL,
+			SimulationOptions::default(),
+			LIMITS,
+		);
+		assert!(issues.is_empty());
+	}
+}
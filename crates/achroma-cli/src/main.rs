@@ -0,0 +1,169 @@
+//! Command-line tool for simulating color vision deficiency (CVD)
+
+use achroma::simulation::{Algorithm, Simulation};
+use achroma::species::Species;
+use achroma::ColorVision;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::process::ExitCode;
+
+mod color;
+mod css;
+mod font;
+mod lint;
+mod palette;
+mod plate;
+mod simulate;
+mod tokens;
+
+#[derive(Debug, Parser)]
+#[command(name = "achroma", version, about)]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+	/// Simulate or daltonize an image for one or more types of color vision
+	Simulate(simulate::SimulateArgs),
+	/// Check a palette for colors that are confusable with color vision deficiency
+	Palette(palette::PaletteArgs),
+	/// Check stylesheets for text that loses contrast with color vision deficiency
+	Lint(lint::LintArgs),
+	/// Audit pairs of color tokens from a W3C Design Tokens file
+	Tokens(tokens::TokensArgs),
+	/// Generate a pseudoisochromatic plate, in the style of the Ishihara test
+	Plate(plate::PlateArgs),
+}
+
+/// Options shared by subcommands which simulate color vision
+#[derive(Debug, Clone, Copy, Args)]
+struct SimulationOptions {
+	/// Algorithm used for simulating color vision
+	#[arg(short, long, value_enum, default_value_t)]
+	algorithm: AlgorithmArg,
+
+	/// Severity of the deficiency, from 0.0 (none) to 1.0 (full)
+	#[arg(short, long, default_value_t = 1.0, value_parser = parse_severity)]
+	severity: f32,
+}
+
+impl SimulationOptions {
+	fn simulation(&self, vision: ColorVision) -> Simulation {
+		Simulation::new(vision, self.severity).with_algorithm(self.algorithm.into())
+	}
+}
+
+impl Default for SimulationOptions {
+	fn default() -> Self {
+		Self {
+			algorithm: AlgorithmArg::default(),
+			severity: 1.0,
+		}
+	}
+}
+
+/// A simulation algorithm, as accepted on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum AlgorithmArg {
+	/// Machado, Oliveira & Fernandes (2009)
+	#[default]
+	Machado,
+	/// Viénot, Brettel & Mollon (1999)
+	Vienot,
+}
+
+impl From<AlgorithmArg> for Algorithm {
+	fn from(arg: AlgorithmArg) -> Self {
+		match arg {
+			AlgorithmArg::Machado => Algorithm::Machado2009,
+			AlgorithmArg::Vienot => Algorithm::Vienot1999,
+		}
+	}
+}
+
+/// Parses a type of color vision by its name
+fn parse_vision(s: &str) -> Result<ColorVision, String> {
+	s.parse().map_err(|()| {
+		let names: Vec<&str> = ColorVision::ALL.iter().map(|v| v.name()).collect();
+		format!("expected one of: {}", names.join(", "))
+	})
+}
+
+/// Parses a species by its name
+fn parse_species(s: &str) -> Result<Species, String> {
+	s.parse().map_err(|()| {
+		let names: Vec<&str> = Species::ALL.iter().map(|s| s.name()).collect();
+		format!("expected one of: {}", names.join(", "))
+	})
+}
+
+/// Parses a severity between 0.0 and 1.0
+fn parse_severity(s: &str) -> Result<f32, String> {
+	let severity: f32 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+	if (0.0..=1.0).contains(&severity) {
+		Ok(severity)
+	} else {
+		Err(String::from("severity must be between 0.0 and 1.0"))
+	}
+}
+
+/// Parses a positive luminance in cd/m²
+fn parse_luminance(s: &str) -> Result<f32, String> {
+	let luminance: f32 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+	if luminance > 0.0 && luminance.is_finite() {
+		Ok(luminance)
+	} else {
+		Err(String::from("luminance must be greater than 0.0"))
+	}
+}
+
+fn main() -> ExitCode {
+	let cli = Cli::parse();
+	let result = match cli.command {
+		Command::Simulate(args) => simulate::run(&args).map(|()| ExitCode::SUCCESS),
+		Command::Palette(args) => palette::run(&args),
+		Command::Lint(args) => lint::run(&args),
+		Command::Tokens(args) => tokens::run(&args),
+		Command::Plate(args) => plate::run(&args).map(|()| ExitCode::SUCCESS),
+	};
+
+	match result {
+		Ok(code) => code,
+		Err(err) => {
+			eprintln!("error: {}", err);
+			ExitCode::FAILURE
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::CommandFactory;
+
+	#[test]
+	fn test_cli_debug_assert() {
+		Cli::command().debug_assert();
+	}
+
+	#[test]
+	fn test_parse_severity() {
+		assert_eq!(parse_severity("0.5"), Ok(0.5));
+		assert!(parse_severity("1.5").is_err());
+		assert!(parse_severity("mild").is_err());
+	}
+
+	#[test]
+	fn test_parse_luminance() {
+		assert_eq!(parse_luminance("0.1"), Ok(0.1));
+		assert!(parse_luminance("0").is_err());
+		assert!(parse_luminance("dark").is_err());
+	}
+
+	#[test]
+	fn test_parse_vision() {
+		assert_eq!(parse_vision("protanopia"), Ok(ColorVision::Protanopia));
+		assert!(parse_vision("purple").unwrap_err().contains("deuteranopia"));
+	}
+}
@@ -0,0 +1,441 @@
+//! The `palette` subcommand, which finds colors in a palette that become
+//! confusable with a type of color vision
+
+use crate::color::{hex, parse_color, Swatch};
+use crate::css::Stylesheet;
+use crate::{parse_vision, SimulationOptions};
+use achroma::difference::Lab;
+use achroma::simulation::decode_srgb8;
+use achroma::ColorVision;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Debug, Args)]
+pub struct PaletteArgs {
+	/// Colors to check, as hex, `rgb()` or a CSS color name, optionally named as `name=color`
+	pub colors: Vec<String>,
+
+	/// Read colors from a JSON, CSV or CSS custom properties file
+	#[arg(short, long)]
+	pub file: Option<PathBuf>,
+
+	/// Types of color vision to check, separated by commas [default: all]
+	#[arg(
+		short,
+		long = "vision",
+		value_name = "VISION",
+		value_delimiter = ',',
+		value_parser = parse_vision,
+	)]
+	pub visions: Vec<ColorVision>,
+
+	#[command(flatten)]
+	pub options: SimulationOptions,
+
+	/// Minimum CIEDE2000 difference for two colors to be distinguishable
+	#[arg(short, long, default_value_t = 10.0)]
+	pub threshold: f32,
+
+	/// Format of the report
+	#[arg(long, value_enum, default_value_t)]
+	pub format: OutputFormat,
+}
+
+/// Format of a report written to standard output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+	/// Human-readable table
+	#[default]
+	Table,
+	/// Machine-readable JSON
+	Json,
+}
+
+/// Confusable pairs of a palette for every checked type of color vision
+#[derive(Debug, Serialize)]
+pub struct Report {
+	pub threshold: f32,
+	pub passed: bool,
+	pub visions: Vec<VisionReport>,
+}
+
+/// Confusable pairs of a palette for a single type of color vision
+#[derive(Debug, Serialize)]
+pub struct VisionReport {
+	#[serde(serialize_with = "serialize_vision")]
+	pub vision: ColorVision,
+	pub pairs: Vec<Pair>,
+}
+
+/// Two colors of a palette which are too similar once simulated
+#[derive(Debug, Serialize)]
+pub struct Pair {
+	pub first: SwatchReport,
+	pub second: SwatchReport,
+	pub delta_e: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwatchReport {
+	pub name: String,
+	pub color: String,
+}
+
+impl From<&Swatch> for SwatchReport {
+	fn from(swatch: &Swatch) -> Self {
+		Self {
+			name: swatch.name.clone(),
+			color: hex(swatch.rgb),
+		}
+	}
+}
+
+pub fn serialize_vision<S: serde::Serializer>(
+	vision: &ColorVision,
+	serializer: S,
+) -> Result<S::Ok, S::Error> {
+	serializer.serialize_str(vision.name())
+}
+
+/// Runs the `palette` subcommand, failing when any pair is confusable
+pub fn run(args: &PaletteArgs) -> Result<ExitCode, Box<dyn Error>> {
+	let mut swatches = Vec::new();
+	for color in &args.colors {
+		swatches.push(
+			Swatch::parse(color).ok_or_else(|| format!("invalid color `{}`", color))?
+		);
+	}
+	if let Some(file) = &args.file {
+		swatches.extend(read_file(file)?);
+	}
+	if swatches.len() < 2 {
+		return Err("a palette needs at least two colors".into());
+	}
+
+	let visions = if args.visions.is_empty() {
+		&ColorVision::ALL[..]
+	} else {
+		&args.visions[..]
+	};
+	let report = check(&swatches, visions, args.options, args.threshold);
+
+	let mut stdout = io::stdout().lock();
+	match args.format {
+		OutputFormat::Table => write_table(&mut stdout, &report)?,
+		OutputFormat::Json => {
+			serde_json::to_writer_pretty(&mut stdout, &report)?;
+			writeln!(stdout)?;
+		}
+	}
+
+	Ok(if report.passed {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::FAILURE
+	})
+}
+
+/// Compares every pair of colors in a palette as seen with each type of color vision
+pub fn check(
+	swatches: &[Swatch],
+	visions: &[ColorVision],
+	options: SimulationOptions,
+	threshold: f32,
+) -> Report {
+	let visions: Vec<VisionReport> = visions
+		.iter()
+		.map(|&vision| {
+			let simulation = options.simulation(vision);
+			let simulated: Vec<Lab> = swatches
+				.iter()
+				.map(|s| {
+					Lab::from_linear_srgb(
+						simulation.simulate_linear(decode_srgb8(s.rgb)),
+					)
+				})
+				.collect();
+
+			let mut pairs = Vec::new();
+			for i in 0..swatches.len() {
+				for j in i + 1..swatches.len() {
+					let delta_e = simulated[i].delta_e_2000(&simulated[j]);
+					if delta_e < threshold {
+						pairs.push(Pair {
+							first: SwatchReport::from(&swatches[i]),
+							second: SwatchReport::from(&swatches[j]),
+							delta_e,
+						});
+					}
+				}
+			}
+			pairs.sort_by(|a, b| a.delta_e.total_cmp(&b.delta_e));
+			VisionReport { vision, pairs }
+		})
+		.collect();
+
+	Report {
+		threshold,
+		passed: visions.iter().all(|v| v.pairs.is_empty()),
+		visions,
+	}
+}
+
+fn write_table(w: &mut impl Write, report: &Report) -> io::Result<()> {
+	let label = |s: &SwatchReport| {
+		if s.name == s.color {
+			s.color.clone()
+		} else {
+			format!("{} ({})", s.name, s.color)
+		}
+	};
+
+	let name_width = report
+		.visions
+		.iter()
+		.map(|v| v.vision.name().len())
+		.max()
+		.unwrap_or(0);
+	let pair_width = report
+		.visions
+		.iter()
+		.flat_map(|v| &v.pairs)
+		.map(|p| label(&p.first).len())
+		.max()
+		.unwrap_or(0);
+
+	for vision in &report.visions {
+		if vision.pairs.is_empty() {
+			writeln!(w, "{:<name_width$}  ok", vision.vision.name())?;
+			continue;
+		}
+		for (i, pair) in vision.pairs.iter().enumerate() {
+			let name = if i == 0 { vision.vision.name() } else { "" };
+			writeln!(
+				w,
+				"{:<name_width$}  {:<pair_width$}  ~  {}  ΔE {:.2}",
+				name,
+				label(&pair.first),
+				label(&pair.second),
+				pair.delta_e,
+			)?;
+		}
+	}
+
+	let confusable: usize = report.visions.iter().map(|v| v.pairs.len()).sum();
+	if confusable > 0 {
+		writeln!(
+			w,
+			"\n{} confusable pair(s) below ΔE {:.1}",
+			confusable, report.threshold
+		)?;
+	}
+	Ok(())
+}
+
+/// Reads a palette from a file, with the format chosen by its extension
+fn read_file(path: &Path) -> Result<Vec<Swatch>, Box<dyn Error>> {
+	let contents = std::fs::read_to_string(path)
+		.map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+	let extension = path
+		.extension()
+		.map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+
+	match extension.as_deref() {
+		Some("json") => parse_json(&contents),
+		Some("csv") => parse_csv(&contents),
+		Some("css") => Ok(parse_css(&contents)),
+		_ => Err(format!(
+			"unknown palette format for {}, expected .json, .csv or .css",
+			path.display()
+		)
+		.into()),
+	}
+}
+
+/// Parses a JSON palette, as either an array of colors, an array of
+/// `{ "name": …, "color": … }` objects, or an object of names to colors
+fn parse_json(contents: &str) -> Result<Vec<Swatch>, Box<dyn Error>> {
+	use serde_json::Value;
+
+	let swatch = |name: Option<&str>, value: &Value| -> Result<Swatch, String> {
+		let color = value
+			.as_str()
+			.ok_or_else(|| format!("expected a color string, found {}", value))?;
+		let rgb = parse_color(color).ok_or_else(|| format!("invalid color `{}`", color))?;
+		let name = name.map_or_else(|| hex(rgb), str::to_owned);
+		Ok(Swatch { name, rgb })
+	};
+
+	match serde_json::from_str(contents)? {
+		Value::Array(items) => items
+			.iter()
+			.map(|item| match item {
+				Value::Object(object) => {
+					let name = object.get("name").and_then(Value::as_str);
+					let color = object.get("color").ok_or(
+						"expected a `color` property in palette entry",
+					)?;
+					Ok(swatch(name, color)?)
+				}
+				value => Ok(swatch(None, value)?),
+			})
+			.collect(),
+		Value::Object(object) => object
+			.iter()
+			.map(|(name, value)| Ok(swatch(Some(name), value)?))
+			.collect(),
+		_ => Err("expected a JSON array or object of colors".into()),
+	}
+}
+
+/// Parses a CSV palette with rows of either `color` or `name,color`,
+/// skipping a header row when it doesn't contain a color
+fn parse_csv(contents: &str) -> Result<Vec<Swatch>, Box<dyn Error>> {
+	let mut swatches = Vec::new();
+	for (i, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() {
+			continue;
+		}
+		let fields: Vec<&str> = line
+			.split(',')
+			.map(|f| f.trim().trim_matches('"'))
+			.collect();
+		let (name, color) = match fields[..] {
+			[color] => (None, color),
+			[name, color, ..] => (Some(name), color),
+			[] => continue,
+		};
+		match parse_color(color) {
+			Some(rgb) => swatches.push(Swatch {
+				name: name.map_or_else(|| hex(rgb), str::to_owned),
+				rgb,
+			}),
+			None if i == 0 => continue,
+			None => {
+				return Err(format!("invalid color `{}` on line {}", color, i + 1)
+					.into())
+			}
+		}
+	}
+	Ok(swatches)
+}
+
+/// Collects custom properties with color values, such as `--primary: #0055ff;`,
+/// in the order they're first declared, ignoring those with any other value
+fn parse_css(contents: &str) -> Vec<Swatch> {
+	let sheet = Stylesheet::parse(contents);
+	let properties = sheet.custom_properties();
+	let mut swatches: Vec<Swatch> = Vec::new();
+	for decl in sheet.rules.iter().flat_map(|rule| &rule.declarations) {
+		let name = decl.property.as_str();
+		if swatches.iter().any(|swatch| swatch.name == name) {
+			continue;
+		}
+		if let Some(rgb) = properties.get(name).and_then(|value| parse_color(value)) {
+			swatches.push(Swatch {
+				name: name.to_owned(),
+				rgb,
+			});
+		}
+	}
+	swatches
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn palette(colors: &[&str]) -> Vec<Swatch> {
+		colors.iter().map(|c| Swatch::parse(c).unwrap()).collect()
+	}
+
+	#[test]
+	fn test_check_red_green_confusion() {
+		let swatches = palette(&["red=#d62728", "green=#2ca02c", "blue=#1f77b4"]);
+		let visions = [ColorVision::Normal, ColorVision::Deuteranopia];
+		let report = check(&swatches, &visions, SimulationOptions::default(), 10.0);
+
+		assert!(!report.passed);
+		assert!(report.visions[0].pairs.is_empty());
+		let pairs = &report.visions[1].pairs;
+		assert_eq!(pairs.len(), 1);
+		assert_eq!(pairs[0].first.name, "red");
+		assert_eq!(pairs[0].second.name, "green");
+	}
+
+	#[test]
+	fn test_check_passes_distinct_palette() {
+		let swatches = palette(&["#000000", "#ffffff"]);
+		let report = check(
+			&swatches,
+			&ColorVision::ALL,
+			SimulationOptions::default(),
+			10.0,
+		);
+		assert!(report.passed);
+	}
+
+	#[test]
+	fn test_parse_json() {
+		let array = parse_json(r##"["#ff0000", "blue"]"##).unwrap();
+		assert_eq!(array, palette(&["#ff0000", "#0000ff"]));
+
+		let objects = parse_json(r##"[{"name": "error", "color": "#f00"}]"##).unwrap();
+		assert_eq!(objects, palette(&["error=#ff0000"]));
+
+		let map = parse_json(r##"{"ok": "green"}"##).unwrap();
+		assert_eq!(map, palette(&["ok=green"]));
+
+		assert!(parse_json(r#"["nope"]"#).is_err());
+		assert!(parse_json("42").is_err());
+	}
+
+	#[test]
+	fn test_parse_csv() {
+		let swatches = parse_csv("name,color\nerror,#ff0000\n\nok, \"#00ff00\"\n").unwrap();
+		assert_eq!(swatches, palette(&["error=#ff0000", "ok=#00ff00"]));
+
+		let swatches = parse_csv("#ff0000\n#00ff00").unwrap();
+		assert_eq!(swatches.len(), 2);
+
+		assert!(parse_csv("error,#ff0000\nok,nope").is_err());
+	}
+
+	#[test]
+	fn test_parse_css() {
+		let css = ":root {\n\t--primary: #0055ff;\n\t--spacing: 4px;\n\t--danger:rgb(200, 0, 0)}\n.a { color: var(--primary); }";
+		let swatches = parse_css(css);
+		assert_eq!(
+			swatches,
+			palette(&["--primary=#0055ff", "--danger=rgb(200,0,0)"])
+		);
+
+		// commented out properties are ignored, and later declarations win
+		let css = "/* --old: #ff0000; */\n:root { --primary: #0055ff; }\n.dark { --primary: #000000; }";
+		assert_eq!(parse_css(css), palette(&["--primary=#000000"]));
+	}
+
+	#[test]
+	fn test_write_table() {
+		let swatches = palette(&["red=#d62728", "green=#2ca02c"]);
+		let report = check(
+			&swatches,
+			&[ColorVision::Normal, ColorVision::Deuteranopia],
+			SimulationOptions::default(),
+			10.0,
+		);
+		let mut out = Vec::new();
+		write_table(&mut out, &report).unwrap();
+		let table = String::from_utf8(out).unwrap();
+
+		assert!(table.starts_with(
+			"normal        ok\ndeuteranopia  red (#d62728)  ~  green (#2ca02c)"
+		));
+		assert!(table.ends_with("1 confusable pair(s) below ΔE 10.0\n"));
+	}
+}
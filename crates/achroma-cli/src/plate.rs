@@ -0,0 +1,126 @@
+//! The `plate` subcommand, which generates pseudoisochromatic plates
+
+use crate::palette::{serialize_vision, OutputFormat};
+use crate::parse_vision;
+use achroma::plate::{Design, Plate};
+use achroma::ColorVision;
+use clap::{Args, ValueEnum};
+use image::RgbImage;
+use serde::Serialize;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Args)]
+pub struct PlateArgs {
+	/// Type of color vision the plate targets
+	#[arg(short, long, value_parser = parse_vision)]
+	pub vision: ColorVision,
+
+	/// Number shown on the plate, from 0 to 99
+	#[arg(short, long, value_parser = clap::value_parser!(u8).range(0..=99))]
+	pub number: u8,
+
+	/// Seed for placing the dots, where the same seed produces the same plate
+	#[arg(long, default_value_t = 0)]
+	pub seed: u64,
+
+	/// Design of the plate
+	#[arg(short, long, value_enum, default_value_t)]
+	pub design: DesignArg,
+
+	/// Width and height of the plate in pixels
+	#[arg(long, default_value_t = 512)]
+	pub size: u32,
+
+	/// Number of dots across the plate
+	#[arg(long, default_value_t = 48, value_parser = clap::value_parser!(u16).range(8..))]
+	pub density: u16,
+
+	/// Output file, as SVG or an image chosen by its extension (PNG, JPEG or PPM)
+	#[arg(short, long)]
+	pub output: PathBuf,
+
+	/// Format of the expected answers
+	#[arg(long, value_enum, default_value_t)]
+	pub format: OutputFormat,
+}
+
+/// The design of a plate, as accepted on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DesignArg {
+	/// The number vanishes for the targeted color vision
+	#[default]
+	Vanishing,
+	/// The number is visible to everyone
+	Demonstration,
+}
+
+impl From<DesignArg> for Design {
+	fn from(arg: DesignArg) -> Self {
+		match arg {
+			DesignArg::Vanishing => Design::Vanishing,
+			DesignArg::Demonstration => Design::Demonstration,
+		}
+	}
+}
+
+/// The expected answers of a generated plate
+#[derive(Debug, Serialize)]
+pub struct PlateReport {
+	pub output: PathBuf,
+	#[serde(serialize_with = "serialize_vision")]
+	pub vision: ColorVision,
+	pub seed: u64,
+	pub normal: Option<u8>,
+	pub deficient: Option<u8>,
+}
+
+/// Runs the `plate` subcommand
+pub fn run(args: &PlateArgs) -> Result<(), Box<dyn Error>> {
+	let plate = Plate::new(args.vision, args.number, args.seed)
+		.ok_or("normal color vision has no confusion lines to hide a number along")?
+		.with_design(args.design.into())
+		.with_density(args.density);
+	save(&plate, args.size, &args.output)?;
+
+	let answers = plate.answers();
+	let report = PlateReport {
+		output: args.output.clone(),
+		vision: args.vision,
+		seed: args.seed,
+		normal: answers.normal,
+		deficient: answers.deficient,
+	};
+
+	let mut stdout = io::stdout().lock();
+	match args.format {
+		OutputFormat::Table => {
+			let answer = |n: Option<u8>| {
+				n.map_or_else(|| String::from("nothing"), |n| n.to_string())
+			};
+			writeln!(stdout, "wrote {}", report.output.display())?;
+			writeln!(stdout, "normal: {}", answer(report.normal))?;
+			writeln!(stdout, "{}: {}", report.vision, answer(report.deficient))?;
+		}
+		OutputFormat::Json => {
+			serde_json::to_writer_pretty(&mut stdout, &report)?;
+			writeln!(stdout)?;
+		}
+	}
+	Ok(())
+}
+
+fn save(plate: &Plate, size: u32, path: &Path) -> Result<(), String> {
+	let is_svg = path
+		.extension()
+		.is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+	let result = if is_svg {
+		std::fs::write(path, plate.svg(size).to_string()).map_err(|err| err.to_string())
+	} else {
+		let mut image = RgbImage::new(size, size);
+		plate.render_rgb8(&mut image, size as usize);
+		image.save(path).map_err(|err| err.to_string())
+	};
+	result.map_err(|err| format!("failed to write {}: {}", path.display(), err))
+}
@@ -0,0 +1,301 @@
+//! The `simulate` subcommand, which simulates or daltonizes images
+
+use crate::font;
+use crate::{parse_luminance, parse_species, parse_vision, SimulationOptions};
+use achroma::mesopic::{Adaptation, MesopicSimulation};
+use achroma::simulation::{encode_srgb8, srgb_to_linear, Simulation};
+use achroma::species::{Species, SpeciesSimulation};
+use achroma::ColorVision;
+use clap::Args;
+use image::{Rgba, RgbaImage};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Number of tiles per row when writing a grid with `--all`
+const GRID_COLUMNS: u32 = 3;
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+	/// Input image (PNG, JPEG or PPM)
+	pub input: PathBuf,
+
+	/// Types of color vision to simulate, separated by commas
+	#[arg(
+		short,
+		long = "vision",
+		value_name = "VISION",
+		value_delimiter = ',',
+		value_parser = parse_vision,
+		required_unless_present_any = ["all", "species"],
+		conflicts_with = "all",
+	)]
+	pub visions: Vec<ColorVision>,
+
+	/// Animals to view the image as in false color, separated by commas
+	#[arg(
+		long,
+		value_delimiter = ',',
+		value_parser = parse_species,
+		conflicts_with_all = ["all", "visions", "daltonize"],
+	)]
+	pub species: Vec<Species>,
+
+	/// Simulate low light, adapted to a luminance in cd/m² such as 0.1 for a
+	/// dashboard at night
+	#[arg(
+		long,
+		value_name = "CD_M2",
+		value_parser = parse_luminance,
+		conflicts_with_all = ["species", "daltonize"],
+	)]
+	pub luminance: Option<f32>,
+
+	/// Write a single labeled grid of every type of color vision
+	#[arg(long)]
+	pub all: bool,
+
+	#[command(flatten)]
+	pub options: SimulationOptions,
+
+	/// Correct the image for the color vision instead of simulating it
+	#[arg(short, long)]
+	pub daltonize: bool,
+
+	/// Output file, or a directory when writing several images
+	#[arg(short, long)]
+	pub output: Option<PathBuf>,
+}
+
+impl SimulateArgs {
+	/// The suffix added to the input's file stem for a given output
+	fn suffix(&self, name: &str) -> String {
+		if self.daltonize {
+			format!("{}-daltonized", name)
+		} else {
+			name.to_owned()
+		}
+	}
+
+	/// Simulates or daltonizes an image for a type of color vision
+	fn render(&self, image: &RgbaImage, vision: ColorVision) -> RgbaImage {
+		let simulation = self.options.simulation(vision);
+		match self.luminance {
+			Some(luminance) => {
+				let mesopic = MesopicSimulation::new(
+					simulation,
+					&Adaptation::display(luminance),
+				);
+				map_pixels(image, |rgb| mesopic.simulate_linear(rgb))
+			}
+			None => apply(image, simulation, self.daltonize),
+		}
+	}
+}
+
+/// Runs the `simulate` subcommand
+pub fn run(args: &SimulateArgs) -> Result<(), Box<dyn Error>> {
+	let image = image::open(&args.input)
+		.map_err(|err| format!("failed to read {}: {}", args.input.display(), err))?
+		.to_rgba8();
+
+	if args.all {
+		let tiles: Vec<(String, RgbaImage)> = ColorVision::ALL
+			.iter()
+			.map(|&vision| {
+				let simulation = args.options.simulation(vision);
+				(label(simulation), args.render(&image, vision))
+			})
+			.collect();
+		let output =
+			output_path(&args.input, args.output.as_deref(), &args.suffix("all"), 1)?;
+		save(&grid(&tiles), &output)?;
+		return Ok(());
+	}
+
+	for &species in &args.species {
+		let output = output_path(
+			&args.input,
+			args.output.as_deref(),
+			species.name(),
+			args.species.len(),
+		)?;
+		let simulation = SpeciesSimulation::from(species);
+		save(
+			&map_pixels(&image, |rgb| simulation.simulate_linear(rgb)),
+			&output,
+		)?;
+	}
+
+	for &vision in &args.visions {
+		let output = output_path(
+			&args.input,
+			args.output.as_deref(),
+			&args.suffix(vision.name()),
+			args.visions.len(),
+		)?;
+		save(&args.render(&image, vision), &output)?;
+	}
+	Ok(())
+}
+
+/// Simulates or daltonizes every pixel of an image, preserving alpha
+pub fn apply(image: &RgbaImage, simulation: Simulation, daltonize: bool) -> RgbaImage {
+	map_pixels(image, |linear| {
+		if daltonize {
+			simulation.daltonize_linear(linear)
+		} else {
+			simulation.simulate_linear(linear)
+		}
+	})
+}
+
+/// Maps the linear RGB of every pixel of an image, preserving alpha
+fn map_pixels(image: &RgbaImage, map: impl Fn([f32; 3]) -> [f32; 3]) -> RgbaImage {
+	let decode: [f32; 256] = std::array::from_fn(|c| srgb_to_linear(c as f32 / 255.0));
+	let mut output = image.clone();
+	for pixel in output.pixels_mut() {
+		let [r, g, b, a] = pixel.0;
+		let linear = [decode[r as usize], decode[g as usize], decode[b as usize]];
+		let [r, g, b] = encode_srgb8(map(linear));
+		*pixel = Rgba([r, g, b, a]);
+	}
+	output
+}
+
+/// The label of a tile in a grid
+fn label(simulation: Simulation) -> String {
+	let vision = simulation.vision();
+	let severity = simulation.severity();
+	if vision == ColorVision::Normal || severity >= 1.0 {
+		vision.name().to_ascii_uppercase()
+	} else {
+		format!(
+			"{} {:.0}%",
+			vision.name().to_ascii_uppercase(),
+			severity * 100.0
+		)
+	}
+}
+
+/// Lays out equally-sized images in a grid, each with a label above it
+pub fn grid(tiles: &[(String, RgbaImage)]) -> RgbaImage {
+	let Some((_, first)) = tiles.first() else {
+		return RgbaImage::new(0, 0);
+	};
+	let (width, height) = first.dimensions();
+	let scale = (width / 160).max(1);
+	let label_height = (font::GLYPH_HEIGHT + 4) * scale;
+	let columns = GRID_COLUMNS.min(tiles.len() as u32);
+	let rows = (tiles.len() as u32).div_ceil(columns);
+
+	let white = Rgba([255, 255, 255, 255]);
+	let black = Rgba([0, 0, 0, 255]);
+	let mut canvas =
+		RgbaImage::from_pixel(columns * width, rows * (height + label_height), white);
+
+	for (i, (text, tile)) in tiles.iter().enumerate() {
+		let x = (i as u32 % columns) * width;
+		let y = (i as u32 / columns) * (height + label_height);
+		let text_x = x + width.saturating_sub(font::text_width(text, scale)) / 2;
+		font::draw_text(&mut canvas, text, text_x, y + 2 * scale, scale, black);
+		image::imageops::replace(&mut canvas, tile, x as i64, (y + label_height) as i64);
+	}
+	canvas
+}
+
+/// Decides where to write an output image. Without an explicit output,
+/// images are written next to the input as `<stem>.<suffix>.<ext>`.
+fn output_path(
+	input: &Path,
+	output: Option<&Path>,
+	suffix: &str,
+	count: usize,
+) -> Result<PathBuf, String> {
+	let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+	let extension = input
+		.extension()
+		.map(|ext| ext.to_string_lossy())
+		.unwrap_or_else(|| "png".into());
+	let file_name = format!("{}.{}.{}", stem, suffix, extension);
+
+	match output {
+		Some(dir) if dir.is_dir() => Ok(dir.join(file_name)),
+		Some(_) if count > 1 => Err(String::from(
+			"--output must be a directory when writing several images",
+		)),
+		Some(file) => Ok(file.to_path_buf()),
+		None => Ok(input.with_file_name(file_name)),
+	}
+}
+
+fn save(image: &RgbaImage, path: &Path) -> Result<(), String> {
+	// formats such as JPEG and PPM have no alpha channel
+	let result = match image::ImageFormat::from_path(path) {
+		Ok(image::ImageFormat::Png) => image.save(path),
+		_ => image::DynamicImage::ImageRgba8(image.clone())
+			.to_rgb8()
+			.save(path),
+	};
+	result.map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+	println!("wrote {}", path.display());
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_output_path() {
+		let input = Path::new("shots/home.png");
+		assert_eq!(
+			output_path(input, None, "protanopia", 2),
+			Ok(PathBuf::from("shots/home.protanopia.png"))
+		);
+		assert_eq!(
+			output_path(input, Some(Path::new("out.jpg")), "protanopia", 1),
+			Ok(PathBuf::from("out.jpg"))
+		);
+		assert!(output_path(input, Some(Path::new("out.jpg")), "protanopia", 2).is_err());
+	}
+
+	#[test]
+	fn test_apply_preserves_alpha_and_gray() {
+		let image = RgbaImage::from_pixel(2, 2, Rgba([90, 90, 90, 128]));
+		let simulated = apply(&image, Simulation::from(ColorVision::Deuteranopia), false);
+		let Rgba([r, g, b, a]) = *simulated.get_pixel(1, 1);
+		assert_eq!(a, 128);
+		assert!(r.abs_diff(90) <= 1 && g.abs_diff(90) <= 1 && b.abs_diff(90) <= 1);
+	}
+
+	#[test]
+	fn test_species_preserves_white() {
+		let image = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
+		let simulation = SpeciesSimulation::from(Species::Honeybee);
+		let mapped = map_pixels(&image, |rgb| simulation.simulate_linear(rgb));
+		assert_eq!(*mapped.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+	}
+
+	#[test]
+	fn test_grid_dimensions() {
+		let tiles: Vec<(String, RgbaImage)> = ColorVision::ALL
+			.iter()
+			.map(|v| (v.name().to_owned(), RgbaImage::new(20, 10)))
+			.collect();
+		let canvas = grid(&tiles);
+		assert_eq!(canvas.width(), 60);
+		assert_eq!(canvas.height(), 3 * (10 + font::GLYPH_HEIGHT + 4));
+	}
+
+	#[test]
+	fn test_label() {
+		assert_eq!(
+			label(Simulation::from(ColorVision::Protanopia)),
+			"PROTANOPIA"
+		);
+		assert_eq!(
+			label(Simulation::new(ColorVision::Deuteranomaly, 0.6)),
+			"DEUTERANOMALY 60%"
+		);
+	}
+}
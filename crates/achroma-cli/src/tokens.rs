@@ -0,0 +1,526 @@
+//! The `tokens` subcommand, which audits pairs of color tokens from a
+//! [W3C Design Tokens][dtcg] file for contrast with each type of color vision
+//!
+//! Pairs are declared with `--pair` or in the `$extensions` of a text token:
+//!
+//! ```json
+//! "on-primary": {
+//!   "$value": "#ffffff",
+//!   "$extensions": { "achroma": { "background": "{color.primary}" } }
+//! }
+//! ```
+//!
+//! [dtcg]: <https://tr.designtokens.org/format/>
+
+use crate::color::{hex, parse_color};
+use crate::palette::{serialize_vision, OutputFormat};
+use crate::{parse_vision, SimulationOptions};
+use achroma::contrast::{apca_contrast_srgb8, contrast_ratio_linear, APCA_CONTENT_TEXT, WCAG_AA};
+use achroma::difference::Lab;
+use achroma::simulation::{decode_srgb8, encode_srgb8};
+use achroma::ColorVision;
+use clap::Args;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// The maximum depth of aliases which are resolved
+const MAX_ALIAS_DEPTH: usize = 32;
+
+/// The key of this tool's data within `$extensions`
+const EXTENSION_KEY: &str = "achroma";
+
+#[derive(Debug, Args)]
+pub struct TokensArgs {
+	/// Design tokens file in the W3C Design Tokens Community Group format
+	pub file: PathBuf,
+
+	/// A pair of text and background tokens to check, such as
+	/// `color.on-primary:color.primary` (`/` also separates groups)
+	#[arg(short, long = "pair", value_name = "TEXT:BACKGROUND")]
+	pub pairs: Vec<String>,
+
+	/// Types of color vision to check, separated by commas [default: all]
+	#[arg(
+		short,
+		long = "vision",
+		value_name = "VISION",
+		value_delimiter = ',',
+		value_parser = parse_vision,
+	)]
+	pub visions: Vec<ColorVision>,
+
+	#[command(flatten)]
+	pub options: SimulationOptions,
+
+	/// Minimum WCAG contrast ratio between text and its background
+	#[arg(short = 'c', long, default_value_t = WCAG_AA)]
+	pub min_contrast: f32,
+
+	/// Minimum absolute APCA lightness contrast (Lc) between text and its background
+	#[arg(long, default_value_t = APCA_CONTENT_TEXT)]
+	pub min_apca: f32,
+
+	/// Minimum CIEDE2000 difference for text to be distinguishable from its background
+	#[arg(short, long, default_value_t = 10.0)]
+	pub threshold: f32,
+
+	/// Format of the report
+	#[arg(long, value_enum, default_value_t)]
+	pub format: OutputFormat,
+}
+
+/// A single design token, with its type inherited from its groups
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+	pub ty: Option<String>,
+	pub value: Value,
+	pub extensions: Option<Value>,
+}
+
+/// All tokens of a design tokens file, by their dot-separated path
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tokens {
+	pub tokens: BTreeMap<String, Token>,
+}
+
+impl Tokens {
+	/// Parses a design tokens file
+	pub fn parse(contents: &str) -> Result<Self, Box<dyn Error>> {
+		let root: Value = serde_json::from_str(contents)?;
+		let root = root
+			.as_object()
+			.ok_or("expected a JSON object of design tokens")?;
+		let mut tokens = Self::default();
+		tokens.collect(root, &mut Vec::new(), None);
+		Ok(tokens)
+	}
+
+	fn collect<'a>(
+		&mut self,
+		group: &'a Map<String, Value>,
+		path: &mut Vec<&'a str>,
+		inherited: Option<&'a str>,
+	) {
+		let ty = group.get("$type").and_then(Value::as_str).or(inherited);
+		for (name, child) in group {
+			if name.starts_with('$') {
+				continue;
+			}
+			let Some(child) = child.as_object() else {
+				continue;
+			};
+			path.push(name);
+			if let Some(value) = child.get("$value") {
+				let token_ty = child.get("$type").and_then(Value::as_str).or(ty);
+				self.tokens.insert(
+					path.join("."),
+					Token {
+						ty: token_ty.map(str::to_owned),
+						value: value.clone(),
+						extensions: child.get("$extensions").cloned(),
+					},
+				);
+			} else {
+				self.collect(child, path, ty);
+			}
+			path.pop();
+		}
+	}
+
+	/// Resolves the color of a token, following aliases such as `{color.primary}`
+	pub fn resolve_color(&self, path: &str) -> Result<[u8; 3], String> {
+		let mut path = normalize_path(path);
+		for _ in 0..MAX_ALIAS_DEPTH {
+			let token = self
+				.tokens
+				.get(&path)
+				.ok_or_else(|| format!("unknown token `{}`", path))?;
+			if let Some(ty) = &token.ty {
+				if ty != "color" {
+					return Err(format!(
+						"token `{}` is a {}, not a color",
+						path, ty
+					));
+				}
+			}
+			match token.value.as_str().and_then(alias) {
+				Some(target) => path = target.to_owned(),
+				None => {
+					return parse_token_color(&token.value).ok_or_else(|| {
+						format!("token `{}` has an invalid color", path)
+					})
+				}
+			}
+		}
+		Err(format!(
+			"alias of `{}` is too deeply nested or circular",
+			path
+		))
+	}
+
+	/// Pairs of text and background tokens declared in `$extensions`
+	pub fn declared_pairs(&self) -> Vec<(String, String)> {
+		let mut pairs = Vec::new();
+		for (path, token) in &self.tokens {
+			let Some(backgrounds) = token
+				.extensions
+				.as_ref()
+				.and_then(|ext| ext.get(EXTENSION_KEY))
+				.and_then(|ext| ext.get("background"))
+			else {
+				continue;
+			};
+			let backgrounds = match backgrounds {
+				Value::Array(items) => items.iter().collect(),
+				value => vec![value],
+			};
+			for background in backgrounds.into_iter().filter_map(Value::as_str) {
+				let background = alias(background).unwrap_or(background);
+				pairs.push((path.clone(), normalize_path(background)));
+			}
+		}
+		pairs
+	}
+}
+
+/// The target of an alias value, such as `color.primary` for `{color.primary}`
+fn alias(value: &str) -> Option<&str> {
+	value.trim()
+		.strip_prefix('{')?
+		.strip_suffix('}')
+		.map(str::trim)
+}
+
+/// Accepts `/` as a group separator, as used by some design tools
+fn normalize_path(path: &str) -> String {
+	path.trim().replace('/', ".")
+}
+
+/// Parses a color value, either as a string or as an object with
+/// `colorSpace` and `components` (or a `hex` fallback)
+fn parse_token_color(value: &Value) -> Option<[u8; 3]> {
+	match value {
+		Value::String(s) => parse_color(s),
+		Value::Object(object) => {
+			let srgb = object.get("colorSpace").and_then(Value::as_str) == Some("srgb");
+			let components = object.get("components").and_then(Value::as_array);
+			match components {
+				Some(components) if srgb && components.len() == 3 => {
+					let mut rgb = [0; 3];
+					for (c, component) in rgb.iter_mut().zip(components) {
+						let component = component.as_f64()?;
+						*c = (component.clamp(0.0, 1.0) * 255.0).round()
+							as u8;
+					}
+					Some(rgb)
+				}
+				_ => parse_color(object.get("hex")?.as_str()?),
+			}
+		}
+		_ => None,
+	}
+}
+
+/// Limits that a pair of tokens is checked against
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Limits {
+	pub min_contrast: f32,
+	pub min_apca: f32,
+	pub threshold: f32,
+}
+
+/// Audit of every pair of tokens
+#[derive(Debug, Serialize)]
+pub struct Report {
+	pub limits: Limits,
+	pub passed: bool,
+	pub pairs: Vec<PairReport>,
+}
+
+/// Audit of a pair of text and background tokens
+#[derive(Debug, Serialize)]
+pub struct PairReport {
+	pub text: TokenReport,
+	pub background: TokenReport,
+	pub passed: bool,
+	pub visions: Vec<VisionReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenReport {
+	pub token: String,
+	pub color: String,
+}
+
+/// Contrast of a pair of tokens as seen with a type of color vision
+#[derive(Debug, Serialize)]
+pub struct VisionReport {
+	#[serde(serialize_with = "serialize_vision")]
+	pub vision: ColorVision,
+	pub wcag: f32,
+	pub apca: f32,
+	pub delta_e: f32,
+	pub passed: bool,
+}
+
+/// Runs the `tokens` subcommand, failing when any pair fails the audit
+pub fn run(args: &TokensArgs) -> Result<ExitCode, Box<dyn Error>> {
+	let contents = std::fs::read_to_string(&args.file)
+		.map_err(|err| format!("failed to read {}: {}", args.file.display(), err))?;
+	let tokens = Tokens::parse(&contents)?;
+
+	let mut pairs = Vec::new();
+	for pair in &args.pairs {
+		let (text, background) = pair.split_once(':').ok_or_else(|| {
+			format!("expected a pair as `TEXT:BACKGROUND`, found `{}`", pair)
+		})?;
+		pairs.push((normalize_path(text), normalize_path(background)));
+	}
+	pairs.extend(tokens.declared_pairs());
+	if pairs.is_empty() {
+		return Err("no pairs of tokens to check, declare them with `--pair` or in `$extensions`".into());
+	}
+
+	let visions = if args.visions.is_empty() {
+		&ColorVision::ALL[..]
+	} else {
+		&args.visions[..]
+	};
+	let limits = Limits {
+		min_contrast: args.min_contrast,
+		min_apca: args.min_apca,
+		threshold: args.threshold,
+	};
+	let report = audit(&tokens, &pairs, visions, args.options, limits)?;
+
+	let mut stdout = io::stdout().lock();
+	match args.format {
+		OutputFormat::Table => write_table(&mut stdout, &report)?,
+		OutputFormat::Json => {
+			serde_json::to_writer_pretty(&mut stdout, &report)?;
+			writeln!(stdout)?;
+		}
+	}
+
+	Ok(if report.passed {
+		ExitCode::SUCCESS
+	} else {
+		ExitCode::FAILURE
+	})
+}
+
+/// Checks the contrast of each pair of text and background tokens as seen
+/// with each type of color vision
+pub fn audit(
+	tokens: &Tokens,
+	pairs: &[(String, String)],
+	visions: &[ColorVision],
+	options: SimulationOptions,
+	limits: Limits,
+) -> Result<Report, String> {
+	let mut reports = Vec::new();
+	for (text_path, background_path) in pairs {
+		let text = tokens.resolve_color(text_path)?;
+		let background = tokens.resolve_color(background_path)?;
+
+		let visions: Vec<VisionReport> = visions
+			.iter()
+			.map(|&vision| {
+				let simulation = options.simulation(vision);
+				let fg = simulation.simulate_linear(decode_srgb8(text));
+				let bg = simulation.simulate_linear(decode_srgb8(background));
+
+				let wcag = contrast_ratio_linear(fg, bg);
+				let apca = apca_contrast_srgb8(encode_srgb8(fg), encode_srgb8(bg));
+				let delta_e = Lab::from_linear_srgb(fg)
+					.delta_e_2000(&Lab::from_linear_srgb(bg));
+				VisionReport {
+					vision,
+					wcag,
+					apca,
+					delta_e,
+					passed: wcag >= limits.min_contrast
+						&& apca.abs() >= limits.min_apca && delta_e
+						>= limits.threshold,
+				}
+			})
+			.collect();
+
+		reports.push(PairReport {
+			text: TokenReport {
+				token: text_path.clone(),
+				color: hex(text),
+			},
+			background: TokenReport {
+				token: background_path.clone(),
+				color: hex(background),
+			},
+			passed: visions.iter().all(|v| v.passed),
+			visions,
+		});
+	}
+
+	Ok(Report {
+		limits,
+		passed: reports.iter().all(|p| p.passed),
+		pairs: reports,
+	})
+}
+
+fn write_table(w: &mut impl Write, report: &Report) -> io::Result<()> {
+	let name_width = report
+		.pairs
+		.iter()
+		.flat_map(|p| &p.visions)
+		.map(|v| v.vision.name().len())
+		.max()
+		.unwrap_or(0);
+
+	for (i, pair) in report.pairs.iter().enumerate() {
+		if i > 0 {
+			writeln!(w)?;
+		}
+		writeln!(
+			w,
+			"{} ({}) on {} ({})",
+			pair.text.token,
+			pair.text.color,
+			pair.background.token,
+			pair.background.color
+		)?;
+		for vision in &pair.visions {
+			writeln!(
+				w,
+				"  {:<name_width$}  {:>5.2}:1  Lc {:>6.1}  ΔE {:>6.2}  {}",
+				vision.vision.name(),
+				vision.wcag,
+				vision.apca,
+				vision.delta_e,
+				if vision.passed { "ok" } else { "FAIL" },
+			)?;
+		}
+	}
+
+	let failed = report.pairs.iter().filter(|p| !p.passed).count();
+	if failed > 0 {
+		writeln!(
+			w,
+			"\n{} of {} pair(s) failed (minimum {:.1}:1, Lc {:.0}, ΔE {:.1})",
+			failed,
+			report.pairs.len(),
+			report.limits.min_contrast,
+			report.limits.min_apca,
+			report.limits.threshold
+		)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TOKENS: &str = r##"{
+		"color": {
+			"$type": "color",
+			"primary": { "$value": "#0055ff" },
+			"brand": { "$value": "{color.primary}" },
+			"danger": {
+				"$value": { "colorSpace": "srgb", "components": [0.84, 0.153, 0.157] }
+			},
+			"success": { "$value": "#2ca02c" },
+			"text": {
+				"on-primary": {
+					"$value": "#ffffff",
+					"$extensions": { "achroma": { "background": "{color.brand}" } }
+				},
+				"on-success": {
+					"$value": "{color.danger}",
+					"$extensions": { "achroma": { "background": ["color/success"] } }
+				}
+			}
+		},
+		"size": {
+			"small": { "$type": "dimension", "$value": "4px" }
+		}
+	}"##;
+
+	const LIMITS: Limits = Limits {
+		min_contrast: WCAG_AA,
+		min_apca: APCA_CONTENT_TEXT,
+		threshold: 10.0,
+	};
+
+	#[test]
+	fn test_parse_tokens() {
+		let tokens = Tokens::parse(TOKENS).unwrap();
+		assert_eq!(tokens.tokens.len(), 7);
+		assert_eq!(tokens.tokens["color.primary"].ty.as_deref(), Some("color"));
+		assert_eq!(tokens.tokens["size.small"].ty.as_deref(), Some("dimension"));
+	}
+
+	#[test]
+	fn test_resolve_color() {
+		let tokens = Tokens::parse(TOKENS).unwrap();
+		assert_eq!(tokens.resolve_color("color.brand"), Ok([0x00, 0x55, 0xff]));
+		assert_eq!(tokens.resolve_color("color/danger"), Ok([214, 39, 40]));
+		assert!(tokens.resolve_color("size.small").is_err());
+		assert!(tokens.resolve_color("color.missing").is_err());
+	}
+
+	#[test]
+	fn test_resolve_circular_alias() {
+		let tokens =
+			Tokens::parse(r#"{ "a": { "$value": "{b}" }, "b": { "$value": "{a}" } }"#)
+				.unwrap();
+		assert!(tokens.resolve_color("a").unwrap_err().contains("circular"));
+	}
+
+	#[test]
+	fn test_declared_pairs() {
+		let tokens = Tokens::parse(TOKENS).unwrap();
+		assert_eq!(
+			tokens.declared_pairs(),
+			vec![
+				(
+					String::from("color.text.on-primary"),
+					String::from("color.brand")
+				),
+				(
+					String::from("color.text.on-success"),
+					String::from("color.success")
+				),
+			]
+		);
+	}
+
+	#[test]
+	fn test_audit() {
+		let tokens = Tokens::parse(TOKENS).unwrap();
+		let report = audit(
+			&tokens,
+			&tokens.declared_pairs(),
+			&[ColorVision::Normal, ColorVision::Deuteranopia],
+			SimulationOptions::default(),
+			LIMITS,
+		)
+		.unwrap();
+
+		assert!(!report.passed);
+		assert_eq!(report.pairs[0].text.color, "#ffffff");
+		assert_eq!(report.pairs[0].background.color, "#0055ff");
+		assert!(report.pairs[0].visions.iter().all(|v| v.apca < 0.0));
+
+		// red on green fails for everyone, but is only indistinguishable
+		// once simulated
+		let danger = &report.pairs[1];
+		assert!(!danger.passed);
+		assert!(danger.visions[0].delta_e > LIMITS.threshold);
+		assert!(danger.visions[1].delta_e < LIMITS.threshold);
+	}
+}
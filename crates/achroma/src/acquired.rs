@@ -0,0 +1,417 @@
+//! Acquired color vision deficiencies
+//!
+//! Unlike the congenital types of [ColorVision], acquired deficiencies are
+//! caused by disease or medication, may affect one eye more than the other,
+//! and can progress or recover over time. They are grouped by the
+//! classification of Verriest (1963) into two types of red-green defect and
+//! one type of blue-yellow defect.
+//!
+//! Köllner's rule relates the type of defect to the site of the damage:
+//! diseases of the outer retina tend to cause blue-yellow defects, and
+//! diseases of the inner retina and optic nerve tend to cause red-green
+//! defects. The rule has well known exceptions, such as glaucoma, which
+//! usually causes a blue-yellow defect early on.
+//!
+//! ```
+//! use achroma::acquired::{Acquired, Cause, Defect};
+//! use achroma::simulation::Simulation;
+//! use achroma::ConeCell;
+//!
+//! let baseline = Acquired::from_cause(Cause::Ethambutol, 0.2);
+//! assert_eq!(baseline.defect, Defect::RedGreenII);
+//! assert!(baseline.affects(ConeCell::Medium));
+//!
+//! // a follow up visit shows the defect is progressing
+//! let follow_up = Acquired::from_cause(Cause::Ethambutol, 0.5);
+//! assert!(follow_up.change_since(&baseline).unwrap() > 0.0);
+//!
+//! let simulation = Simulation::from(follow_up);
+//! assert_ne!(simulation.simulate_srgb([255, 0, 0]), [255, 0, 0]);
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::simulation::Simulation;
+use crate::{Classification, ColorVision, ConeCell, ConeCellCond, ConeCellSummary};
+
+/// The site of the damage causing an acquired deficiency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Site {
+	/// The photoreceptors and retinal pigment epithelium
+	OuterRetina,
+	/// The ganglion cells of the retina and the optic nerve
+	InnerRetina,
+}
+
+/// A type of acquired deficiency, following the classification of Verriest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Defect {
+	/// Type I red-green defect, resembling protan deficiencies with a loss of
+	/// sensitivity to long wavelengths. Seen in progressive cone dystrophies.
+	RedGreenI,
+	/// Type II red-green defect, resembling deutan deficiencies with a mild
+	/// blue-yellow loss. Seen in optic nerve diseases.
+	RedGreenII,
+	/// Type III blue-yellow defect, resembling tritan deficiencies.
+	/// The most common acquired defect, seen in many retinal diseases.
+	BlueYellowIII,
+}
+
+impl Defect {
+	/// All types of acquired defects
+	pub const ALL: [Self; 3] = [Self::RedGreenI, Self::RedGreenII, Self::BlueYellowIII];
+
+	/// The defect predicted by Köllner's rule for damage at a site
+	///
+	/// ```
+	/// use achroma::acquired::{Defect, Site};
+	///
+	/// assert_eq!(Defect::kollner(Site::OuterRetina), Defect::BlueYellowIII);
+	/// assert_eq!(Defect::kollner(Site::InnerRetina), Defect::RedGreenII);
+	/// ```
+	pub const fn kollner(site: Site) -> Self {
+		match site {
+			Site::OuterRetina => Self::BlueYellowIII,
+			Site::InnerRetina => Self::RedGreenII,
+		}
+	}
+
+	/// Gets the name of the defect, such as `"type I red-green"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::RedGreenI => "type I red-green",
+			Self::RedGreenII => "type II red-green",
+			Self::BlueYellowIII => "type III blue-yellow",
+		}
+	}
+
+	/// Reports if the defect is one of the red-green types
+	pub const fn is_red_green(&self) -> bool {
+		matches!(self, Self::RedGreenI | Self::RedGreenII)
+	}
+
+	/// Reports if the defect is the blue-yellow type
+	pub const fn is_blue_yellow(&self) -> bool {
+		matches!(self, Self::BlueYellowIII)
+	}
+
+	/// The congenital color vision the defect resembles, at the anomalous
+	/// trichromacy of the most affected cone cell
+	///
+	/// ```
+	/// use achroma::acquired::Defect;
+	/// use achroma::ColorVision;
+	///
+	/// assert_eq!(Defect::RedGreenI.resembles(), ColorVision::Protanomaly);
+	/// assert_eq!(Defect::BlueYellowIII.resembles(), ColorVision::Tritanomaly);
+	/// ```
+	pub const fn resembles(&self) -> ColorVision {
+		match self {
+			Self::RedGreenI => ColorVision::Protanomaly,
+			Self::RedGreenII => ColorVision::Deuteranomaly,
+			Self::BlueYellowIII => ColorVision::Tritanomaly,
+		}
+	}
+
+	/// The cone cells affected by the defect, where the most affected cone cell comes first
+	///
+	/// ```
+	/// use achroma::acquired::Defect;
+	/// use achroma::ConeCell;
+	///
+	/// assert_eq!(Defect::RedGreenII.cones(), &[ConeCell::Medium, ConeCell::Short]);
+	/// ```
+	pub const fn cones(&self) -> &'static [ConeCell] {
+		match self {
+			Self::RedGreenI => &[ConeCell::Long, ConeCell::Medium],
+			Self::RedGreenII => &[ConeCell::Medium, ConeCell::Short],
+			Self::BlueYellowIII => &[ConeCell::Short],
+		}
+	}
+
+	/// Reports if a cone cell is affected by the defect
+	pub fn affects(&self, cone: ConeCell) -> bool {
+		self.cones().contains(&cone)
+	}
+
+	/// The summary of cone cells, where each affected cone cell is anomalous
+	///
+	/// ```
+	/// use achroma::acquired::Defect;
+	/// use achroma::ConeCellSummary;
+	///
+	/// let summary = Defect::BlueYellowIII.summary();
+	/// assert_eq!(summary, ConeCellSummary::TRITANOMALY);
+	/// ```
+	pub fn summary(&self) -> ConeCellSummary {
+		let mut summary = ConeCellSummary::NORMAL;
+		for &cone in self.cones() {
+			summary[cone] = ConeCellCond::Anomalous;
+		}
+		summary
+	}
+}
+
+impl fmt::Display for Defect {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Defect {
+	type Err = ();
+	/// Attempt to parse the name of a defect, ignoring ASCII case
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|defect| defect.name().eq_ignore_ascii_case(s.trim()))
+			.ok_or(())
+	}
+}
+
+/// A known cause of an acquired deficiency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cause {
+	/// Ethambutol, an antibiotic for tuberculosis, toxic to the optic nerve
+	Ethambutol,
+	/// Hydroxychloroquine, an antimalarial and antirheumatic, toxic to the outer retina
+	Hydroxychloroquine,
+	/// Diabetic retinopathy
+	DiabeticRetinopathy,
+	/// Glaucoma, an exception to Köllner's rule causing early blue-yellow defects
+	Glaucoma,
+	/// Optic neuritis
+	OpticNeuritis,
+	/// Progressive cone dystrophies, an exception to Köllner's rule causing red-green defects
+	ConeDystrophy,
+	/// Age-related macular degeneration
+	MacularDegeneration,
+}
+
+impl Cause {
+	/// All known causes
+	pub const ALL: [Self; 7] = [
+		Self::Ethambutol,
+		Self::Hydroxychloroquine,
+		Self::DiabeticRetinopathy,
+		Self::Glaucoma,
+		Self::OpticNeuritis,
+		Self::ConeDystrophy,
+		Self::MacularDegeneration,
+	];
+
+	/// Gets the name of the cause, such as `"ethambutol"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Ethambutol => "ethambutol",
+			Self::Hydroxychloroquine => "hydroxychloroquine",
+			Self::DiabeticRetinopathy => "diabetic retinopathy",
+			Self::Glaucoma => "glaucoma",
+			Self::OpticNeuritis => "optic neuritis",
+			Self::ConeDystrophy => "cone dystrophy",
+			Self::MacularDegeneration => "macular degeneration",
+		}
+	}
+
+	/// Reports if the cause is a medication
+	pub const fn is_drug(&self) -> bool {
+		matches!(self, Self::Ethambutol | Self::Hydroxychloroquine)
+	}
+
+	/// Reports if the cause is a disease
+	pub const fn is_disease(&self) -> bool {
+		!self.is_drug()
+	}
+
+	/// The site of the damage
+	pub const fn site(&self) -> Site {
+		match self {
+			Self::Ethambutol | Self::Glaucoma | Self::OpticNeuritis => {
+				Site::InnerRetina
+			}
+			Self::Hydroxychloroquine
+			| Self::DiabeticRetinopathy
+			| Self::ConeDystrophy
+			| Self::MacularDegeneration => Site::OuterRetina,
+		}
+	}
+
+	/// The defect typically reported for the cause, which differs from
+	/// [Köllner's rule][Defect::kollner] for its exceptions
+	///
+	/// ```
+	/// use achroma::acquired::{Cause, Defect};
+	///
+	/// let cause = Cause::Glaucoma;
+	/// assert_eq!(cause.defect(), Defect::BlueYellowIII);
+	/// assert!(!cause.follows_kollner());
+	/// ```
+	pub const fn defect(&self) -> Defect {
+		match self {
+			Self::Ethambutol | Self::OpticNeuritis => Defect::RedGreenII,
+			Self::ConeDystrophy => Defect::RedGreenI,
+			Self::Hydroxychloroquine
+			| Self::DiabeticRetinopathy
+			| Self::Glaucoma
+			| Self::MacularDegeneration => Defect::BlueYellowIII,
+		}
+	}
+
+	/// Reports if the typical defect is the one predicted by Köllner's rule
+	pub fn follows_kollner(&self) -> bool {
+		self.defect() == Defect::kollner(self.site())
+	}
+}
+
+impl fmt::Display for Cause {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Cause {
+	type Err = ();
+	/// Attempt to parse the name of a cause, ignoring ASCII case
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|cause| cause.name().eq_ignore_ascii_case(s.trim()))
+			.ok_or(())
+	}
+}
+
+/// An acquired deficiency, of a type of defect at a severity
+///
+/// The severity ranges from `0.0` (no measurable defect) to `1.0`
+/// (the full loss of the affected cone cells).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Acquired {
+	/// The type of defect
+	pub defect: Defect,
+	/// The cause of the defect, if known
+	pub cause: Option<Cause>,
+	/// The severity between `0.0` and `1.0`
+	pub severity: f32,
+}
+
+impl Acquired {
+	/// Creates a new acquired deficiency of an unknown cause
+	pub const fn new(defect: Defect, severity: f32) -> Self {
+		Self {
+			defect,
+			cause: None,
+			severity,
+		}
+	}
+
+	/// Creates a new acquired deficiency with the defect typical of its cause
+	pub const fn from_cause(cause: Cause, severity: f32) -> Self {
+		Self::new(cause.defect(), severity).with_cause(cause)
+	}
+
+	/// Records the cause of the defect
+	pub const fn with_cause(mut self, cause: Cause) -> Self {
+		self.cause = Some(cause);
+		self
+	}
+
+	/// Reports if a cone cell is affected by the defect
+	pub fn affects(&self, cone: ConeCell) -> bool {
+		self.defect.affects(cone)
+	}
+
+	/// The congenital classification the deficiency resembles
+	///
+	/// ```
+	/// use achroma::acquired::{Acquired, Cause};
+	/// use achroma::ColorVision;
+	///
+	/// let acquired = Acquired::from_cause(Cause::Hydroxychloroquine, 0.3);
+	/// assert_eq!(acquired.classification().vision, ColorVision::Tritanomaly);
+	/// ```
+	pub fn classification(&self) -> Classification {
+		Classification::new(self.defect.resembles(), self.severity)
+	}
+
+	/// The change in severity since an earlier measurement of the same defect,
+	/// where a positive change is a progression and a negative change is a recovery.
+	/// Returns [None] when the defects differ.
+	///
+	/// ```
+	/// use achroma::acquired::{Acquired, Defect};
+	///
+	/// let baseline = Acquired::new(Defect::BlueYellowIII, 0.4);
+	/// let follow_up = Acquired::new(Defect::BlueYellowIII, 0.1);
+	/// assert!(follow_up.change_since(&baseline).unwrap() < 0.0);
+	///
+	/// let other = Acquired::new(Defect::RedGreenII, 0.1);
+	/// assert_eq!(other.change_since(&baseline), None);
+	/// ```
+	pub fn change_since(&self, earlier: &Self) -> Option<f32> {
+		(self.defect == earlier.defect).then_some(self.severity - earlier.severity)
+	}
+
+	/// A simulation of the deficiency, approximated by the congenital
+	/// deficiency it resembles at the same severity
+	pub const fn simulation(&self) -> Simulation {
+		Simulation::new(self.defect.resembles(), self.severity)
+	}
+}
+
+impl From<Acquired> for Simulation {
+	fn from(acquired: Acquired) -> Self {
+		acquired.simulation()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_kollner_exceptions() {
+		for cause in Cause::ALL {
+			let exception = matches!(cause, Cause::Glaucoma | Cause::ConeDystrophy);
+			assert_eq!(cause.follows_kollner(), !exception, "{:?}", cause);
+		}
+	}
+
+	#[test]
+	fn test_names_parse() {
+		for cause in Cause::ALL {
+			assert_eq!(cause.name().parse(), Ok(cause));
+		}
+		for defect in Defect::ALL {
+			assert_eq!(defect.name().parse(), Ok(defect));
+		}
+		assert_eq!(" Type III Blue-Yellow ".parse(), Ok(Defect::BlueYellowIII));
+		assert_eq!("aspirin".parse::<Cause>(), Err(()));
+	}
+
+	#[test]
+	fn test_defect_summary() {
+		for defect in Defect::ALL {
+			let summary = defect.summary();
+			for cone in [ConeCell::Long, ConeCell::Medium, ConeCell::Short] {
+				assert_eq!(summary[cone].is_anomalous(), defect.affects(cone));
+			}
+			let resembles = ConeCellSummary::from(defect.resembles());
+			assert_eq!(resembles[defect.cones()[0]], ConeCellCond::Anomalous);
+		}
+	}
+
+	#[test]
+	fn test_simulation_severity() {
+		let none = Simulation::from(Acquired::new(Defect::RedGreenI, 0.0));
+		assert_eq!(none.simulate_srgb([200, 40, 40]), [200, 40, 40]);
+
+		let full = Simulation::from(Acquired::new(Defect::RedGreenI, 1.0));
+		assert_eq!(full.vision(), ColorVision::Protanomaly);
+		assert_eq!(full.severity(), 1.0);
+	}
+}
@@ -0,0 +1,765 @@
+//! Adaptive procedures for estimating discrimination thresholds
+//!
+//! An adaptive procedure chooses the intensity of each trial from the
+//! responses to previous trials, converging on the threshold of the subject.
+//! This module provides transformed up-down [Staircase]s and the Bayesian
+//! [Quest] procedure, and a [ConeTest] which runs one procedure along the
+//! isolating direction of each cone cell, and maps the thresholds to the
+//! condition of each cone cell.
+//!
+//! Intensities are cone contrasts, such as 0.01 for a 1% change of the
+//! excitation of the isolated cone cell.
+//!
+//! ```
+//! use achroma::adaptive::{ConeLimits, ConeTest, Staircase};
+//! use achroma::{ConeCell, ConeCellSummary};
+//!
+//! let mut test = ConeTest::from_fn(|_| Staircase::new(0.2));
+//!
+//! // a subject without L cones, who never sees L cone contrast
+//! while let Some(trial) = test.next_trial() {
+//!     let threshold = match trial.cone {
+//!         ConeCell::Long => f32::INFINITY,
+//!         ConeCell::Medium => 0.004,
+//!         ConeCell::Short => 0.03,
+//!     };
+//!     test.respond(trial.cone, trial.contrast >= threshold);
+//! }
+//!
+//! let summary = test.summary(&ConeLimits::DEFAULT).unwrap();
+//! assert_eq!(summary, ConeCellSummary::PROTANOPIA);
+//! ```
+
+use crate::{ConeCell, ConeCellCond, ConeCellSummary};
+
+/// An adaptive procedure which chooses the intensity of each trial
+pub trait Procedure {
+	/// The intensity of the next trial
+	fn intensity(&self) -> f32;
+
+	/// Records whether the subject responded correctly to the trial at the current intensity
+	fn respond(&mut self, correct: bool);
+
+	/// Whether the procedure has reached its stopping rule
+	fn is_finished(&self) -> bool;
+
+	/// The current estimate of the threshold, if the procedure has one
+	fn threshold(&self) -> Option<f32>;
+}
+
+/// The most reversals recorded by a [Staircase]
+pub const MAX_REVERSALS: usize = 16;
+
+/// The direction in which a staircase last moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Direction {
+	Up,
+	Down,
+}
+
+/// A transformed up-down staircase, which lowers the intensity after a run
+/// of correct responses and raises it after each incorrect response.
+///
+/// A 2-down 1-up staircase converges on the intensity at which 70.7% of
+/// responses are correct, and a 3-down 1-up staircase on 79.4%.
+/// Steps are multiplicative, and the threshold is the geometric mean of the
+/// intensities at the reversals after the first two.
+///
+/// ```
+/// use achroma::adaptive::{Procedure, Staircase};
+///
+/// let mut staircase = Staircase::new(0.1).with_reversals(8);
+/// while !staircase.is_finished() {
+///     let correct = staircase.intensity() >= 0.01;
+///     staircase.respond(correct);
+/// }
+///
+/// let threshold = staircase.threshold().unwrap();
+/// assert!(threshold > 0.007 && threshold < 0.014);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Staircase {
+	intensity: f32,
+	step: f32,
+	min: f32,
+	max: f32,
+	down: u8,
+	run: u8,
+	direction: Option<Direction>,
+	reversals: [f32; MAX_REVERSALS],
+	reversal_count: u8,
+	max_reversals: u8,
+	ceiling_misses: u8,
+	trials: u16,
+	max_trials: u16,
+}
+
+impl Staircase {
+	/// The number of incorrect responses in a row at the highest intensity
+	/// after which the staircase stops, as the subject can't see the stimulus
+	pub const CEILING_MISSES: u8 = 3;
+
+	/// Creates a new 2-down 1-up staircase starting at an intensity, with steps of
+	/// 0.1 log units, 10 reversals, at most 100 trials and intensities within 0.0001 and 1.0
+	pub const fn new(start: f32) -> Self {
+		Self {
+			intensity: start,
+			step: 0.1,
+			min: 0.0001,
+			max: 1.0,
+			down: 2,
+			run: 0,
+			direction: None,
+			reversals: [0.0; MAX_REVERSALS],
+			reversal_count: 0,
+			max_reversals: 10,
+			ceiling_misses: 0,
+			trials: 0,
+			max_trials: 100,
+		}
+	}
+
+	/// Sets the number of correct responses in a row before lowering the intensity
+	pub const fn with_down(self, down: u8) -> Self {
+		let down = if down == 0 { 1 } else { down };
+		Self { down, ..self }
+	}
+
+	/// Sets the size of each step in log10 units
+	pub const fn with_step(self, step: f32) -> Self {
+		Self { step, ..self }
+	}
+
+	/// Sets the lowest and highest intensities
+	pub fn with_range(self, min: f32, max: f32) -> Self {
+		Self {
+			min,
+			max,
+			intensity: self.intensity.clamp(min, max),
+			..self
+		}
+	}
+
+	/// Sets the number of reversals after which the staircase stops, up to [MAX_REVERSALS]
+	pub const fn with_reversals(self, reversals: u8) -> Self {
+		let max_reversals = if reversals as usize > MAX_REVERSALS {
+			MAX_REVERSALS as u8
+		} else {
+			reversals
+		};
+		Self {
+			max_reversals,
+			..self
+		}
+	}
+
+	/// Sets the number of trials after which the staircase stops
+	pub const fn with_max_trials(self, max_trials: u16) -> Self {
+		Self { max_trials, ..self }
+	}
+
+	/// The number of trials responded to
+	pub const fn trials(&self) -> u16 {
+		self.trials
+	}
+
+	/// The intensities at which the staircase reversed its direction
+	pub fn reversals(&self) -> &[f32] {
+		&self.reversals[..self.reversal_count as usize]
+	}
+
+	/// Whether the staircase stopped at the highest intensity
+	pub const fn is_at_ceiling(&self) -> bool {
+		self.ceiling_misses >= Self::CEILING_MISSES
+	}
+
+	fn step(&mut self, direction: Direction) {
+		if self.direction.is_some_and(|last| last != direction)
+			&& (self.reversal_count as usize) < MAX_REVERSALS
+		{
+			self.reversals[self.reversal_count as usize] = self.intensity;
+			self.reversal_count += 1;
+		}
+		self.direction = Some(direction);
+
+		let factor = libm::powf(10.0, self.step);
+		self.intensity = match direction {
+			Direction::Up => self.intensity * factor,
+			Direction::Down => self.intensity / factor,
+		}
+		.clamp(self.min, self.max);
+	}
+}
+
+impl Procedure for Staircase {
+	fn intensity(&self) -> f32 {
+		self.intensity
+	}
+
+	fn respond(&mut self, correct: bool) {
+		if self.is_finished() {
+			return;
+		}
+		self.trials += 1;
+
+		if correct {
+			self.ceiling_misses = 0;
+			self.run += 1;
+			if self.run >= self.down {
+				self.run = 0;
+				self.step(Direction::Down);
+			}
+		} else {
+			if self.intensity >= self.max {
+				self.ceiling_misses += 1;
+			}
+			self.run = 0;
+			self.step(Direction::Up);
+		}
+	}
+
+	fn is_finished(&self) -> bool {
+		self.reversal_count >= self.max_reversals
+			|| self.trials >= self.max_trials
+			|| self.is_at_ceiling()
+	}
+
+	fn threshold(&self) -> Option<f32> {
+		if self.is_at_ceiling() {
+			return Some(self.max);
+		}
+		let reversals = self.reversals();
+		let reversals = if reversals.len() > 2 {
+			&reversals[2..]
+		} else {
+			reversals
+		};
+		if reversals.is_empty() {
+			return None;
+		}
+		let mean = reversals.iter().map(|&r| libm::log10f(r)).sum::<f32>()
+			/ reversals.len() as f32;
+		Some(libm::powf(10.0, mean))
+	}
+}
+
+/// The number of candidate thresholds in the posterior of [Quest]
+pub const QUEST_GRID: usize = 128;
+
+/// The Bayesian QUEST procedure of Watson & Pelli (1983), which keeps a
+/// posterior distribution of the log threshold, and tests at its mean.
+///
+/// Responses follow a Weibull psychometric function with a slope β,
+/// a guess rate γ and a lapse rate δ.
+///
+/// ```
+/// use achroma::adaptive::{Procedure, Quest};
+///
+/// // a four-alternative forced choice, starting from a guess of 5% contrast
+/// let mut quest = Quest::new(0.05).with_guess_rate(0.25);
+/// while !quest.is_finished() {
+///     let correct = quest.intensity() >= 0.02;
+///     quest.respond(correct);
+/// }
+///
+/// let threshold = quest.threshold().unwrap();
+/// assert!(threshold > 0.015 && threshold < 0.027);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quest {
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+	posterior: [f32; QUEST_GRID],
+	min: f32,
+	max: f32,
+	beta: f32,
+	gamma: f32,
+	delta: f32,
+	trials: u16,
+	max_trials: u16,
+	max_deviation: f32,
+}
+
+impl Quest {
+	/// Creates a new procedure with a prior centered on a guess of the threshold,
+	/// with a standard deviation of 1 log unit, searching within 0.0001 and 1.0.
+	///
+	/// The default psychometric function has β = 3.5, γ = 0.5 and δ = 0.01,
+	/// and the procedure stops after 40 trials.
+	pub fn new(guess: f32) -> Self {
+		let mut quest = Self {
+			posterior: [0.0; QUEST_GRID],
+			min: -4.0,
+			max: 0.0,
+			beta: 3.5,
+			gamma: 0.5,
+			delta: 0.01,
+			trials: 0,
+			max_trials: 40,
+			max_deviation: 0.0,
+		};
+		quest.set_prior(libm::log10f(guess), 1.0);
+		quest
+	}
+
+	/// Sets a prior centered on a guess of the threshold with a standard deviation
+	/// in log10 units, discarding any responses
+	pub fn with_prior(mut self, guess: f32, deviation: f32) -> Self {
+		self.trials = 0;
+		self.set_prior(libm::log10f(guess), deviation);
+		self
+	}
+
+	/// Sets the lowest and highest intensities, discarding any responses
+	pub fn with_range(mut self, min: f32, max: f32) -> Self {
+		let mean = libm::powf(10.0, self.mean());
+		let deviation = self.deviation();
+		self.min = libm::log10f(min);
+		self.max = libm::log10f(max);
+		self.with_prior(mean, deviation)
+	}
+
+	/// Sets the slope β of the psychometric function
+	pub const fn with_slope(self, beta: f32) -> Self {
+		Self { beta, ..self }
+	}
+
+	/// Sets the guess rate γ, such as 0.5 for a two-alternative forced choice
+	pub const fn with_guess_rate(self, gamma: f32) -> Self {
+		Self { gamma, ..self }
+	}
+
+	/// Sets the lapse rate δ, the chance of an incorrect response well above threshold
+	pub const fn with_lapse_rate(self, delta: f32) -> Self {
+		Self { delta, ..self }
+	}
+
+	/// Sets the number of trials after which the procedure stops
+	pub const fn with_max_trials(self, max_trials: u16) -> Self {
+		Self { max_trials, ..self }
+	}
+
+	/// Stops the procedure early once the standard deviation of the posterior
+	/// falls below a number of log10 units
+	pub const fn with_max_deviation(self, max_deviation: f32) -> Self {
+		Self {
+			max_deviation,
+			..self
+		}
+	}
+
+	/// The number of trials responded to
+	pub const fn trials(&self) -> u16 {
+		self.trials
+	}
+
+	/// The standard deviation of the posterior in log10 units
+	pub fn deviation(&self) -> f32 {
+		let mean = self.mean();
+		let variance: f32 = (0..QUEST_GRID)
+			.map(|i| {
+				self.posterior[i]
+					* (self.log_threshold(i) - mean) * (self.log_threshold(i) - mean)
+			})
+			.sum();
+		libm::sqrtf(variance)
+	}
+
+	/// The probability of a correct response at a log intensity
+	/// for a log threshold
+	fn psychometric(&self, log_intensity: f32, log_threshold: f32) -> f32 {
+		let weibull = 1.0 - libm::expf(-libm::powf(
+			10.0,
+			self.beta * (log_intensity - log_threshold),
+		));
+		self.gamma + (1.0 - self.gamma - self.delta) * weibull
+	}
+
+	fn log_threshold(&self, index: usize) -> f32 {
+		self.min + (self.max - self.min) * index as f32 / (QUEST_GRID - 1) as f32
+	}
+
+	fn mean(&self) -> f32 {
+		(0..QUEST_GRID)
+			.map(|i| self.posterior[i] * self.log_threshold(i))
+			.sum()
+	}
+
+	fn set_prior(&mut self, mean: f32, deviation: f32) {
+		for i in 0..QUEST_GRID {
+			let z = (self.log_threshold(i) - mean) / deviation;
+			self.posterior[i] = libm::expf(-0.5 * z * z);
+		}
+		self.normalize();
+	}
+
+	fn normalize(&mut self) {
+		let total: f32 = self.posterior.iter().sum();
+		if total > 0.0 {
+			for p in &mut self.posterior {
+				*p /= total;
+			}
+		} else {
+			self.posterior = [1.0 / QUEST_GRID as f32; QUEST_GRID];
+		}
+	}
+}
+
+impl Procedure for Quest {
+	fn intensity(&self) -> f32 {
+		libm::powf(10.0, self.mean().clamp(self.min, self.max))
+	}
+
+	fn respond(&mut self, correct: bool) {
+		if self.is_finished() {
+			return;
+		}
+		self.trials += 1;
+
+		let log_intensity = libm::log10f(self.intensity());
+		for i in 0..QUEST_GRID {
+			let p = self.psychometric(log_intensity, self.log_threshold(i));
+			self.posterior[i] *= if correct { p } else { 1.0 - p };
+		}
+		self.normalize();
+	}
+
+	fn is_finished(&self) -> bool {
+		self.trials >= self.max_trials
+			|| (self.trials > 0 && self.deviation() < self.max_deviation)
+	}
+
+	fn threshold(&self) -> Option<f32> {
+		Some(libm::powf(10.0, self.mean()))
+	}
+}
+
+/// The limits of the threshold of a cone cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limits {
+	/// The highest threshold of a normal cone cell
+	pub normal: f32,
+	/// The lowest threshold of a missing cone cell
+	pub missing: f32,
+}
+
+impl Limits {
+	/// Creates new limits from the highest normal and lowest missing thresholds
+	pub const fn new(normal: f32, missing: f32) -> Self {
+		Self { normal, missing }
+	}
+
+	/// The condition of a cone cell with a threshold
+	///
+	/// ```
+	/// use achroma::adaptive::Limits;
+	/// use achroma::ConeCellCond;
+	///
+	/// let limits = Limits::new(0.01, 0.1);
+	/// assert_eq!(limits.cond(0.005), ConeCellCond::Normal);
+	/// assert_eq!(limits.cond(0.05), ConeCellCond::Anomalous);
+	/// assert_eq!(limits.cond(0.1), ConeCellCond::Missing);
+	/// ```
+	pub fn cond(&self, threshold: f32) -> ConeCellCond {
+		if threshold <= self.normal {
+			ConeCellCond::Normal
+		} else if threshold < self.missing {
+			ConeCellCond::Anomalous
+		} else {
+			ConeCellCond::Missing
+		}
+	}
+}
+
+/// The limits of the thresholds of each cone cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConeLimits {
+	/// Limits of the L cone contrast threshold
+	pub long: Limits,
+	/// Limits of the M cone contrast threshold
+	pub medium: Limits,
+	/// Limits of the S cone contrast threshold
+	pub short: Limits,
+}
+
+impl ConeLimits {
+	/// Rough limits of cone contrast thresholds, where S cones are far less
+	/// sensitive to contrast than L and M cones.
+	///
+	/// Thresholds depend on the display, stimulus and procedure, so tests
+	/// should prefer limits measured with their own setup.
+	pub const DEFAULT: Self = Self::new(
+		Limits::new(0.01, 0.1),
+		Limits::new(0.01, 0.1),
+		Limits::new(0.1, 0.6),
+	);
+
+	/// Creates new limits from the limits of L, M and S cones
+	pub const fn new(long: Limits, medium: Limits, short: Limits) -> Self {
+		Self {
+			long,
+			medium,
+			short,
+		}
+	}
+
+	/// Gets the limits of a cone cell
+	pub const fn get(&self, cone: ConeCell) -> Limits {
+		match cone {
+			ConeCell::Long => self.long,
+			ConeCell::Medium => self.medium,
+			ConeCell::Short => self.short,
+		}
+	}
+}
+
+impl Default for ConeLimits {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+/// A trial of a [ConeTest]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trial {
+	/// The cone cell isolated by the stimulus
+	pub cone: ConeCell,
+	/// The contrast of the isolated cone cell
+	pub contrast: f32,
+}
+
+impl Trial {
+	/// The L, M and S cone contrasts of the stimulus
+	///
+	/// ```
+	/// use achroma::adaptive::Trial;
+	/// use achroma::ConeCell;
+	///
+	/// let trial = Trial { cone: ConeCell::Short, contrast: 0.2 };
+	/// assert_eq!(trial.cone_contrast(), [0.0, 0.0, 0.2]);
+	/// ```
+	pub const fn cone_contrast(&self) -> [f32; 3] {
+		match self.cone {
+			ConeCell::Long => [self.contrast, 0.0, 0.0],
+			ConeCell::Medium => [0.0, self.contrast, 0.0],
+			ConeCell::Short => [0.0, 0.0, self.contrast],
+		}
+	}
+}
+
+/// A test running one adaptive procedure along the isolating direction of
+/// each cone cell, interleaving their trials
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConeTest<P> {
+	procedures: [P; 3],
+	next: usize,
+}
+
+impl<P: Procedure> ConeTest<P> {
+	const CONES: [ConeCell; 3] = [ConeCell::Long, ConeCell::Medium, ConeCell::Short];
+
+	/// Creates a new test from the procedures of the L, M and S cones
+	pub const fn new(long: P, medium: P, short: P) -> Self {
+		Self {
+			procedures: [long, medium, short],
+			next: 0,
+		}
+	}
+
+	/// Creates a new test with a procedure for each cone cell
+	pub fn from_fn(mut f: impl FnMut(ConeCell) -> P) -> Self {
+		Self::new(f(ConeCell::Long), f(ConeCell::Medium), f(ConeCell::Short))
+	}
+
+	/// Gets the procedure of a cone cell
+	pub fn procedure(&self, cone: ConeCell) -> &P {
+		&self.procedures[Self::index(cone)]
+	}
+
+	/// The next trial, taking turns between the unfinished procedures,
+	/// or [None] when every procedure is finished
+	pub fn next_trial(&mut self) -> Option<Trial> {
+		for offset in 0..3 {
+			let index = (self.next + offset) % 3;
+			let procedure = &self.procedures[index];
+			if !procedure.is_finished() {
+				self.next = (index + 1) % 3;
+				return Some(Trial {
+					cone: Self::CONES[index],
+					contrast: procedure.intensity(),
+				});
+			}
+		}
+		None
+	}
+
+	/// Records the response to a trial of a cone cell
+	pub fn respond(&mut self, cone: ConeCell, correct: bool) {
+		self.procedures[Self::index(cone)].respond(correct);
+	}
+
+	/// Whether every procedure is finished
+	pub fn is_finished(&self) -> bool {
+		self.procedures.iter().all(Procedure::is_finished)
+	}
+
+	/// The current threshold estimates of the L, M and S cones
+	pub fn thresholds(&self) -> [Option<f32>; 3] {
+		[
+			self.procedures[0].threshold(),
+			self.procedures[1].threshold(),
+			self.procedures[2].threshold(),
+		]
+	}
+
+	/// The condition of each cone cell from its threshold, or [None] when
+	/// a procedure has no estimate yet
+	pub fn summary(&self, limits: &ConeLimits) -> Option<ConeCellSummary> {
+		let mut summary = ConeCellSummary::NORMAL;
+		for (cone, threshold) in Self::CONES.into_iter().zip(self.thresholds()) {
+			summary[cone] = limits.get(cone).cond(threshold?);
+		}
+		Some(summary)
+	}
+
+	const fn index(cone: ConeCell) -> usize {
+		match cone {
+			ConeCell::Long => 0,
+			ConeCell::Medium => 1,
+			ConeCell::Short => 2,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A deterministic random number generator for simulated observers
+	struct Rng(u64);
+
+	impl Rng {
+		fn next(&mut self) -> f32 {
+			self.0 =
+				self.0.wrapping_mul(6364136223846793005)
+					.wrapping_add(1442695040888963407);
+			(self.0 >> 40) as f32 / (1u64 << 24) as f32
+		}
+	}
+
+	/// Simulates a two-alternative forced choice observer
+	fn observe(rng: &mut Rng, contrast: f32, threshold: f32) -> bool {
+		let seen = 1.0 - libm::expf(-libm::powf(contrast / threshold, 3.5));
+		rng.next() < 0.5 + 0.5 * seen
+	}
+
+	#[test]
+	fn test_staircase_reversals() {
+		let mut staircase = Staircase::new(0.1).with_down(1).with_reversals(4);
+		for correct in [true, true, false, true, false, false, true] {
+			staircase.respond(correct);
+		}
+		assert_eq!(staircase.reversals().len(), 4);
+		assert!(staircase.is_finished());
+		assert_eq!(staircase.trials(), 7);
+	}
+
+	#[test]
+	fn test_staircase_ceiling() {
+		let mut staircase = Staircase::new(0.5).with_range(0.001, 0.5);
+		while !staircase.is_finished() {
+			staircase.respond(false);
+		}
+		assert!(staircase.is_at_ceiling());
+		assert_eq!(staircase.threshold(), Some(0.5));
+	}
+
+	#[test]
+	fn test_staircase_simulated_observer() {
+		let mut rng = Rng(7);
+		let mut total = 0.0;
+		for _ in 0..20 {
+			let mut staircase = Staircase::new(0.1).with_reversals(12);
+			while !staircase.is_finished() {
+				let correct = observe(&mut rng, staircase.intensity(), 0.01);
+				staircase.respond(correct);
+			}
+			total += libm::log10f(staircase.threshold().unwrap());
+		}
+		let threshold = libm::powf(10.0, total / 20.0);
+		assert!(threshold > 0.006 && threshold < 0.015);
+	}
+
+	#[test]
+	fn test_quest_simulated_observer() {
+		let mut rng = Rng(11);
+		let mut total = 0.0;
+		for _ in 0..20 {
+			let mut quest = Quest::new(0.1);
+			while !quest.is_finished() {
+				let correct = observe(&mut rng, quest.intensity(), 0.01);
+				quest.respond(correct);
+			}
+			total += libm::log10f(quest.threshold().unwrap());
+		}
+		let threshold = libm::powf(10.0, total / 20.0);
+		assert!(threshold > 0.007 && threshold < 0.014);
+	}
+
+	#[test]
+	fn test_quest_early_stop() {
+		let mut quest = Quest::new(0.05).with_max_deviation(0.1);
+		while !quest.is_finished() {
+			let correct = quest.intensity() >= 0.02;
+			quest.respond(correct);
+		}
+		assert!(quest.trials() < 40);
+		assert!(quest.deviation() < 0.1);
+	}
+
+	#[test]
+	fn test_cone_test_interleaves() {
+		let mut test = ConeTest::from_fn(|_| Staircase::new(0.1));
+		let cones: [ConeCell; 4] = core::array::from_fn(|_| {
+			let trial = test.next_trial().unwrap();
+			test.respond(trial.cone, true);
+			trial.cone
+		});
+		assert_eq!(
+			cones,
+			[
+				ConeCell::Long,
+				ConeCell::Medium,
+				ConeCell::Short,
+				ConeCell::Long
+			]
+		);
+		assert_eq!(test.summary(&ConeLimits::DEFAULT), None);
+	}
+
+	#[test]
+	fn test_cone_test_summary() {
+		let mut rng = Rng(3);
+		let mut test = ConeTest::from_fn(|_| Quest::new(0.05));
+		while let Some(trial) = test.next_trial() {
+			let threshold = match trial.cone {
+				ConeCell::Long => 0.006,
+				ConeCell::Medium => 0.03,
+				ConeCell::Short => 0.05,
+			};
+			let correct = observe(&mut rng, trial.contrast, threshold);
+			test.respond(trial.cone, correct);
+		}
+		assert!(test.is_finished());
+		assert_eq!(
+			test.summary(&ConeLimits::DEFAULT),
+			Some(ConeCellSummary::DEUTERANOMALY)
+		);
+	}
+}
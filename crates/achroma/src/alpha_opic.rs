@@ -0,0 +1,516 @@
+//! α-opic quantities of light, as defined by CIE S 026:2018, "CIE System for
+//! Metrology of Optical Radiation for ipRGC-Influenced Responses to Light"
+//!
+//! Besides the three types of cone cells, light is detected by rod cells and
+//! by intrinsically photosensitive retinal ganglion cells (ipRGCs), which
+//! express melanopsin and drive non-visual responses such as the circadian
+//! rhythm. Each of these five photoreceptors has an action spectrum, which
+//! weighs a [Spectrum] into its α-opic irradiance.
+//!
+//! The α-opic equivalent daylight illuminance (EDI) is the illuminance of
+//! CIE standard illuminant D65 that gives the same α-opic irradiance, so that
+//! daylight has the same EDI for all five photoreceptors as its illuminance.
+//!
+//! The rhodopic action spectrum is the scotopic luminous efficiency function
+//! V'(λ). The cone and melanopic action spectra are modeled by the
+//! [Govardovskii template][govardovskii_a1] of each photopigment, filtered by
+//! the [lens][lens_density] of the 32 year old standard observer. This
+//! matches the construction of the melanopic spectrum of CIE S 026, but leaves
+//! out the macular pigment and self-screening of the cone fundamentals, so
+//! that cone-opic quantities of daylight deviate from the tabulated spectra by
+//! up to 10%. The tabulated spectra of CIE S 026, which for the cones are the
+//! CIE 2006 10° cone fundamentals, aren't bundled, but can be loaded into
+//! [ActionSpectra] for quantities that conform to the standard.
+//!
+//! ```
+//! use achroma::alpha_opic::{Receptor, Spectrum};
+//!
+//! // daylight at 500 lx has a melanopic EDI of 500 lx
+//! let daylight = Spectrum::D65.scaled(500.0 / Spectrum::D65.illuminance());
+//! let edi = daylight.equivalent_daylight_illuminance();
+//! assert!((edi[Receptor::Melanopsin] - 500.0).abs() < 0.01);
+//! ```
+
+use core::fmt;
+use core::ops::Index;
+use core::str::FromStr;
+
+use crate::mesopic::{photopic_efficiency, scotopic_efficiency, PHOTOPIC_EFFICACY};
+use crate::spectral::{govardovskii_a1, lens_density, L_PEAK, M_PEAK, S_PEAK};
+use crate::ConeCell;
+
+/// The age in years of the standard observer of CIE S 026
+pub const OBSERVER_AGE: f32 = 32.0;
+
+/// The relative spectral power of CIE standard illuminant D65, from 380 to
+/// 780 nm in steps of 10 nm
+pub const D65: [f32; 41] = [
+	49.9755, 54.6482, 82.7549, 91.486, 93.4318, 86.6823, 104.865, 117.008, 117.812, 114.861,
+	115.923, 108.811, 109.354, 107.802, 104.79, 107.689, 104.405, 104.046, 100.0, 96.3342,
+	95.788, 88.6856, 90.0062, 89.5991, 87.6987, 83.2886, 83.6992, 80.0268, 80.2146, 82.2778,
+	78.2842, 69.7213, 71.6091, 74.349, 61.604, 69.8856, 75.087, 63.5927, 46.4182, 66.8054,
+	63.3828,
+];
+
+/// A photoreceptor with an α-opic action spectrum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Receptor {
+	/// A type of cone cell, for the S-, M- and L-cone-opic quantities
+	Cone(ConeCell),
+	/// Rod cells, for the rhodopic quantities
+	Rod,
+	/// Melanopsin in ipRGCs, for the melanopic quantities
+	Melanopsin,
+}
+
+impl Receptor {
+	/// All photoreceptors of CIE S 026, from the shortest to the longest peak wavelength
+	pub const ALL: [Self; 5] = [
+		Self::Cone(ConeCell::Short),
+		Self::Melanopsin,
+		Self::Rod,
+		Self::Cone(ConeCell::Medium),
+		Self::Cone(ConeCell::Long),
+	];
+
+	/// Gets the name of the α-opic quantities of the photoreceptor, such as `"melanopic"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Cone(ConeCell::Short) => "s-cone-opic",
+			Self::Cone(ConeCell::Medium) => "m-cone-opic",
+			Self::Cone(ConeCell::Long) => "l-cone-opic",
+			Self::Rod => "rhodopic",
+			Self::Melanopsin => "melanopic",
+		}
+	}
+
+	/// The peak wavelength in nanometers of the photopigment, before the
+	/// filtering of the lens shifts the action spectrum to longer wavelengths
+	pub const fn pigment_peak(&self) -> f32 {
+		match self {
+			Self::Cone(ConeCell::Short) => S_PEAK,
+			Self::Cone(ConeCell::Medium) => M_PEAK,
+			Self::Cone(ConeCell::Long) => L_PEAK,
+			Self::Rod => 498.0,
+			Self::Melanopsin => 480.0,
+		}
+	}
+
+	/// The action spectrum at a wavelength in nanometers, normalized to 1.0 at the peak
+	///
+	/// ```
+	/// use achroma::alpha_opic::Receptor;
+	///
+	/// // the lens shifts the peak of melanopsin from 480 nm to about 490 nm
+	/// let melanopsin = Receptor::Melanopsin;
+	/// assert!(melanopsin.sensitivity(490.0) > 0.99);
+	/// assert!(melanopsin.sensitivity(600.0) < 0.1);
+	/// ```
+	pub fn sensitivity(&self, wavelength: f32) -> f32 {
+		self.unnormalized(wavelength) / self.maximum()
+	}
+
+	/// The action spectrum at a wavelength in nanometers, with any peak
+	fn unnormalized(&self, wavelength: f32) -> f32 {
+		match self {
+			Self::Rod => scotopic_efficiency(wavelength),
+			_ => {
+				govardovskii_a1(self.pigment_peak(), wavelength)
+					* libm::powf(10.0, -lens_density(OBSERVER_AGE, wavelength))
+			}
+		}
+	}
+
+	/// The peak of the unnormalized action spectrum, found in steps of 1 nm
+	fn maximum(&self) -> f32 {
+		if *self == Self::Rod {
+			return 1.0;
+		}
+		let peak = self.pigment_peak();
+		let mut maximum: f32 = 0.0;
+		let mut wavelength = peak;
+		while wavelength <= peak + 40.0 {
+			maximum = maximum.max(self.unnormalized(wavelength));
+			wavelength += 1.0;
+		}
+		maximum
+	}
+
+	/// The α-opic efficacy of D65 in watts per lumen, the α-opic irradiance
+	/// per lux of illuminance
+	///
+	/// ```
+	/// use achroma::alpha_opic::Receptor;
+	///
+	/// // CIE S 026 gives 1.4497 mW/lm
+	/// let rhodopic = Receptor::Rod.daylight_efficacy() * 1000.0;
+	/// assert!((rhodopic - 1.4497).abs() < 1e-3);
+	/// ```
+	pub fn daylight_efficacy(&self) -> f32 {
+		Spectrum::D65.irradiance_of(*self) / Spectrum::D65.illuminance()
+	}
+}
+
+impl fmt::Display for Receptor {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Receptor {
+	type Err = ();
+	/// Attempt to parse the name of α-opic quantities, ignoring ASCII case
+	///
+	/// ```
+	/// use achroma::alpha_opic::Receptor;
+	/// use achroma::ConeCell;
+	///
+	/// assert_eq!("Melanopic".parse(), Ok(Receptor::Melanopsin));
+	/// assert_eq!("l-cone-opic".parse(), Ok(Receptor::Cone(ConeCell::Long)));
+	/// assert_eq!("photopic".parse::<Receptor>(), Err(()));
+	/// ```
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|receptor| receptor.name().eq_ignore_ascii_case(s.trim()))
+			.ok_or(())
+	}
+}
+
+/// A value of an α-opic quantity for each photoreceptor
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlphaOpic {
+	/// The L-, M- and S-cone-opic values, in the order of [ConeCell::Long],
+	/// [ConeCell::Medium] and [ConeCell::Short]
+	pub cones: [f32; 3],
+	/// The rhodopic value
+	pub rhodopic: f32,
+	/// The melanopic value
+	pub melanopic: f32,
+}
+
+impl AlphaOpic {
+	/// Creates new values from a function of each photoreceptor
+	pub fn from_fn(mut f: impl FnMut(Receptor) -> f32) -> Self {
+		Self {
+			cones: [
+				f(Receptor::Cone(ConeCell::Long)),
+				f(Receptor::Cone(ConeCell::Medium)),
+				f(Receptor::Cone(ConeCell::Short)),
+			],
+			rhodopic: f(Receptor::Rod),
+			melanopic: f(Receptor::Melanopsin),
+		}
+	}
+}
+
+impl Index<ConeCell> for AlphaOpic {
+	type Output = f32;
+
+	/// Index the cone-opic values by [ConeCell]
+	///
+	/// ```
+	/// use achroma::alpha_opic::AlphaOpic;
+	/// use achroma::ConeCell;
+	///
+	/// let values = AlphaOpic { cones: [3.0, 2.0, 1.0], rhodopic: 4.0, melanopic: 5.0 };
+	/// assert_eq!(values[ConeCell::Short], 1.0);
+	/// ```
+	fn index(&self, cone: ConeCell) -> &Self::Output {
+		match cone {
+			ConeCell::Long => &self.cones[0],
+			ConeCell::Medium => &self.cones[1],
+			ConeCell::Short => &self.cones[2],
+		}
+	}
+}
+
+impl Index<Receptor> for AlphaOpic {
+	type Output = f32;
+
+	fn index(&self, receptor: Receptor) -> &Self::Output {
+		match receptor {
+			Receptor::Cone(cone) => &self[cone],
+			Receptor::Rod => &self.rhodopic,
+			Receptor::Melanopsin => &self.melanopic,
+		}
+	}
+}
+
+/// Tabulated α-opic action spectra, each normalized to 1.0 at its peak,
+/// such as the spectra of CIE S 026
+///
+/// ```
+/// use achroma::alpha_opic::{ActionSpectra, Receptor, Spectrum};
+///
+/// // spectra tabulated from 380 to 780 nm, here sampled from the modeled ones
+/// let table = |receptor: Receptor| {
+///     let mut values = [0.0; 81];
+///     for (index, value) in values.iter_mut().enumerate() {
+///         *value = receptor.sensitivity(380.0 + 5.0 * index as f32);
+///     }
+///     values
+/// };
+/// let [s, m, l] = [0, 3, 4].map(|index| table(Receptor::ALL[index]));
+/// let (rod, melanopsin) = (table(Receptor::Rod), table(Receptor::Melanopsin));
+/// let spectrum = |values| Spectrum::new(380.0, 5.0, values);
+/// let spectra = ActionSpectra::new(
+///     [spectrum(&l), spectrum(&m), spectrum(&s)],
+///     spectrum(&rod),
+///     spectrum(&melanopsin),
+/// );
+///
+/// // daylight has the same EDI for every photoreceptor as its illuminance
+/// let edi = Spectrum::D65.equivalent_daylight_illuminance_with(&spectra);
+/// let illuminance = Spectrum::D65.illuminance();
+/// assert!((edi[Receptor::Melanopsin] / illuminance - 1.0).abs() < 1e-4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionSpectra<'a> {
+	/// The L-, M- and S-cone-opic action spectra, in the order of
+	/// [ConeCell::Long], [ConeCell::Medium] and [ConeCell::Short]
+	pub cones: [Spectrum<'a>; 3],
+	/// The rhodopic action spectrum
+	pub rhodopic: Spectrum<'a>,
+	/// The melanopic action spectrum
+	pub melanopic: Spectrum<'a>,
+}
+
+impl<'a> ActionSpectra<'a> {
+	/// Creates new action spectra from the spectrum of each photoreceptor
+	pub const fn new(
+		cones: [Spectrum<'a>; 3],
+		rhodopic: Spectrum<'a>,
+		melanopic: Spectrum<'a>,
+	) -> Self {
+		Self {
+			cones,
+			rhodopic,
+			melanopic,
+		}
+	}
+
+	/// The action spectrum of a photoreceptor
+	pub const fn get(&self, receptor: Receptor) -> &Spectrum<'a> {
+		match receptor {
+			Receptor::Cone(ConeCell::Long) => &self.cones[0],
+			Receptor::Cone(ConeCell::Medium) => &self.cones[1],
+			Receptor::Cone(ConeCell::Short) => &self.cones[2],
+			Receptor::Rod => &self.rhodopic,
+			Receptor::Melanopsin => &self.melanopic,
+		}
+	}
+
+	/// The α-opic efficacy of D65 in watts per lumen of a photoreceptor,
+	/// as in [Receptor::daylight_efficacy]
+	pub fn daylight_efficacy(&self, receptor: Receptor) -> f32 {
+		Spectrum::D65.irradiance_with(self)[receptor] / Spectrum::D65.illuminance()
+	}
+}
+
+/// A spectral irradiance in W/m²/nm, sampled at evenly spaced wavelengths
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spectrum<'a> {
+	start: f32,
+	step: f32,
+	values: &'a [f32],
+	scale: f32,
+}
+
+impl<'a> Spectrum<'a> {
+	/// CIE standard illuminant D65, with its relative spectral power taken as W/m²/nm
+	pub const D65: Spectrum<'static> = Spectrum::new(380.0, 10.0, &D65);
+
+	/// Creates a new spectrum from the irradiances in W/m²/nm at wavelengths
+	/// starting at `start` nanometers and spaced by `step` nanometers
+	pub const fn new(start: f32, step: f32, values: &'a [f32]) -> Self {
+		Self {
+			start,
+			step,
+			values,
+			scale: 1.0,
+		}
+	}
+
+	/// Scales the irradiances by a factor
+	pub fn scaled(mut self, factor: f32) -> Self {
+		self.scale *= factor;
+		self
+	}
+
+	/// Iterates over the wavelengths in nanometers and irradiances in W/m²/nm
+	pub fn samples(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+		self.values.iter().enumerate().map(|(index, &value)| {
+			(self.start + index as f32 * self.step, value * self.scale)
+		})
+	}
+
+	/// The value at a wavelength in nanometers, interpolated linearly
+	/// between samples, and 0.0 outside of them
+	///
+	/// ```
+	/// use achroma::alpha_opic::Spectrum;
+	///
+	/// let spectrum = Spectrum::new(500.0, 10.0, &[1.0, 3.0]);
+	/// assert_eq!(spectrum.value(505.0), 2.0);
+	/// assert_eq!(spectrum.value(520.0), 0.0);
+	/// ```
+	pub fn value(&self, wavelength: f32) -> f32 {
+		let position = (wavelength - self.start) / self.step;
+		let last = self.values.len().saturating_sub(1);
+		if self.values.is_empty() || position < 0.0 || position > last as f32 {
+			return 0.0;
+		}
+		let index = (libm::floorf(position) as usize).min(last);
+		let next = self.values[(index + 1).min(last)];
+		let t = position - index as f32;
+		self.scale * (self.values[index] + t * (next - self.values[index]))
+	}
+
+	/// Integrates the spectrum weighted by a function of the wavelength
+	fn integrate(&self, weight: impl Fn(f32) -> f32) -> f32 {
+		self.samples()
+			.map(|(wavelength, value)| value * weight(wavelength))
+			.sum::<f32>() * self.step
+	}
+
+	/// The photopic illuminance in lux
+	pub fn illuminance(&self) -> f32 {
+		PHOTOPIC_EFFICACY * self.integrate(photopic_efficiency)
+	}
+
+	/// The α-opic irradiance of a photoreceptor in W/m²
+	pub fn irradiance_of(&self, receptor: Receptor) -> f32 {
+		self.integrate(|wavelength| receptor.unnormalized(wavelength)) / receptor.maximum()
+	}
+
+	/// The α-opic irradiances in W/m²
+	pub fn irradiance(&self) -> AlphaOpic {
+		AlphaOpic::from_fn(|receptor| self.irradiance_of(receptor))
+	}
+
+	/// The α-opic irradiances in W/m² weighted by tabulated action spectra
+	pub fn irradiance_with(&self, spectra: &ActionSpectra) -> AlphaOpic {
+		AlphaOpic::from_fn(|receptor| {
+			let action = spectra.get(receptor);
+			self.integrate(|wavelength| action.value(wavelength))
+		})
+	}
+
+	/// The α-opic equivalent daylight illuminances in lux
+	///
+	/// ```
+	/// use achroma::alpha_opic::{Receptor, Spectrum};
+	///
+	/// // a narrowband amber light around 590 nm
+	/// let amber = [0.0, 0.5, 1.0, 0.5, 0.0];
+	/// let edi = Spectrum::new(570.0, 10.0, &amber).equivalent_daylight_illuminance();
+	/// // barely affects the circadian rhythm, but still looks bright
+	/// assert!(edi[Receptor::Melanopsin] < 0.1 * edi.cones[0]);
+	/// ```
+	pub fn equivalent_daylight_illuminance(&self) -> AlphaOpic {
+		AlphaOpic::from_fn(|receptor| {
+			self.irradiance_of(receptor) / receptor.daylight_efficacy()
+		})
+	}
+
+	/// The α-opic equivalent daylight illuminances in lux weighted by
+	/// tabulated action spectra
+	pub fn equivalent_daylight_illuminance_with(&self, spectra: &ActionSpectra) -> AlphaOpic {
+		let irradiance = self.irradiance_with(spectra);
+		AlphaOpic::from_fn(|receptor| {
+			irradiance[receptor] / spectra.daylight_efficacy(receptor)
+		})
+	}
+
+	/// The α-opic daylight efficacy ratios, the equivalent daylight
+	/// illuminances relative to the illuminance, which are all 1.0 for D65
+	pub fn daylight_efficacy_ratio(&self) -> AlphaOpic {
+		let illuminance = self.illuminance();
+		let edi = self.equivalent_daylight_illuminance();
+		AlphaOpic::from_fn(|receptor| edi[receptor] / illuminance)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_daylight_efficacy() {
+		// the values of CIE S 026 in mW/lm
+		let tabulated = [
+			(Receptor::Cone(ConeCell::Short), 0.8173),
+			(Receptor::Cone(ConeCell::Medium), 1.4558),
+			(Receptor::Cone(ConeCell::Long), 1.6289),
+			(Receptor::Rod, 1.4497),
+			(Receptor::Melanopsin, 1.3262),
+		];
+		// the modeled cone spectra leave out the macular pigment and
+		// self-screening, and deviate by about -9%, +5% and -3% for the
+		// S, M and L cones, which only tabulated spectra avoid
+		for (receptor, efficacy) in tabulated {
+			let approximate = receptor.daylight_efficacy() * 1000.0;
+			assert!((approximate / efficacy - 1.0).abs() < 0.1);
+		}
+		// the rhodopic spectrum is tabulated, and the melanopic spectrum
+		// follows the same construction as CIE S 026
+		let rhodopic = Receptor::Rod.daylight_efficacy() * 1000.0;
+		assert!((rhodopic - 1.4497).abs() < 1e-3);
+		let melanopic = Receptor::Melanopsin.daylight_efficacy() * 1000.0;
+		assert!((melanopic - 1.3262).abs() < 2e-3);
+	}
+
+	#[test]
+	fn test_tabulated_spectra() {
+		// the modeled spectra tabulated every 10 nm, as D65 is
+		let table = |receptor: Receptor| {
+			let mut values = [0.0; 41];
+			for (index, value) in values.iter_mut().enumerate() {
+				*value = receptor.sensitivity(380.0 + 10.0 * index as f32);
+			}
+			values
+		};
+		let tables = Receptor::ALL.map(table);
+		let spectrum = |index: usize| Spectrum::new(380.0, 10.0, &tables[index]);
+		let spectra = ActionSpectra::new(
+			[spectrum(4), spectrum(3), spectrum(0)],
+			spectrum(2),
+			spectrum(1),
+		);
+
+		for receptor in Receptor::ALL {
+			let tabulated = spectra.daylight_efficacy(receptor);
+			let modeled = receptor.daylight_efficacy();
+			assert!((tabulated / modeled - 1.0).abs() < 1e-5, "{}", receptor);
+		}
+		let rhodopic = spectra.daylight_efficacy(Receptor::Rod) * 1000.0;
+		assert!((rhodopic - 1.4497).abs() < 1e-3);
+
+		assert_eq!(spectrum(0).value(370.0), 0.0);
+		assert_eq!(spectrum(0).value(790.0), 0.0);
+		assert_eq!(spectrum(0).value(780.0), tables[0][40]);
+	}
+
+	#[test]
+	fn test_daylight_ratio() {
+		let ratio = Spectrum::D65.scaled(3.0).daylight_efficacy_ratio();
+		for receptor in Receptor::ALL {
+			assert!((ratio[receptor] - 1.0).abs() < 1e-5);
+		}
+	}
+
+	#[test]
+	fn test_warm_light_is_less_melanopic() {
+		// a warm light with more power at long wavelengths
+		let mut warm = [0.0; 41];
+		for (index, value) in warm.iter_mut().enumerate() {
+			*value = D65[index] * (0.2 + index as f32 / 40.0);
+		}
+		let ratio = Spectrum::new(380.0, 10.0, &warm).daylight_efficacy_ratio();
+		assert!(ratio.melanopic < 1.0 && ratio[ConeCell::Short] < ratio.melanopic);
+		assert!(ratio[ConeCell::Long] > ratio[ConeCell::Medium]);
+	}
+}
@@ -0,0 +1,604 @@
+//! Model of a Nagel-type anomaloscope and its Rayleigh match
+//!
+//! In a Rayleigh match, the subject adjusts a mixture of red (670 nm) and
+//! green (545 nm) light, and the brightness of a yellow (589 nm) light, until
+//! both halves of the field look the same. As only the L and M cones respond
+//! to these wavelengths, the range of accepted mixtures reveals the
+//! photopigments of the subject.
+//!
+//! The match of each pigment configuration is predicted from the sensitivity
+//! of the pigments at the three primaries, and a measured range is assessed
+//! by the anomalous quotient (AQ) and the width of the range.
+//!
+//! ```
+//! use achroma::anomaloscope::{Anomaloscope, Grade, Pigments};
+//! use achroma::ColorVision;
+//!
+//! let anomaloscope = Anomaloscope::default();
+//! let range = anomaloscope.predict(Pigments::deuteranomalous(0.5)).unwrap();
+//!
+//! // deuteranomalous subjects need more green in the mixture
+//! assert!(range.high.mixture < anomaloscope.normal().mixture);
+//!
+//! let assessment = anomaloscope.assess(&range);
+//! assert_eq!(assessment.grade, Grade::Simple);
+//! assert_eq!(assessment.classification.vision, ColorVision::Deuteranomaly);
+//! assert!((assessment.classification.severity - 0.5).abs() < 0.05);
+//! ```
+
+use crate::spectral::{govardovskii_a1, L_PEAK, M_PEAK};
+use crate::{Classification, ColorVision, ConeCellSummary};
+
+/// The wavelength in nanometers of the red primary
+pub const RED_PRIMARY: f32 = 670.0;
+
+/// The wavelength in nanometers of the green primary
+pub const GREEN_PRIMARY: f32 = 545.0;
+
+/// The wavelength in nanometers of the yellow primary
+pub const YELLOW_PRIMARY: f32 = 589.0;
+
+/// The end of the mixture scale, where 0 is pure green and 73 is pure red
+pub const MIXTURE_SCALE: f32 = 73.0;
+
+/// The end of the yellow brightness scale
+pub const YELLOW_SCALE: f32 = 87.0;
+
+/// The lowest anomalous quotient of normal color vision
+pub const AQ_NORMAL_MIN: f32 = 0.7;
+
+/// The highest anomalous quotient of normal color vision
+pub const AQ_NORMAL_MAX: f32 = 1.4;
+
+/// The narrowest range of mixtures, in scale units, accepted by an extreme anomalous trichromat
+pub const EXTREME_WIDTH: f32 = 10.0;
+
+/// The distance to the ends of the mixture scale, in scale units,
+/// within which a range is considered to cover the whole scale
+const ANOPE_MARGIN: f32 = 1.0;
+
+/// The peak wavelengths of the L and M photopigments, if present
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pigments {
+	/// Peak wavelength in nanometers of the pigment in L cones
+	pub long: Option<f32>,
+	/// Peak wavelength in nanometers of the pigment in M cones
+	pub medium: Option<f32>,
+}
+
+impl Pigments {
+	/// Pigments of normal color vision
+	pub const NORMAL: Self = Self::new(Some(L_PEAK), Some(M_PEAK));
+
+	/// Pigments of protanopia, lacking L cones
+	pub const PROTANOPE: Self = Self::new(None, Some(M_PEAK));
+
+	/// Pigments of deuteranopia, lacking M cones
+	pub const DEUTERANOPE: Self = Self::new(Some(L_PEAK), None);
+
+	/// Creates a new pigment configuration from peak wavelengths in nanometers
+	pub const fn new(long: Option<f32>, medium: Option<f32>) -> Self {
+		Self { long, medium }
+	}
+
+	/// Pigments of protanomaly, where the L pigment is shifted towards the M pigment
+	/// by a severity from 0.0 (normal) to 1.0 (identical to the M pigment)
+	pub fn protanomalous(severity: f32) -> Self {
+		let shift = severity.clamp(0.0, 1.0) * (L_PEAK - M_PEAK);
+		Self::new(Some(L_PEAK - shift), Some(M_PEAK))
+	}
+
+	/// Pigments of deuteranomaly, where the M pigment is shifted towards the L pigment
+	/// by a severity from 0.0 (normal) to 1.0 (identical to the L pigment)
+	pub fn deuteranomalous(severity: f32) -> Self {
+		let shift = severity.clamp(0.0, 1.0) * (L_PEAK - M_PEAK);
+		Self::new(Some(L_PEAK), Some(M_PEAK + shift))
+	}
+}
+
+impl Default for Pigments {
+	fn default() -> Self {
+		Self::NORMAL
+	}
+}
+
+/// A setting of the anomaloscope, in scale units
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Match {
+	/// Red-green mixture, from 0 (green) to 73 (red)
+	pub mixture: f32,
+	/// Brightness of the yellow light, from 0 to 87
+	pub yellow: f32,
+}
+
+impl Match {
+	/// Creates a new setting from a mixture and a yellow brightness
+	pub const fn new(mixture: f32, yellow: f32) -> Self {
+		Self { mixture, yellow }
+	}
+}
+
+/// The range of settings accepted as a match, from the greenest to the reddest mixture
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MatchRange {
+	/// Accepted setting with the greenest mixture
+	pub low: Match,
+	/// Accepted setting with the reddest mixture
+	pub high: Match,
+}
+
+impl MatchRange {
+	/// Creates a new range from its greenest and reddest settings
+	pub const fn new(low: Match, high: Match) -> Self {
+		Self { low, high }
+	}
+
+	/// The setting in the middle of the range
+	pub fn midpoint(&self) -> Match {
+		Match::new(
+			(self.low.mixture + self.high.mixture) / 2.0,
+			(self.low.yellow + self.high.yellow) / 2.0,
+		)
+	}
+
+	/// The width of the range on the mixture scale
+	pub fn width(&self) -> f32 {
+		self.high.mixture - self.low.mixture
+	}
+
+	/// Whether a mixture lies within the range
+	pub fn contains(&self, mixture: f32) -> bool {
+		self.low.mixture <= mixture && mixture <= self.high.mixture
+	}
+
+	/// The change of the yellow brightness per unit of mixture across the range
+	pub fn yellow_slope(&self) -> f32 {
+		let width = self.width();
+		if width > 0.0 {
+			(self.high.yellow - self.low.yellow) / width
+		} else {
+			0.0
+		}
+	}
+}
+
+/// Grade of a Rayleigh match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Grade {
+	/// A narrow range around the normal match
+	Normal,
+	/// A narrow range displaced from the normal match, of a simple anomalous trichromat
+	Simple,
+	/// A wide range of an extreme anomalous trichromat
+	Extreme,
+	/// Any mixture matches, of a protanope or deuteranope
+	Anope,
+}
+
+/// The assessment of a measured match range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Assessment {
+	/// Anomalous quotient of the midpoint of the range
+	pub anomalous_quotient: f32,
+	/// Grade of the match
+	pub grade: Grade,
+	/// Classified color vision and its severity
+	pub classification: Classification,
+}
+
+impl Assessment {
+	/// The cone cells of the classified color vision
+	///
+	/// ```
+	/// use achroma::anomaloscope::{Anomaloscope, Pigments};
+	/// use achroma::ConeCellSummary;
+	///
+	/// let anomaloscope = Anomaloscope::default();
+	/// let range = anomaloscope.predict(Pigments::PROTANOPE).unwrap();
+	/// assert_eq!(anomaloscope.assess(&range).summary(), ConeCellSummary::PROTANOPIA);
+	/// ```
+	pub fn summary(&self) -> ConeCellSummary {
+		self.classification.vision.into()
+	}
+}
+
+/// A model of a Nagel-type anomaloscope, calibrated to the match of normal color vision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anomaloscope {
+	normal: Match,
+	red: f32,
+	yellow: f32,
+	threshold: f32,
+}
+
+impl Default for Anomaloscope {
+	/// An anomaloscope where normal color vision matches at a mixture of 40
+	/// and a yellow of 15, with a range 3 units wide
+	fn default() -> Self {
+		Self::new(Match::new(40.0, 15.0), 3.0)
+	}
+}
+
+impl Anomaloscope {
+	/// Creates a new anomaloscope, where normal color vision matches at a
+	/// setting with a range of mixtures of a given width.
+	///
+	/// The powers of the red and yellow primaries, relative to the green primary,
+	/// are calibrated such that the normal pigments match at the given setting.
+	pub fn new(normal: Match, width: f32) -> Self {
+		let mixture = normal.mixture / MIXTURE_SCALE;
+		let yellow = normal.yellow / YELLOW_SCALE;
+		let l = Sensitivity::new(L_PEAK);
+		let m = Sensitivity::new(M_PEAK);
+
+		// solve for the red and yellow powers, where the mixture matches the
+		// yellow light in both L and M cones
+		let rhs_l = -(1.0 - mixture) * l.green;
+		let rhs_m = -(1.0 - mixture) * m.green;
+		let det = -l.red * m.yellow + l.yellow * m.red;
+		let red = (-rhs_l * m.yellow + l.yellow * rhs_m) / det;
+		let yellow_power = (l.red * rhs_m - m.red * rhs_l) / det;
+
+		let mut anomaloscope = Self {
+			normal,
+			red: red / mixture,
+			yellow: yellow_power / yellow,
+			threshold: 0.0,
+		};
+		let edge = mixture + width / MIXTURE_SCALE / 2.0;
+		anomaloscope.threshold = libm::fabsf(
+			anomaloscope.opponency(l, m, edge) - Sensitivity::opponency_yellow(l, m),
+		);
+		anomaloscope
+	}
+
+	/// The match of normal color vision
+	pub const fn normal(&self) -> Match {
+		self.normal
+	}
+
+	/// Predicts the range of settings matched by a pigment configuration.
+	///
+	/// Returns [None] when no mixture on the scale matches, or when neither
+	/// L nor M cones are present, as the rods and S cones have no useful response
+	/// to the primaries.
+	///
+	/// ```
+	/// use achroma::anomaloscope::{Anomaloscope, Pigments, MIXTURE_SCALE};
+	///
+	/// let anomaloscope = Anomaloscope::default();
+	///
+	/// let normal = anomaloscope.predict(Pigments::NORMAL).unwrap();
+	/// assert!((normal.midpoint().mixture - 40.0).abs() < 0.5);
+	/// assert!((normal.width() - 3.0).abs() < 0.1);
+	///
+	/// // a protanope matches every mixture, with a dimmer yellow for redder mixtures
+	/// let protanope = anomaloscope.predict(Pigments::PROTANOPE).unwrap();
+	/// assert_eq!(protanope.width(), MIXTURE_SCALE);
+	/// assert!(protanope.high.yellow < protanope.low.yellow);
+	/// ```
+	pub fn predict(&self, pigments: Pigments) -> Option<MatchRange> {
+		let (l, m) = match (pigments.long, pigments.medium) {
+			(Some(long), Some(medium)) => {
+				(Sensitivity::new(long), Sensitivity::new(medium))
+			}
+			(Some(peak), None) | (None, Some(peak)) => {
+				let cone = Sensitivity::new(peak);
+				let yellow = |mixture| {
+					self.mixture_response(cone, mixture)
+						/ (self.yellow * cone.yellow)
+				};
+				return Some(MatchRange::new(
+					Match::new(0.0, yellow(0.0) * YELLOW_SCALE),
+					Match::new(MIXTURE_SCALE, yellow(1.0) * YELLOW_SCALE),
+				));
+			}
+			(None, None) => return None,
+		};
+
+		// the opponency (L - M) / (L + M) of the mixture is a ratio of
+		// linear functions, (a m + b) / (d m + e), and is monotonic in m
+		let (alpha_l, beta_l) = (self.red * l.red - l.green, l.green);
+		let (alpha_m, beta_m) = (self.red * m.red - m.green, m.green);
+		let (a, b) = (alpha_l - alpha_m, beta_l - beta_m);
+		let (d, e) = (alpha_l + alpha_m, beta_l + beta_m);
+
+		let target = Sensitivity::opponency_yellow(l, m);
+		let (lowest, highest) = (target - self.threshold, target + self.threshold);
+		let (at_green, at_red) = (b / e, (a + b) / (d + e));
+		let (min, max) = (at_green.min(at_red), at_green.max(at_red));
+		if max < lowest || min > highest {
+			return None;
+		}
+
+		let increasing = at_green < at_red;
+		let solve = |opponency: f32| {
+			if opponency <= min {
+				if increasing {
+					0.0
+				} else {
+					1.0
+				}
+			} else if opponency >= max {
+				if increasing {
+					1.0
+				} else {
+					0.0
+				}
+			} else {
+				((opponency * e - b) / (a - opponency * d)).clamp(0.0, 1.0)
+			}
+		};
+		let (first, second) = (solve(lowest), solve(highest));
+		let setting = |mixture: f32| {
+			let response = self.mixture_response(l, mixture)
+				+ self.mixture_response(m, mixture);
+			let yellow = response / (self.yellow * (l.yellow + m.yellow));
+			Match::new(mixture * MIXTURE_SCALE, yellow * YELLOW_SCALE)
+		};
+		Some(MatchRange::new(
+			setting(first.min(second)),
+			setting(first.max(second)),
+		))
+	}
+
+	/// Computes the anomalous quotient of a measured range, which compares
+	/// the red-green ratio of its midpoint to that of the normal match.
+	///
+	/// The quotient is 1.0 for the normal match, below 1.0 for protans who need
+	/// more red, and above 1.0 for deutans who need more green.
+	///
+	/// ```
+	/// use achroma::anomaloscope::{Anomaloscope, Match, MatchRange};
+	///
+	/// let anomaloscope = Anomaloscope::default();
+	/// let range = MatchRange::new(Match::new(39.0, 15.0), Match::new(41.0, 15.0));
+	/// assert!((anomaloscope.anomalous_quotient(&range) - 1.0).abs() < 1e-6);
+	/// ```
+	pub fn anomalous_quotient(&self, range: &MatchRange) -> f32 {
+		let ratio = |mixture: f32| (MIXTURE_SCALE - mixture) / mixture;
+		ratio(range.midpoint().mixture) / ratio(self.normal.mixture)
+	}
+
+	/// Assesses a measured range by its anomalous quotient and width, and
+	/// classifies it as protan or deutan.
+	///
+	/// The severity of anomalous trichromacy is estimated by finding the shift of
+	/// the anomalous pigment that predicts the same end of the range, away from
+	/// the normal match.
+	///
+	/// ```
+	/// use achroma::anomaloscope::{Anomaloscope, Grade, Match, MatchRange};
+	/// use achroma::ColorVision;
+	///
+	/// let anomaloscope = Anomaloscope::default();
+	///
+	/// // a range displaced towards red
+	/// let range = MatchRange::new(Match::new(52.0, 10.0), Match::new(56.0, 9.0));
+	/// let assessment = anomaloscope.assess(&range);
+	/// assert!(assessment.anomalous_quotient < 0.7);
+	/// assert_eq!(assessment.grade, Grade::Simple);
+	/// assert_eq!(assessment.classification.vision, ColorVision::Protanomaly);
+	/// ```
+	pub fn assess(&self, range: &MatchRange) -> Assessment {
+		let anomalous_quotient = self.anomalous_quotient(range);
+		let anope = range.low.mixture <= ANOPE_MARGIN
+			&& range.high.mixture >= MIXTURE_SCALE - ANOPE_MARGIN;
+
+		let (grade, vision) = if anope {
+			// protanopes need a much dimmer yellow for red mixtures, while
+			// the yellow of deuteranopes is nearly constant
+			let slope = range.yellow_slope();
+			let expected = |pigments| {
+				self.predict(pigments)
+					.map_or(0.0, |range| range.yellow_slope())
+			};
+			let protan = libm::fabsf(slope - expected(Pigments::PROTANOPE))
+				< libm::fabsf(slope - expected(Pigments::DEUTERANOPE));
+			let vision = if protan {
+				ColorVision::Protanopia
+			} else {
+				ColorVision::Deuteranopia
+			};
+			(Grade::Anope, vision)
+		} else if range.width() >= EXTREME_WIDTH {
+			let vision = if anomalous_quotient < 1.0 {
+				ColorVision::Protanomaly
+			} else {
+				ColorVision::Deuteranomaly
+			};
+			(Grade::Extreme, vision)
+		} else if anomalous_quotient < AQ_NORMAL_MIN {
+			(Grade::Simple, ColorVision::Protanomaly)
+		} else if anomalous_quotient > AQ_NORMAL_MAX {
+			(Grade::Simple, ColorVision::Deuteranomaly)
+		} else {
+			(Grade::Normal, ColorVision::Normal)
+		};
+
+		let severity = match grade {
+			Grade::Normal => 0.0,
+			Grade::Anope => 1.0,
+			Grade::Simple | Grade::Extreme => self.severity(vision, range),
+		};
+		Assessment {
+			anomalous_quotient,
+			grade,
+			classification: Classification::new(vision, severity),
+		}
+	}
+
+	/// Finds the severity of an anomalous pigment by bisection, where the far
+	/// end of the predicted range moves monotonically away from the normal match
+	fn severity(&self, vision: ColorVision, range: &MatchRange) -> f32 {
+		let protan = vision == ColorVision::Protanomaly;
+		let far_end = |range: MatchRange| {
+			if protan {
+				range.high.mixture
+			} else {
+				MIXTURE_SCALE - range.low.mixture
+			}
+		};
+		let target = far_end(*range);
+		let (mut low, mut high) = (0.0, 1.0);
+		for _ in 0..24 {
+			let severity = (low + high) / 2.0;
+			let pigments = if protan {
+				Pigments::protanomalous(severity)
+			} else {
+				Pigments::deuteranomalous(severity)
+			};
+			let predicted = self.predict(pigments).map_or(MIXTURE_SCALE, far_end);
+			if predicted < target {
+				low = severity;
+			} else {
+				high = severity;
+			}
+		}
+		(low + high) / 2.0
+	}
+
+	/// The response of a cone to a mixture from 0.0 (green) to 1.0 (red)
+	fn mixture_response(&self, cone: Sensitivity, mixture: f32) -> f32 {
+		mixture * self.red * cone.red + (1.0 - mixture) * cone.green
+	}
+
+	/// The opponency (L - M) / (L + M) of a mixture from 0.0 (green) to 1.0 (red)
+	fn opponency(&self, l: Sensitivity, m: Sensitivity, mixture: f32) -> f32 {
+		let l = self.mixture_response(l, mixture);
+		let m = self.mixture_response(m, mixture);
+		(l - m) / (l + m)
+	}
+}
+
+/// The sensitivity of a pigment at the three primaries
+#[derive(Debug, Clone, Copy)]
+struct Sensitivity {
+	red: f32,
+	green: f32,
+	yellow: f32,
+}
+
+impl Sensitivity {
+	fn new(peak: f32) -> Self {
+		Self {
+			red: govardovskii_a1(peak, RED_PRIMARY),
+			green: govardovskii_a1(peak, GREEN_PRIMARY),
+			yellow: govardovskii_a1(peak, YELLOW_PRIMARY),
+		}
+	}
+
+	/// The opponency (L - M) / (L + M) of the yellow light,
+	/// which doesn't depend on its brightness
+	fn opponency_yellow(l: Self, m: Self) -> f32 {
+		(l.yellow - m.yellow) / (l.yellow + m.yellow)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_normal_match() {
+		let anomaloscope = Anomaloscope::default();
+		let range = anomaloscope.predict(Pigments::NORMAL).unwrap();
+		let midpoint = range.midpoint();
+		assert!(libm::fabsf(midpoint.mixture - 40.0) < 0.5);
+		assert!(libm::fabsf(midpoint.yellow - 15.0) < 0.5);
+		assert!(libm::fabsf(range.width() - 3.0) < 0.1);
+
+		let assessment = anomaloscope.assess(&range);
+		assert_eq!(assessment.grade, Grade::Normal);
+		assert_eq!(assessment.classification, Classification::NORMAL);
+		assert_eq!(assessment.summary(), ConeCellSummary::NORMAL);
+	}
+
+	#[test]
+	fn test_protanomalous_match() {
+		let anomaloscope = Anomaloscope::default();
+		let normal = anomaloscope.predict(Pigments::NORMAL).unwrap();
+		let range = anomaloscope.predict(Pigments::protanomalous(0.5)).unwrap();
+
+		// more red and a dimmer yellow than normal
+		assert!(range.low.mixture > normal.high.mixture);
+		assert!(range.midpoint().yellow < normal.midpoint().yellow - 3.0);
+
+		let assessment = anomaloscope.assess(&range);
+		assert!(assessment.anomalous_quotient < AQ_NORMAL_MIN);
+		assert_eq!(assessment.grade, Grade::Simple);
+		assert_eq!(assessment.summary(), ConeCellSummary::PROTANOMALY);
+		assert!(libm::fabsf(assessment.classification.severity - 0.5) < 0.05);
+	}
+
+	#[test]
+	fn test_deuteranomalous_match() {
+		let anomaloscope = Anomaloscope::default();
+		let normal = anomaloscope.predict(Pigments::NORMAL).unwrap();
+		let range = anomaloscope
+			.predict(Pigments::deuteranomalous(0.3))
+			.unwrap();
+
+		// more green than normal, with about the same yellow
+		assert!(range.high.mixture < normal.low.mixture);
+		assert!(libm::fabsf(range.midpoint().yellow - normal.midpoint().yellow) < 1.0);
+
+		let assessment = anomaloscope.assess(&range);
+		assert!(assessment.anomalous_quotient > AQ_NORMAL_MAX);
+		assert_eq!(assessment.grade, Grade::Simple);
+		assert_eq!(assessment.summary(), ConeCellSummary::DEUTERANOMALY);
+		assert!(libm::fabsf(assessment.classification.severity - 0.3) < 0.05);
+	}
+
+	#[test]
+	fn test_extreme_anomalous_match() {
+		let anomaloscope = Anomaloscope::default();
+		for (pigments, vision) in [
+			(Pigments::protanomalous(0.95), ColorVision::Protanomaly),
+			(Pigments::deuteranomalous(0.9), ColorVision::Deuteranomaly),
+		] {
+			let range = anomaloscope.predict(pigments).unwrap();
+			let assessment = anomaloscope.assess(&range);
+			assert_eq!(assessment.grade, Grade::Extreme);
+			assert_eq!(assessment.classification.vision, vision);
+			assert!(assessment.classification.severity > 0.85);
+		}
+	}
+
+	#[test]
+	fn test_anope_match() {
+		let anomaloscope = Anomaloscope::default();
+		for (pigments, summary) in [
+			(Pigments::PROTANOPE, ConeCellSummary::PROTANOPIA),
+			(Pigments::DEUTERANOPE, ConeCellSummary::DEUTERANOPIA),
+		] {
+			let range = anomaloscope.predict(pigments).unwrap();
+			let assessment = anomaloscope.assess(&range);
+			assert_eq!(assessment.grade, Grade::Anope);
+			assert_eq!(assessment.summary(), summary);
+			assert_eq!(assessment.classification.severity, 1.0);
+		}
+	}
+
+	#[test]
+	fn test_no_match() {
+		let anomaloscope = Anomaloscope::default();
+		assert_eq!(anomaloscope.predict(Pigments::new(None, None)), None);
+	}
+
+	#[test]
+	fn test_severity_is_monotonic() {
+		let anomaloscope = Anomaloscope::default();
+		let mut previous = 0.0;
+		for step in 1..10 {
+			let severity = step as f32 / 10.0;
+			let range = anomaloscope
+				.predict(Pigments::protanomalous(severity))
+				.unwrap();
+			let estimated = anomaloscope.severity(ColorVision::Protanomaly, &range);
+			assert!(estimated > previous);
+			assert!(libm::fabsf(estimated - severity) < 0.02);
+			previous = estimated;
+		}
+	}
+}
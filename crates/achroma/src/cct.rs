@@ -0,0 +1,501 @@
+//! Results of the Cambridge Colour Test (CCT)
+//!
+//! The CCT measures the smallest chromaticity difference at which a subject
+//! can find the gap of a Landolt C, shown in a field of luminance noise.
+//! The trivector test measures thresholds along the protan, deutan and tritan
+//! confusion lines, and the ellipse test measures discrimination ellipses
+//! around several reference chromaticities.
+//!
+//! Thresholds and ellipse axes are given in units of 10⁻⁴ in the CIE 1976
+//! u'v' chromaticity diagram, as reported by the test, and are compared to
+//! limits that grow with age.
+//!
+//! No normative limits are bundled. Published norms, such as those of
+//! Paramei (2012) and Ventura et al. (2003), were measured on specific
+//! monitors and viewing setups, and thresholds don't transfer between
+//! setups, so callers supply their own table to [Norms::new].
+//!
+//! ```
+//! use achroma::cct::{AgeLimit, Norms, Trivector};
+//! use achroma::ColorVision;
+//!
+//! // upper limits of normal trichromats measured on the same monitor
+//! let rows = [
+//!     AgeLimit::new(20, Trivector::new(75.0, 75.0, 110.0)),
+//!     AgeLimit::new(80, Trivector::new(150.0, 150.0, 265.0)),
+//! ];
+//! let norms = Norms::new(&rows).unwrap();
+//!
+//! let result = Trivector::new(420.0, 180.0, 70.0);
+//! let classification = result.classify(32, &norms);
+//! assert_eq!(classification.vision, ColorVision::Protanomaly);
+//! ```
+
+use crate::{Classification, ColorVision};
+use core::f32::consts::PI;
+
+/// The largest vector the test can display, which depends on the gamut of
+/// the monitor, and is reached by dichromats along their confusion line
+pub const CEILING: f32 = 1100.0;
+
+/// The lowest ratio between the major and minor axes of an ellipse
+/// that indicates a confusion axis
+pub const ELONGATION_LIMIT: f32 = 2.0;
+
+/// An axis along which thresholds are measured, towards the copunctal point
+/// of a type of dichromacy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+	/// Along protan confusion lines
+	Protan,
+	/// Along deutan confusion lines
+	Deutan,
+	/// Along tritan confusion lines
+	Tritan,
+}
+
+impl Axis {
+	/// All axes of the trivector test
+	pub const ALL: [Self; 3] = [Self::Protan, Self::Deutan, Self::Tritan];
+
+	/// The copunctal point of the axis in u'v' coordinates, where the
+	/// confusion lines converge
+	///
+	/// ```
+	/// use achroma::cct::Axis;
+	///
+	/// assert_eq!(Axis::Tritan.copunctal(), [0.257, 0.0]);
+	/// ```
+	pub const fn copunctal(&self) -> [f32; 2] {
+		match self {
+			Self::Protan => [0.678, 0.501],
+			Self::Deutan => [-1.217, 0.782],
+			Self::Tritan => [0.257, 0.0],
+		}
+	}
+
+	/// The anomalous trichromacy and dichromacy along the axis
+	const fn visions(&self) -> (ColorVision, ColorVision) {
+		match self {
+			Self::Protan => (ColorVision::Protanomaly, ColorVision::Protanopia),
+			Self::Deutan => (ColorVision::Deuteranomaly, ColorVision::Deuteranopia),
+			Self::Tritan => (ColorVision::Tritanomaly, ColorVision::Tritanopia),
+		}
+	}
+}
+
+/// Thresholds of the trivector test, in units of 10⁻⁴ u'v'
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Trivector {
+	/// Threshold along the protan axis
+	pub protan: f32,
+	/// Threshold along the deutan axis
+	pub deutan: f32,
+	/// Threshold along the tritan axis
+	pub tritan: f32,
+}
+
+impl Trivector {
+	/// Creates a new result from protan, deutan and tritan thresholds
+	pub const fn new(protan: f32, deutan: f32, tritan: f32) -> Self {
+		Self {
+			protan,
+			deutan,
+			tritan,
+		}
+	}
+
+	/// Gets the threshold along an axis
+	pub const fn get(&self, axis: Axis) -> f32 {
+		match axis {
+			Axis::Protan => self.protan,
+			Axis::Deutan => self.deutan,
+			Axis::Tritan => self.tritan,
+		}
+	}
+
+	/// The axes whose thresholds exceed the limits of an age
+	///
+	/// ```
+	/// use achroma::cct::{AgeLimit, Norms, Trivector};
+	///
+	/// let rows = [
+	///     AgeLimit::new(20, Trivector::new(75.0, 75.0, 110.0)),
+	///     AgeLimit::new(80, Trivector::new(150.0, 150.0, 265.0)),
+	/// ];
+	/// let norms = Norms::new(&rows).unwrap();
+	/// let result = Trivector::new(60.0, 70.0, 300.0);
+	/// assert_eq!(result.elevated(25, &norms), [false, false, true]);
+	/// ```
+	pub fn elevated(&self, age: u8, norms: &Norms) -> [bool; 3] {
+		let limits = norms.limits(age);
+		Axis::ALL.map(|axis| self.get(axis) > limits.get(axis))
+	}
+
+	/// Classifies the thresholds of a subject of an age.
+	///
+	/// The type of color vision follows the axis that exceeds its limit by
+	/// the largest ratio, and the severity grows from 0.0 at the limit to
+	/// 1.0 at the [CEILING], which is classified as dichromacy.
+	/// When every axis is elevated, the result is classified as achromatomaly,
+	/// or achromatopsia when every axis reaches the ceiling.
+	///
+	/// ```
+	/// use achroma::cct::{AgeLimit, Norms, Trivector, CEILING};
+	/// use achroma::{Classification, ColorVision};
+	///
+	/// let rows = [
+	///     AgeLimit::new(20, Trivector::new(75.0, 75.0, 110.0)),
+	///     AgeLimit::new(80, Trivector::new(150.0, 150.0, 265.0)),
+	/// ];
+	/// let norms = Norms::new(&rows).unwrap();
+	///
+	/// let normal = Trivector::new(45.0, 50.0, 80.0);
+	/// assert_eq!(normal.classify(25, &norms), Classification::NORMAL);
+	///
+	/// let deuteranope = Trivector::new(600.0, CEILING, 90.0);
+	/// let classification = deuteranope.classify(25, &norms);
+	/// assert_eq!(classification, Classification::new(ColorVision::Deuteranopia, 1.0));
+	/// ```
+	pub fn classify(&self, age: u8, norms: &Norms) -> Classification {
+		let limits = norms.limits(age);
+		let severities = Axis::ALL.map(|axis| severity(self.get(axis), limits.get(axis)));
+		let ratios = Axis::ALL.map(|axis| self.get(axis) / limits.get(axis));
+
+		if ratios.iter().all(|&ratio| ratio > 1.0) {
+			if severities.iter().all(|&severity| severity >= 1.0) {
+				return Classification::new(ColorVision::Achromatopsia, 1.0);
+			}
+			let severity = severities.iter().sum::<f32>() / 3.0;
+			return Classification::new(ColorVision::Achromatomaly, severity);
+		}
+
+		let mut worst = 0;
+		for index in 1..ratios.len() {
+			if ratios[index] > ratios[worst] {
+				worst = index;
+			}
+		}
+		if ratios[worst] <= 1.0 {
+			return Classification::NORMAL;
+		}
+		classification(Axis::ALL[worst], severities[worst])
+	}
+}
+
+/// A discrimination ellipse of the ellipse test
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Ellipse {
+	/// Reference chromaticity at the center of the ellipse, in u'v' coordinates
+	pub center: [f32; 2],
+	/// Length of the semi-major axis, in units of 10⁻⁴ u'v'
+	pub major: f32,
+	/// Length of the semi-minor axis, in units of 10⁻⁴ u'v'
+	pub minor: f32,
+	/// Angle of the major axis in degrees, counterclockwise from the u' axis
+	pub angle: f32,
+}
+
+impl Ellipse {
+	/// Creates a new ellipse around a reference chromaticity
+	pub const fn new(center: [f32; 2], major: f32, minor: f32, angle: f32) -> Self {
+		Self {
+			center,
+			major,
+			minor,
+			angle,
+		}
+	}
+
+	/// The ratio between the major and minor axes
+	pub fn elongation(&self) -> f32 {
+		self.major / self.minor
+	}
+
+	/// The confusion axis most aligned with the major axis of the ellipse
+	///
+	/// ```
+	/// use achroma::cct::{Axis, Ellipse};
+	///
+	/// // an ellipse pointing at the tritan copunctal point, almost vertical in u'v'
+	/// let ellipse = Ellipse::new([0.2, 0.48], 400.0, 40.0, 95.0);
+	/// assert_eq!(ellipse.axis(), Axis::Tritan);
+	/// ```
+	pub fn axis(&self) -> Axis {
+		let mut best = (Axis::Protan, f32::INFINITY);
+		for axis in Axis::ALL {
+			let offset = libm::fabsf(self.angle - self.confusion_angle(axis)) % 180.0;
+			let offset = offset.min(180.0 - offset);
+			if offset < best.1 {
+				best = (axis, offset);
+			}
+		}
+		best.0
+	}
+
+	/// The angle in degrees of the confusion line of an axis through the center of the ellipse
+	pub fn confusion_angle(&self, axis: Axis) -> f32 {
+		let [u, v] = axis.copunctal();
+		libm::atan2f(v - self.center[1], u - self.center[0]) * (180.0 / PI)
+	}
+
+	/// Classifies the ellipse of a subject of an age.
+	///
+	/// An elongated ellipse is classified by its most aligned confusion axis,
+	/// with a severity from the length of its major axis as in
+	/// [Trivector::classify], while an enlarged but round ellipse is classified
+	/// as achromatomaly.
+	///
+	/// ```
+	/// use achroma::cct::{AgeLimit, Ellipse, Norms, Trivector};
+	/// use achroma::ColorVision;
+	///
+	/// let rows = [
+	///     AgeLimit::new(20, Trivector::new(75.0, 75.0, 110.0)),
+	///     AgeLimit::new(80, Trivector::new(150.0, 150.0, 265.0)),
+	/// ];
+	/// let norms = Norms::new(&rows).unwrap();
+	///
+	/// let ellipse = Ellipse::new([0.2, 0.48], 400.0, 40.0, 95.0);
+	/// let classification = ellipse.classify(40, &norms);
+	/// assert_eq!(classification.vision, ColorVision::Tritanomaly);
+	/// ```
+	pub fn classify(&self, age: u8, norms: &Norms) -> Classification {
+		let limits = norms.limits(age);
+		if self.elongation() >= ELONGATION_LIMIT {
+			let axis = self.axis();
+			let limit = limits.get(axis);
+			if self.major > limit {
+				return classification(axis, severity(self.major, limit));
+			}
+			return Classification::NORMAL;
+		}
+
+		let limit = limits.protan.min(limits.deutan).min(limits.tritan);
+		if self.minor > limit {
+			let severity = severity(self.major, limit);
+			return Classification::new(ColorVision::Achromatomaly, severity);
+		}
+		Classification::NORMAL
+	}
+}
+
+/// Classifies a set of ellipses of a subject of an age by their most severe
+/// classification, or [None] when there are no ellipses
+pub fn classify_ellipses(ellipses: &[Ellipse], age: u8, norms: &Norms) -> Option<Classification> {
+	ellipses.iter()
+		.map(|ellipse| ellipse.classify(age, norms))
+		.fold(
+			None,
+			|worst: Option<Classification>, classification| match worst {
+				Some(worst) if worst.severity >= classification.severity => {
+					Some(worst)
+				}
+				_ => Some(classification),
+			},
+		)
+}
+
+/// The upper limits of normal thresholds at an age
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgeLimit {
+	/// Age in years
+	pub age: u8,
+	/// Upper limits of normal thresholds
+	pub limits: Trivector,
+}
+
+impl AgeLimit {
+	/// Creates a new set of limits at an age
+	pub const fn new(age: u8, limits: Trivector) -> Self {
+		Self { age, limits }
+	}
+}
+
+/// Normative upper limits of thresholds, interpolated between ages
+///
+/// Thresholds depend on the monitor, its calibration and the viewing
+/// conditions, so the limits should come from normal trichromats measured
+/// on the same equipment as the subjects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Norms<'a> {
+	rows: &'a [AgeLimit],
+}
+
+impl<'a> Norms<'a> {
+	/// Creates new norms from limits in ascending order of age,
+	/// or [None] when there are no rows or they are out of order
+	///
+	/// ```
+	/// use achroma::cct::{AgeLimit, Norms, Trivector};
+	///
+	/// let rows = [
+	///     AgeLimit::new(20, Trivector::new(60.0, 60.0, 90.0)),
+	///     AgeLimit::new(60, Trivector::new(100.0, 100.0, 150.0)),
+	/// ];
+	/// let norms = Norms::new(&rows).unwrap();
+	/// assert_eq!(norms.limits(40), Trivector::new(80.0, 80.0, 120.0));
+	///
+	/// assert_eq!(Norms::new(&[]), None);
+	/// ```
+	pub fn new(rows: &'a [AgeLimit]) -> Option<Self> {
+		let ascending = rows.windows(2).all(|pair| pair[0].age < pair[1].age);
+		(!rows.is_empty() && ascending).then_some(Self { rows })
+	}
+
+	/// The rows of limits in ascending order of age
+	pub const fn rows(&self) -> &'a [AgeLimit] {
+		self.rows
+	}
+
+	/// The limits at an age, interpolated linearly between rows,
+	/// and clamped to the youngest and oldest rows
+	pub fn limits(&self, age: u8) -> Trivector {
+		let index = self.rows.partition_point(|row| row.age <= age);
+		if index == 0 {
+			return self.rows[0].limits;
+		}
+		let below = self.rows[index - 1];
+		let Some(above) = self.rows.get(index) else {
+			return below.limits;
+		};
+
+		let t = (age - below.age) as f32 / (above.age - below.age) as f32;
+		let lerp = |axis| {
+			below.limits.get(axis)
+				+ t * (above.limits.get(axis) - below.limits.get(axis))
+		};
+		Trivector::new(lerp(Axis::Protan), lerp(Axis::Deutan), lerp(Axis::Tritan))
+	}
+}
+
+/// The severity of a threshold from 0.0 at the limit to 1.0 at the ceiling
+fn severity(threshold: f32, limit: f32) -> f32 {
+	if limit >= CEILING {
+		return 0.0;
+	}
+	((threshold - limit) / (CEILING - limit)).clamp(0.0, 1.0)
+}
+
+fn classification(axis: Axis, severity: f32) -> Classification {
+	let (anomaly, dichromacy) = axis.visions();
+	if severity >= 1.0 {
+		Classification::new(dichromacy, 1.0)
+	} else {
+		Classification::new(anomaly, severity)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const ROWS: [AgeLimit; 8] = [
+		AgeLimit::new(10, Trivector::new(90.0, 90.0, 130.0)),
+		AgeLimit::new(20, Trivector::new(75.0, 75.0, 110.0)),
+		AgeLimit::new(30, Trivector::new(75.0, 75.0, 115.0)),
+		AgeLimit::new(40, Trivector::new(80.0, 80.0, 125.0)),
+		AgeLimit::new(50, Trivector::new(90.0, 90.0, 145.0)),
+		AgeLimit::new(60, Trivector::new(105.0, 105.0, 175.0)),
+		AgeLimit::new(70, Trivector::new(125.0, 125.0, 215.0)),
+		AgeLimit::new(80, Trivector::new(150.0, 150.0, 265.0)),
+	];
+
+	fn norms() -> Norms<'static> {
+		Norms::new(&ROWS).unwrap()
+	}
+
+	#[test]
+	fn test_limits_interpolation() {
+		let norms = norms();
+		assert_eq!(norms.limits(0), norms.limits(10));
+		assert_eq!(norms.limits(99), norms.limits(80));
+		assert_eq!(norms.limits(25), Trivector::new(75.0, 75.0, 112.5));
+		assert_eq!(norms.limits(65).tritan, 195.0);
+	}
+
+	#[test]
+	fn test_norms_out_of_order() {
+		let rows = [
+			AgeLimit::new(60, Trivector::default()),
+			AgeLimit::new(20, Trivector::default()),
+		];
+		assert_eq!(Norms::new(&rows), None);
+	}
+
+	#[test]
+	fn test_age_dependent_classification() {
+		// elevated for a young adult, but within the limits of old age
+		let result = Trivector::new(60.0, 65.0, 190.0);
+		assert_eq!(
+			result.classify(25, &norms()).vision,
+			ColorVision::Tritanomaly
+		);
+		assert_eq!(result.classify(75, &norms()), Classification::NORMAL);
+	}
+
+	#[test]
+	fn test_trivector_severity() {
+		let limits = norms().limits(30);
+		let half = limits.protan + (CEILING - limits.protan) / 2.0;
+		let result = Trivector::new(half, 200.0, 90.0);
+		let classification = result.classify(30, &norms());
+		assert_eq!(classification.vision, ColorVision::Protanomaly);
+		assert!(libm::fabsf(classification.severity - 0.5) < 1e-6);
+
+		let protanope = Trivector::new(CEILING, 900.0, 100.0);
+		let classification = protanope.classify(30, &norms());
+		assert_eq!(
+			classification,
+			Classification::new(ColorVision::Protanopia, 1.0)
+		);
+	}
+
+	#[test]
+	fn test_achromatic_classification() {
+		let result = Trivector::new(300.0, 300.0, 400.0);
+		assert_eq!(
+			result.classify(30, &norms()).vision,
+			ColorVision::Achromatomaly
+		);
+
+		let result = Trivector::new(CEILING, CEILING, CEILING);
+		assert_eq!(
+			result.classify(30, &norms()),
+			Classification::new(ColorVision::Achromatopsia, 1.0)
+		);
+	}
+
+	#[test]
+	fn test_ellipse_axis() {
+		let center = [0.1977, 0.4689];
+		let ellipse = |axis: Axis| {
+			let angle = Ellipse::new(center, 0.0, 0.0, 0.0).confusion_angle(axis);
+			Ellipse::new(center, 500.0, 60.0, angle + 180.0)
+		};
+		for axis in Axis::ALL {
+			assert_eq!(ellipse(axis).axis(), axis);
+		}
+		assert_eq!(
+			ellipse(Axis::Deutan).classify(30, &norms()).vision,
+			ColorVision::Deuteranomaly
+		);
+	}
+
+	#[test]
+	fn test_ellipse_classification() {
+		let norms = norms();
+		let small = Ellipse::new([0.1977, 0.4689], 40.0, 30.0, 10.0);
+		assert_eq!(small.classify(30, &norms), Classification::NORMAL);
+
+		let round = Ellipse::new([0.1977, 0.4689], 300.0, 250.0, 10.0);
+		assert_eq!(
+			round.classify(30, &norms).vision,
+			ColorVision::Achromatomaly
+		);
+
+		let worst = classify_ellipses(&[small, round], 30, &norms).unwrap();
+		assert_eq!(worst.vision, ColorVision::Achromatomaly);
+		assert_eq!(classify_ellipses(&[], 30, &norms), None);
+	}
+}
@@ -0,0 +1,348 @@
+//! Cone contrasts, and cone isolating stimuli by silent substitution
+//!
+//! The cone contrast of a test color against a background is the relative
+//! change in the excitation of each type of cone cell, `(test - background) /
+//! background`. A display is characterized by the matrix from its linear RGB
+//! to the excitations of the L, M and S cone cells, either given directly,
+//! or computed from the measured spectra of its primaries.
+//!
+//! Silent substitution finds colors which change the excitation of only one
+//! type of cone cell, and leave the others silent, so that an experiment can
+//! probe each type of cone cell in isolation. The contrast that a display can
+//! reach is limited by its gamut, which is narrow for S cone isolation and
+//! narrower still for L and M cone isolation.
+//!
+//! ```
+//! use achroma::cone_contrast::Characterization;
+//! use achroma::ConeCell;
+//!
+//! let display = Characterization::SRGB;
+//! let gray = [0.5, 0.5, 0.5];
+//!
+//! // an L cone isolating color at 5% contrast
+//! let test = display.isolate(gray, ConeCell::Long, 0.05).unwrap();
+//! let contrast = display.contrast(gray, test);
+//! assert!((contrast[ConeCell::Long] - 0.05).abs() < 1e-4);
+//! assert!(contrast[ConeCell::Medium].abs() < 1e-4);
+//! assert!(contrast[ConeCell::Short].abs() < 1e-4);
+//! ```
+
+use core::ops::{Index, RangeInclusive};
+
+use crate::alpha_opic::{Receptor, Spectrum};
+use crate::simulation::{mul, Matrix3};
+use crate::ConeCell;
+
+/// The tolerance of the gamut checks, for rounding errors
+const GAMUT_TOLERANCE: f32 = 1e-6;
+
+/// An error when solving for a cone isolating color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsolationError {
+	/// The characterization can't be inverted, as its primaries don't
+	/// excite the cone cells independently
+	Singular,
+	/// The background is outside the gamut, or doesn't excite the cone cell
+	Background,
+	/// The contrast is outside the range the display can reach, which is given
+	OutOfGamut {
+		/// The lowest reachable contrast, which is negative
+		min: f32,
+		/// The highest reachable contrast
+		max: f32,
+	},
+}
+
+/// The contrast of each type of cone cell
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConeContrast {
+	cones: [f32; 3],
+}
+
+impl ConeContrast {
+	/// Creates new cone contrasts of the L, M and S cone cells
+	pub const fn new(long: f32, medium: f32, short: f32) -> Self {
+		Self {
+			cones: [long, medium, short],
+		}
+	}
+
+	/// The length of the contrast vector, a common measure of the strength
+	/// of a stimulus which excites several types of cone cells
+	///
+	/// ```
+	/// use achroma::cone_contrast::ConeContrast;
+	///
+	/// assert_eq!(ConeContrast::new(0.03, 0.04, 0.0).magnitude(), 0.05);
+	/// ```
+	pub fn magnitude(&self) -> f32 {
+		libm::sqrtf(self.cones.iter().map(|c| c * c).sum())
+	}
+}
+
+impl Index<ConeCell> for ConeContrast {
+	type Output = f32;
+
+	/// Index the contrasts by [ConeCell]
+	fn index(&self, cone: ConeCell) -> &Self::Output {
+		&self.cones[row(cone)]
+	}
+}
+
+/// A display, characterized by the matrix from its linear RGB to the
+/// excitations of the L, M and S cone cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Characterization {
+	matrix: Matrix3,
+}
+
+impl Characterization {
+	/// A standard sRGB display, following the matrix of Viénot, Brettel &
+	/// Mollon, "Digital video colourmaps for checking the legibility of
+	/// displays by dichromats", Color Research & Application 24 (1999)
+	pub const SRGB: Self = Self::new([
+		[17.8824, 43.5161, 4.11935],
+		[3.45565, 27.1554, 3.86714],
+		[0.0299566, 0.184309, 1.46709],
+	]);
+
+	/// Creates a new characterization from the matrix from linear RGB to the
+	/// excitations of the L, M and S cone cells, in rows
+	pub const fn new(matrix: Matrix3) -> Self {
+		Self { matrix }
+	}
+
+	/// Creates a new characterization from the measured spectra of the red,
+	/// green and blue primaries at full intensity, using the cone-opic
+	/// [action spectra](crate::alpha_opic)
+	///
+	/// ```
+	/// use achroma::alpha_opic::Spectrum;
+	/// use achroma::cone_contrast::Characterization;
+	/// use achroma::ConeCell;
+	///
+	/// // narrowband primaries, sampled every 10 nm from 400 nm
+	/// let mut red = [0.0; 31];
+	/// let mut green = [0.0; 31];
+	/// let mut blue = [0.0; 31];
+	/// (red[21], green[14], blue[5]) = (1.0, 1.0, 1.0);
+	/// let spectrum = |values| Spectrum::new(400.0, 10.0, values);
+	/// let primaries = [spectrum(&red), spectrum(&green), spectrum(&blue)];
+	/// let display = Characterization::from_primaries(&primaries);
+	///
+	/// // silent substitution works with any three independent primaries
+	/// assert!(display.isolate([0.5; 3], ConeCell::Medium, 0.1).is_ok());
+	/// ```
+	pub fn from_primaries(primaries: &[Spectrum<'_>; 3]) -> Self {
+		let mut matrix = [[0.0; 3]; 3];
+		for cone in [ConeCell::Long, ConeCell::Medium, ConeCell::Short] {
+			for (value, primary) in matrix[row(cone)].iter_mut().zip(primaries) {
+				*value = primary.irradiance_of(Receptor::Cone(cone));
+			}
+		}
+		Self { matrix }
+	}
+
+	/// The matrix from linear RGB to the excitations of the L, M and S cone cells
+	pub const fn matrix(&self) -> Matrix3 {
+		self.matrix
+	}
+
+	/// The excitations of the L, M and S cone cells by a linear RGB color
+	pub fn lms(&self, rgb: [f32; 3]) -> [f32; 3] {
+		mul(&self.matrix, rgb)
+	}
+
+	/// The linear RGB color which gives excitations of the L, M and S cone
+	/// cells, which may be outside the gamut, or [None] when the
+	/// characterization can't be inverted
+	pub fn rgb(&self, lms: [f32; 3]) -> Option<[f32; 3]> {
+		Some(mul(&invert(&self.matrix)?, lms))
+	}
+
+	/// The cone contrasts of a test color against a background color, both
+	/// in linear RGB. A cone cell which the background doesn't excite has a
+	/// contrast of 0.0.
+	pub fn contrast(&self, background: [f32; 3], test: [f32; 3]) -> ConeContrast {
+		let background = self.lms(background);
+		let test = self.lms(test);
+		let mut contrast = ConeContrast::default();
+		for ((value, test), background) in
+			contrast.cones.iter_mut().zip(test).zip(background)
+		{
+			if background > 0.0 {
+				*value = (test - background) / background;
+			}
+		}
+		contrast
+	}
+
+	/// The range of contrasts of a cone cell that colors isolating it can
+	/// reach within the gamut, around a background color in linear RGB
+	///
+	/// ```
+	/// use achroma::cone_contrast::Characterization;
+	/// use achroma::ConeCell;
+	///
+	/// let display = Characterization::SRGB;
+	/// let s = display.isolating_range([0.5; 3], ConeCell::Short).unwrap();
+	/// let l = display.isolating_range([0.5; 3], ConeCell::Long).unwrap();
+	/// // S cones can be isolated at much higher contrasts than L cones
+	/// assert!(*s.end() > 0.5 && *l.end() < 0.2);
+	/// ```
+	pub fn isolating_range(
+		&self,
+		background: [f32; 3],
+		cone: ConeCell,
+	) -> Result<RangeInclusive<f32>, IsolationError> {
+		if !in_gamut(background) {
+			return Err(IsolationError::Background);
+		}
+		let excitation = self.lms(background)[row(cone)];
+		if excitation <= 0.0 {
+			return Err(IsolationError::Background);
+		}
+		let inverse = invert(&self.matrix).ok_or(IsolationError::Singular)?;
+
+		// the change of linear RGB per unit of contrast
+		let mut unit = [0.0; 3];
+		unit[row(cone)] = excitation;
+		let direction = mul(&inverse, unit);
+
+		let (mut min, mut max) = (f32::NEG_INFINITY, f32::INFINITY);
+		for (value, change) in background.into_iter().zip(direction) {
+			if change > 0.0 {
+				max = max.min((1.0 - value) / change);
+				min = min.max(-value / change);
+			} else if change < 0.0 {
+				max = max.min(-value / change);
+				min = min.max((1.0 - value) / change);
+			}
+		}
+		Ok(min..=max)
+	}
+
+	/// Solves for the color in linear RGB that changes the excitation of only
+	/// one cone cell by a contrast, around a background color in linear RGB
+	pub fn isolate(
+		&self,
+		background: [f32; 3],
+		cone: ConeCell,
+		contrast: f32,
+	) -> Result<[f32; 3], IsolationError> {
+		let range = self.isolating_range(background, cone)?;
+		if !range.contains(&contrast) {
+			return Err(IsolationError::OutOfGamut {
+				min: *range.start(),
+				max: *range.end(),
+			});
+		}
+		let mut lms = self.lms(background);
+		lms[row(cone)] *= 1.0 + contrast;
+		let rgb = self.rgb(lms).ok_or(IsolationError::Singular)?;
+		Ok(rgb.map(|c| c.clamp(0.0, 1.0)))
+	}
+}
+
+impl Default for Characterization {
+	fn default() -> Self {
+		Self::SRGB
+	}
+}
+
+/// The row of a cone cell in the matrices and contrasts
+const fn row(cone: ConeCell) -> usize {
+	match cone {
+		ConeCell::Long => 0,
+		ConeCell::Medium => 1,
+		ConeCell::Short => 2,
+	}
+}
+
+/// Reports if a linear RGB color is inside the gamut
+fn in_gamut(rgb: [f32; 3]) -> bool {
+	rgb.iter()
+		.all(|&c| (-GAMUT_TOLERANCE..=1.0 + GAMUT_TOLERANCE).contains(&c))
+}
+
+/// Inverts a matrix, or returns [None] when it's singular
+pub(crate) fn invert(m: &Matrix3) -> Option<Matrix3> {
+	let cofactor = |row: usize, col: usize| {
+		let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+		let (c0, c1) = ((col + 1) % 3, (col + 2) % 3);
+		m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+	};
+	let determinant: f32 = (0..3).map(|col| m[0][col] * cofactor(0, col)).sum();
+	let scale: f32 = m.iter().flatten().map(|v| v * v).sum();
+	if libm::fabsf(determinant) <= 1e-9 * scale * libm::sqrtf(scale) {
+		return None;
+	}
+	let mut inverse = [[0.0; 3]; 3];
+	for (row, values) in inverse.iter_mut().enumerate() {
+		for (col, value) in values.iter_mut().enumerate() {
+			*value = cofactor(col, row) / determinant;
+		}
+	}
+	Some(inverse)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_invert() {
+		let matrix = Characterization::SRGB.matrix();
+		let inverse = invert(&matrix).unwrap();
+		for (row, _) in matrix.iter().enumerate() {
+			let mut unit = [0.0; 3];
+			unit[row] = 1.0;
+			let back = mul(&matrix, mul(&inverse, unit));
+			for (a, b) in back.iter().zip(unit) {
+				assert!((a - b).abs() < 1e-4);
+			}
+		}
+		assert_eq!(
+			invert(&[[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 1.0, 0.0]]),
+			None
+		);
+	}
+
+	#[test]
+	fn test_isolating_range_edges() {
+		let display = Characterization::SRGB;
+		for cone in [ConeCell::Long, ConeCell::Medium, ConeCell::Short] {
+			let range = display.isolating_range([0.5; 3], cone).unwrap();
+			assert!(*range.start() < 0.0 && *range.end() > 0.0);
+			let edge = display.isolate([0.5; 3], cone, *range.end()).unwrap();
+			assert!(edge.iter().any(|&c| !(1e-4..=1.0 - 1e-4).contains(&c)));
+			assert_eq!(
+				display.isolate([0.5; 3], cone, *range.end() + 0.01),
+				Err(IsolationError::OutOfGamut {
+					min: *range.start(),
+					max: *range.end(),
+				})
+			);
+		}
+	}
+
+	#[test]
+	fn test_background_errors() {
+		let display = Characterization::SRGB;
+		assert_eq!(
+			display.isolate([0.0; 3], ConeCell::Short, 0.1),
+			Err(IsolationError::Background)
+		);
+		assert_eq!(
+			display.isolate([1.5, 0.5, 0.5], ConeCell::Long, 0.1),
+			Err(IsolationError::Background)
+		);
+		let singular = Characterization::new([[1.0, 1.0, 1.0]; 3]);
+		assert_eq!(
+			singular.isolate([0.5; 3], ConeCell::Long, 0.1),
+			Err(IsolationError::Singular)
+		);
+	}
+}
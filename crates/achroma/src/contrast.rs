@@ -0,0 +1,179 @@
+//! Luminance contrast between colors, as defined by [WCAG 2][wcag-contrast]
+//! and the [Accessible Perceptual Contrast Algorithm (APCA)][apca]
+//!
+//! ```
+//! use achroma::contrast::{contrast_ratio_srgb8, WCAG_AA};
+//!
+//! let ratio = contrast_ratio_srgb8([0, 0, 0], [255, 255, 255]);
+//! assert!((ratio - 21.0).abs() < 1e-4);
+//! assert!(ratio >= WCAG_AA);
+//! ```
+//!
+//! [wcag-contrast]: <https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio>
+//! [apca]: <https://github.com/Myndex/apca-w3>
+
+use crate::simulation::{decode_srgb8, LUMINANCE};
+
+/// Minimum contrast ratio of normal text for WCAG level AA
+pub const WCAG_AA: f32 = 4.5;
+
+/// Minimum contrast ratio of large text for WCAG level AA
+pub const WCAG_AA_LARGE: f32 = 3.0;
+
+/// Minimum contrast ratio of normal text for WCAG level AAA
+pub const WCAG_AAA: f32 = 7.0;
+
+/// Minimum contrast ratio of large text for WCAG level AAA
+pub const WCAG_AAA_LARGE: f32 = 4.5;
+
+/// Minimum APCA lightness contrast (Lc) for body text
+pub const APCA_BODY_TEXT: f32 = 75.0;
+
+/// Minimum APCA lightness contrast (Lc) for content text that isn't body text
+pub const APCA_CONTENT_TEXT: f32 = 60.0;
+
+/// Minimum APCA lightness contrast (Lc) for large or bold text
+pub const APCA_LARGE_TEXT: f32 = 45.0;
+
+/// The relative luminance of a linear sRGB color, from 0.0 (black)
+/// to 1.0 (white)
+pub fn relative_luminance(rgb: [f32; 3]) -> f32 {
+	LUMINANCE[0] * rgb[0] + LUMINANCE[1] * rgb[1] + LUMINANCE[2] * rgb[2]
+}
+
+/// The contrast ratio between two relative luminances, from 1.0 (no contrast)
+/// to 21.0 (black and white). The order of the luminances doesn't matter.
+///
+/// ```
+/// use achroma::contrast::contrast_ratio;
+///
+/// assert_eq!(contrast_ratio(0.5, 0.5), 1.0);
+/// assert_eq!(contrast_ratio(0.0, 1.0), contrast_ratio(1.0, 0.0));
+/// ```
+pub fn contrast_ratio(a: f32, b: f32) -> f32 {
+	let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+	(lighter + 0.05) / (darker + 0.05)
+}
+
+/// The contrast ratio between two linear sRGB colors
+pub fn contrast_ratio_linear(a: [f32; 3], b: [f32; 3]) -> f32 {
+	contrast_ratio(relative_luminance(a), relative_luminance(b))
+}
+
+/// The contrast ratio between two 8-bit sRGB colors
+///
+/// ```
+/// use achroma::contrast::contrast_ratio_srgb8;
+///
+/// // #777777 on white just falls short of 4.5:1
+/// let ratio = contrast_ratio_srgb8([0x77, 0x77, 0x77], [255, 255, 255]);
+/// assert!((ratio - 4.48).abs() < 0.01);
+/// ```
+pub fn contrast_ratio_srgb8(a: [u8; 3], b: [u8; 3]) -> f32 {
+	contrast_ratio_linear(decode_srgb8(a), decode_srgb8(b))
+}
+
+/// The screen luminance of an 8-bit sRGB color as estimated by APCA,
+/// which uses a simple 2.4 exponent rather than the sRGB transfer function
+pub fn apca_luminance(rgb: [u8; 3]) -> f32 {
+	const COEFFICIENTS: [f32; 3] = [0.2126729, 0.7151522, 0.0721750];
+	let mut y = 0.0;
+	for (c, k) in rgb.iter().zip(COEFFICIENTS) {
+		y += k * libm::powf(f32::from(*c) / 255.0, 2.4);
+	}
+	y
+}
+
+/// The APCA lightness contrast (Lc) of text on a background, given the
+/// luminances from [`apca_luminance`], from about -108 to 106.
+///
+/// Unlike the WCAG contrast ratio, the order matters: the result is
+/// positive for dark text on a light background and negative for light
+/// text on a dark background. Compare its absolute value with a minimum
+/// such as [`APCA_CONTENT_TEXT`].
+///
+/// This implements version 0.0.98G-4g of the APCA-W3 constants.
+pub fn apca_contrast(text: f32, background: f32) -> f32 {
+	const BLACK_THRESHOLD: f32 = 0.022;
+	const BLACK_CLAMP: f32 = 1.414;
+	const DELTA_Y_MIN: f32 = 0.0005;
+	const SCALE: f32 = 1.14;
+	const OFFSET: f32 = 0.027;
+	const LOW_CLIP: f32 = 0.1;
+
+	let soft_clamp = |y: f32| {
+		if y < BLACK_THRESHOLD {
+			y + libm::powf(BLACK_THRESHOLD - y, BLACK_CLAMP)
+		} else {
+			y
+		}
+	};
+	let text = soft_clamp(text);
+	let background = soft_clamp(background);
+	if libm::fabsf(background - text) < DELTA_Y_MIN {
+		return 0.0;
+	}
+
+	let lc = if background > text {
+		// dark text on a light background
+		let sapc = (libm::powf(background, 0.56) - libm::powf(text, 0.57)) * SCALE;
+		if sapc < LOW_CLIP {
+			0.0
+		} else {
+			sapc - OFFSET
+		}
+	} else {
+		// light text on a dark background
+		let sapc = (libm::powf(background, 0.65) - libm::powf(text, 0.62)) * SCALE;
+		if sapc > -LOW_CLIP {
+			0.0
+		} else {
+			sapc + OFFSET
+		}
+	};
+	lc * 100.0
+}
+
+/// The APCA lightness contrast (Lc) of 8-bit sRGB text on a background
+///
+/// ```
+/// use achroma::contrast::{apca_contrast_srgb8, APCA_BODY_TEXT};
+///
+/// let black = [0, 0, 0];
+/// let white = [255, 255, 255];
+/// assert!(apca_contrast_srgb8(black, white) > APCA_BODY_TEXT);
+/// assert!(apca_contrast_srgb8(white, black) < -APCA_BODY_TEXT);
+/// ```
+pub fn apca_contrast_srgb8(text: [u8; 3], background: [u8; 3]) -> f32 {
+	apca_contrast(apca_luminance(text), apca_luminance(background))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_relative_luminance_bounds() {
+		assert_eq!(relative_luminance([0.0, 0.0, 0.0]), 0.0);
+		assert!(libm::fabsf(relative_luminance([1.0, 1.0, 1.0]) - 1.0) < 1e-6);
+	}
+
+	#[test]
+	fn test_contrast_ratio_primaries() {
+		let white = [255, 255, 255];
+		assert!(libm::fabsf(contrast_ratio_srgb8([255, 0, 0], white) - 4.0) < 0.01);
+		assert!(libm::fabsf(contrast_ratio_srgb8([0, 0, 255], white) - 8.59) < 0.01);
+		assert!(libm::fabsf(contrast_ratio_srgb8([0, 128, 0], white) - 5.14) < 0.01);
+	}
+
+	#[test]
+	fn test_apca_contrast_reference_values() {
+		let white = [255, 255, 255];
+		let black = [0, 0, 0];
+		let gray = [0x88, 0x88, 0x88];
+		assert!(libm::fabsf(apca_contrast_srgb8(black, white) - 106.04) < 0.05);
+		assert!(libm::fabsf(apca_contrast_srgb8(white, black) + 107.88) < 0.05);
+		assert!(libm::fabsf(apca_contrast_srgb8(gray, white) - 63.06) < 0.05);
+		assert_eq!(apca_contrast_srgb8(gray, gray), 0.0);
+	}
+}
@@ -0,0 +1,402 @@
+//! Scoring of the Farnsworth D-15 and Lanthony desaturated D-15 arrangement tests
+//!
+//! In a D-15 test, the subject arranges 15 colored caps in order of hue,
+//! starting from a fixed pilot cap. Arrangements are scored with the
+//! moment of inertia method by Vingrys & King-Smith (1988),
+//! which finds the main axis along which the subject's errors lie.
+//!
+//! ```
+//! use achroma::d15::Panel;
+//! use achroma::ColorVision;
+//!
+//! // a typical protanope arrangement
+//! let order = [15, 1, 14, 2, 13, 3, 12, 4, 11, 5, 10, 6, 9, 7, 8];
+//! let score = Panel::Farnsworth.score(&order).unwrap();
+//! assert!(score.major_crossings >= 2);
+//! assert_eq!(score.classify().unwrap().vision, ColorVision::Protanopia);
+//! ```
+
+use crate::{Classification, ColorVision};
+
+/// The number of movable caps in a D-15 panel
+pub const CAPS: usize = 15;
+
+/// The lowest C-index of an arrangement with a significant color vision loss
+pub const C_INDEX_LIMIT: f32 = 1.6;
+
+/// The lowest S-index of an arrangement whose errors lie along a single axis
+pub const S_INDEX_LIMIT: f32 = 1.8;
+
+/// The C-index at which an arrangement is classified as dichromacy. Below
+/// it, the severity of anomalous trichromacy scales with the C-index.
+pub const DICHROMAT_C_INDEX: f32 = 3.0;
+
+/// A [Munsell color][munsell] notation
+///
+/// [munsell]: <https://en.wikipedia.org/wiki/Munsell_color_system>
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Munsell {
+	pub hue: &'static str,
+	pub value: f32,
+	pub chroma: f32,
+}
+
+/// A cap of a D-15 panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cap {
+	/// The number of the cap, where 0 is the pilot cap
+	pub number: u8,
+	pub munsell: Munsell,
+	/// The CIE 1976 u* coordinate under illuminant C
+	pub u: f32,
+	/// The CIE 1976 v* coordinate under illuminant C
+	pub v: f32,
+}
+
+const fn cap(number: u8, hue: &'static str, value: f32, chroma: f32, u: f32, v: f32) -> Cap {
+	Cap {
+		number,
+		munsell: Munsell { hue, value, chroma },
+		u,
+		v,
+	}
+}
+
+/// Caps of the Farnsworth D-15, from the pilot cap to cap 15
+const FARNSWORTH: [Cap; CAPS + 1] = [
+	cap(0, "10B", 5.0, 6.0, -21.54, -38.39),
+	cap(1, "5B", 5.0, 4.0, -23.26, -25.56),
+	cap(2, "10BG", 5.0, 4.0, -22.41, -15.53),
+	cap(3, "5BG", 5.0, 4.0, -23.11, -7.45),
+	cap(4, "10G", 5.0, 4.0, -22.45, 1.10),
+	cap(5, "5G", 5.0, 4.0, -21.67, 7.35),
+	cap(6, "10GY", 5.0, 4.0, -14.08, 18.74),
+	cap(7, "5GY", 5.0, 4.0, -2.72, 28.13),
+	cap(8, "5Y", 5.0, 4.0, 14.84, 31.13),
+	cap(9, "10YR", 5.0, 4.0, 23.87, 26.35),
+	cap(10, "5YR", 5.0, 4.0, 31.82, 14.76),
+	cap(11, "10R", 5.0, 4.0, 31.42, 6.99),
+	cap(12, "5R", 5.0, 4.0, 29.79, 0.10),
+	cap(13, "10RP", 5.0, 4.0, 26.64, -9.38),
+	cap(14, "5RP", 5.0, 4.0, 22.92, -18.65),
+	cap(15, "10P", 5.0, 4.0, 11.20, -24.61),
+];
+
+/// Caps of the Lanthony desaturated D-15, from the pilot cap to cap 15
+#[allow(clippy::approx_constant)] // v* of cap 10 is coincidentally 3.14
+const LANTHONY: [Cap; CAPS + 1] = [
+	cap(0, "10B", 8.0, 2.0, -4.77, -16.63),
+	cap(1, "5B", 8.0, 2.0, -8.63, -14.65),
+	cap(2, "10BG", 8.0, 2.0, -12.08, -11.94),
+	cap(3, "5BG", 8.0, 2.0, -12.86, -6.74),
+	cap(4, "10G", 8.0, 2.0, -12.26, -2.67),
+	cap(5, "5G", 8.0, 2.0, -11.18, 2.01),
+	cap(6, "10GY", 8.0, 2.0, -7.31, 9.08),
+	cap(7, "5GY", 8.0, 2.0, -0.20, 12.88),
+	cap(8, "5Y", 8.0, 2.0, 6.22, 11.80),
+	cap(9, "10YR", 8.0, 2.0, 9.47, 8.68),
+	cap(10, "5YR", 8.0, 2.0, 11.77, 3.14),
+	cap(11, "10R", 8.0, 2.0, 11.65, -0.73),
+	cap(12, "5R", 8.0, 2.0, 11.22, -4.13),
+	cap(13, "10RP", 8.0, 2.0, 8.53, -8.05),
+	cap(14, "5RP", 8.0, 2.0, 5.04, -11.14),
+	cap(15, "10P", 8.0, 2.0, 1.97, -14.20),
+];
+
+/// A panel of D-15 caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Panel {
+	/// The Farnsworth Dichotomous Test for Color Blindness (Panel D-15),
+	/// with saturated caps of Munsell value 5 and chroma 4
+	#[default]
+	Farnsworth,
+	/// The Lanthony desaturated D-15, with pale caps of Munsell value 8
+	/// and chroma 2, which is more sensitive to mild deficiencies
+	Lanthony,
+}
+
+/// An error in a subject's arrangement of caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArrangementError {
+	/// The arrangement doesn't have exactly 15 caps
+	Length(usize),
+	/// A cap number isn't between 1 and 15
+	InvalidCap(u8),
+	/// A cap appears more than once
+	DuplicateCap(u8),
+}
+
+impl Panel {
+	/// The caps of the panel, from the pilot cap to cap 15
+	///
+	/// ```
+	/// use achroma::d15::Panel;
+	///
+	/// let caps = Panel::Lanthony.caps();
+	/// assert_eq!(caps[0].number, 0);
+	/// assert_eq!(caps[15].munsell.hue, "10P");
+	/// ```
+	pub const fn caps(&self) -> &'static [Cap; CAPS + 1] {
+		match self {
+			Self::Farnsworth => &FARNSWORTH,
+			Self::Lanthony => &LANTHONY,
+		}
+	}
+
+	/// Scores a subject's arrangement of the 15 movable caps, as cap
+	/// numbers in the order placed after the pilot cap
+	///
+	/// ```
+	/// use achroma::d15::{ArrangementError, Panel};
+	///
+	/// let perfect = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+	/// let score = Panel::Farnsworth.score(&perfect).unwrap();
+	/// assert_eq!(score.major_crossings, 0);
+	/// assert!((score.c_index - 1.0).abs() < 1e-4);
+	///
+	/// assert_eq!(Panel::Farnsworth.score(&[1, 2, 3]), Err(ArrangementError::Length(3)));
+	/// ```
+	pub fn score(&self, order: &[u8]) -> Result<Score, ArrangementError> {
+		if order.len() != CAPS {
+			return Err(ArrangementError::Length(order.len()));
+		}
+		let mut seen = [false; CAPS + 1];
+		for &number in order {
+			if number == 0 || number as usize > CAPS {
+				return Err(ArrangementError::InvalidCap(number));
+			}
+			if seen[number as usize] {
+				return Err(ArrangementError::DuplicateCap(number));
+			}
+			seen[number as usize] = true;
+		}
+
+		let mut sequence = [0; CAPS + 1];
+		sequence[1..].copy_from_slice(order);
+		let moments = Moments::new(self.caps(), &sequence);
+		let perfect = Moments::new(self.caps(), &PERFECT);
+
+		let major_crossings = sequence
+			.windows(2)
+			.filter(|pair| pair[0].abs_diff(pair[1]) > 2)
+			.count() as u8;
+
+		Ok(Score {
+			major_crossings,
+			angle: moments.angle,
+			major_radius: moments.major_radius,
+			minor_radius: moments.minor_radius,
+			total_error: libm::hypotf(moments.major_radius, moments.minor_radius),
+			s_index: moments.major_radius / moments.minor_radius,
+			c_index: moments.major_radius / perfect.major_radius,
+		})
+	}
+}
+
+/// The sequence of a perfect arrangement, including the pilot cap
+const PERFECT: [u8; CAPS + 1] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+/// The result of scoring an arrangement
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score {
+	/// The number of transitions between caps more than two apart, which
+	/// cross the hue circle. Two or more is a failure of the test.
+	pub major_crossings: u8,
+	/// The confusion angle in degrees from -90 to 90, which identifies the
+	/// type of deficiency: positive for protan, negative for deutan, and
+	/// steep (beyond ±70) for tritan
+	pub angle: f32,
+	/// The radius of the major axis, quantifying the errors along the
+	/// confusion axis
+	pub major_radius: f32,
+	/// The radius of the minor axis, quantifying the errors perpendicular
+	/// to the confusion axis
+	pub minor_radius: f32,
+	/// The total error score (TES), combining both radii
+	pub total_error: f32,
+	/// The selectivity index, as the ratio of the major and minor radii,
+	/// which is higher when errors lie along a single axis
+	pub s_index: f32,
+	/// The confusion index, as the ratio of the major radius to that of a
+	/// perfect arrangement, which is 1 for a perfect arrangement
+	pub c_index: f32,
+}
+
+impl Score {
+	/// Whether the arrangement passes the test, with fewer than two major
+	/// crossings and a C-index below [`C_INDEX_LIMIT`]
+	pub fn passed(&self) -> bool {
+		self.major_crossings < 2 && self.c_index < C_INDEX_LIMIT
+	}
+
+	/// Classifies the color vision of the subject from the confusion angle,
+	/// with a severity proportional to the C-index up to [`DICHROMAT_C_INDEX`].
+	///
+	/// Returns `None` when the errors are significant but not selective
+	/// along a single axis, or the angle doesn't match a known axis.
+	///
+	/// ```
+	/// use achroma::d15::Panel;
+	/// use achroma::{Classification, ColorVision};
+	///
+	/// let perfect = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+	/// let score = Panel::Farnsworth.score(&perfect).unwrap();
+	/// assert_eq!(score.classify(), Some(Classification::NORMAL));
+	/// ```
+	pub fn classify(&self) -> Option<Classification> {
+		if self.c_index < C_INDEX_LIMIT {
+			return Some(Classification::NORMAL);
+		}
+		if self.s_index < S_INDEX_LIMIT {
+			return None;
+		}
+
+		let (anomaly, dichromacy) = if libm::fabsf(self.angle) >= 70.0 {
+			(ColorVision::Tritanomaly, ColorVision::Tritanopia)
+		} else if (0.0..45.0).contains(&self.angle) {
+			(ColorVision::Protanomaly, ColorVision::Protanopia)
+		} else if (-45.0..0.0).contains(&self.angle) {
+			(ColorVision::Deuteranomaly, ColorVision::Deuteranopia)
+		} else {
+			return None;
+		};
+
+		let severity = ((self.c_index - 1.0) / (DICHROMAT_C_INDEX - 1.0)).clamp(0.0, 1.0);
+		let vision = if severity >= 1.0 { dichromacy } else { anomaly };
+		Some(Classification::new(vision, severity))
+	}
+}
+
+/// Moments of inertia of the color difference vectors of an arrangement
+struct Moments {
+	angle: f32,
+	major_radius: f32,
+	minor_radius: f32,
+}
+
+impl Moments {
+	fn new(caps: &[Cap; CAPS + 1], sequence: &[u8; CAPS + 1]) -> Self {
+		let (mut u2, mut v2, mut uv) = (0.0, 0.0, 0.0);
+		for pair in sequence.windows(2) {
+			let (a, b) = (&caps[pair[0] as usize], &caps[pair[1] as usize]);
+			let (du, dv) = (b.u - a.u, b.v - a.v);
+			u2 += du * du;
+			v2 += dv * dv;
+			uv += du * dv;
+		}
+
+		let moment = |angle: f32| {
+			let (sin, cos) = (libm::sinf(angle), libm::cosf(angle));
+			u2 * sin * sin + v2 * cos * cos - 2.0 * uv * sin * cos
+		};
+		let mut major_angle = if u2 == v2 {
+			core::f32::consts::FRAC_PI_4
+		} else {
+			libm::atanf(2.0 * uv / (u2 - v2)) / 2.0
+		};
+		let mut minor_angle = if major_angle < 0.0 {
+			major_angle + core::f32::consts::FRAC_PI_2
+		} else {
+			major_angle - core::f32::consts::FRAC_PI_2
+		};
+		let (mut major, mut minor) = (moment(major_angle), moment(minor_angle));
+		if minor > major {
+			core::mem::swap(&mut major, &mut minor);
+			core::mem::swap(&mut major_angle, &mut minor_angle);
+		}
+
+		// the axis of the errors is perpendicular to the axis of the major moment
+		let vectors = CAPS as f32;
+		Self {
+			angle: minor_angle * (180.0 / core::f32::consts::PI),
+			major_radius: libm::sqrtf(major / vectors),
+			minor_radius: libm::sqrtf(minor / vectors),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: f32, b: f32, tolerance: f32) -> bool {
+		libm::fabsf(a - b) < tolerance
+	}
+
+	#[test]
+	fn test_perfect_arrangement() {
+		// reference values of a perfect arrangement from Vingrys & King-Smith (1988)
+		let score = Panel::Farnsworth.score(&PERFECT[1..]).unwrap();
+		assert!(close(score.angle, 61.97, 0.01));
+		assert!(close(score.major_radius, 9.23, 0.01));
+		assert!(close(score.minor_radius, 6.71, 0.01));
+		assert!(close(score.total_error, 11.42, 0.01));
+		assert!(close(score.s_index, 1.38, 0.01));
+		assert!(score.passed());
+	}
+
+	#[test]
+	fn test_classify_dichromats() {
+		let protan = [15, 1, 14, 2, 13, 3, 12, 4, 11, 5, 10, 6, 9, 7, 8];
+		let score = Panel::Farnsworth.score(&protan).unwrap();
+		assert!(close(score.angle, 3.90, 0.01));
+		assert!(close(score.c_index, 4.86, 0.01));
+		assert!(!score.passed());
+		assert_eq!(
+			score.classify(),
+			Some(Classification::new(ColorVision::Protanopia, 1.0))
+		);
+
+		let deutan = [1, 15, 2, 14, 3, 13, 4, 12, 5, 11, 10, 6, 9, 7, 8];
+		let score = Panel::Farnsworth.score(&deutan).unwrap();
+		assert!(close(score.angle, -4.58, 0.01));
+		assert_eq!(score.classify().unwrap().vision, ColorVision::Deuteranopia);
+
+		let tritan = [1, 2, 3, 4, 5, 6, 7, 15, 8, 14, 9, 13, 10, 12, 11];
+		let score = Panel::Farnsworth.score(&tritan).unwrap();
+		assert!(close(score.angle, -87.12, 0.01));
+		assert_eq!(score.classify().unwrap().vision, ColorVision::Tritanopia);
+	}
+
+	#[test]
+	fn test_classify_anomalous() {
+		let deutan = [1, 15, 2, 14, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
+		let classification = Panel::Farnsworth
+			.score(&deutan)
+			.unwrap()
+			.classify()
+			.unwrap();
+		assert_eq!(classification.vision, ColorVision::Deuteranomaly);
+		assert!(classification.severity > 0.5 && classification.severity < 1.0);
+	}
+
+	#[test]
+	fn test_minor_errors() {
+		// swapping neighboring caps is a minor error, not a crossing
+		let order = [1, 3, 2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15, 14];
+		let score = Panel::Lanthony.score(&order).unwrap();
+		assert_eq!(score.major_crossings, 0);
+		assert_eq!(score.classify(), Some(Classification::NORMAL));
+	}
+
+	#[test]
+	fn test_invalid_arrangements() {
+		let mut order = PERFECT;
+		order[2] = 1;
+		assert_eq!(
+			Panel::Farnsworth.score(&order[1..]),
+			Err(ArrangementError::DuplicateCap(1))
+		);
+		order[2] = 16;
+		assert_eq!(
+			Panel::Farnsworth.score(&order[1..]),
+			Err(ArrangementError::InvalidCap(16))
+		);
+		assert_eq!(
+			Panel::Farnsworth.score(&order),
+			Err(ArrangementError::Length(16))
+		);
+	}
+}
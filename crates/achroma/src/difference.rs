@@ -0,0 +1,222 @@
+//! Perceptual color difference (ΔE) in the CIELAB color space
+//!
+//! ```
+//! use achroma::difference::Lab;
+//!
+//! let red = Lab::from_srgb8([255, 0, 0]);
+//! let darker_red = Lab::from_srgb8([250, 0, 0]);
+//!
+//! // a just-noticeable difference is around 1.0
+//! assert!(red.delta_e_2000(&darker_red) < 2.0);
+//! ```
+
+use crate::simulation::{decode_srgb8, mul, Matrix3};
+use core::f32::consts::PI;
+
+/// Linear sRGB to CIE XYZ, with a D65 white point
+pub const SRGB_TO_XYZ: Matrix3 = [
+	[0.4124564, 0.3575761, 0.1804375],
+	[0.2126729, 0.7151522, 0.072175],
+	[0.0193339, 0.119192, 0.9503041],
+];
+
+/// CIE XYZ of the D65 reference white, normalized to `Y = 1.0`
+pub const D65_WHITE: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+/// A color in the CIE 1976 L\*a\*b\* color space, relative to D65
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Lab {
+	/// Lightness, from 0 (black) to 100 (diffuse white)
+	pub l: f32,
+	/// Green–red opponent axis
+	pub a: f32,
+	/// Blue–yellow opponent axis
+	pub b: f32,
+}
+
+impl Lab {
+	/// Creates a new color from its L\*, a\* and b\* coordinates
+	pub const fn new(l: f32, a: f32, b: f32) -> Self {
+		Self { l, a, b }
+	}
+
+	/// Converts a CIE XYZ color, relative to D65 with `Y = 1.0` for white
+	pub fn from_xyz(xyz: [f32; 3]) -> Self {
+		const EPSILON: f32 = 216.0 / 24389.0;
+		const KAPPA: f32 = 24389.0 / 27.0;
+		let f = |t: f32| {
+			if t > EPSILON {
+				libm::cbrtf(t)
+			} else {
+				(KAPPA * t + 16.0) / 116.0
+			}
+		};
+
+		let fx = f(xyz[0] / D65_WHITE[0]);
+		let fy = f(xyz[1] / D65_WHITE[1]);
+		let fz = f(xyz[2] / D65_WHITE[2]);
+		Self::new(116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+	}
+
+	/// Converts a linear sRGB color
+	pub fn from_linear_srgb(rgb: [f32; 3]) -> Self {
+		Self::from_xyz(mul(&SRGB_TO_XYZ, rgb))
+	}
+
+	/// Converts an 8-bit sRGB color
+	///
+	/// ```
+	/// use achroma::difference::Lab;
+	///
+	/// let white = Lab::from_srgb8([255, 255, 255]);
+	/// assert!((white.l - 100.0).abs() < 0.01);
+	/// assert!(white.a.abs() < 0.01 && white.b.abs() < 0.01);
+	/// ```
+	pub fn from_srgb8(rgb: [u8; 3]) -> Self {
+		Self::from_linear_srgb(decode_srgb8(rgb))
+	}
+
+	/// The chroma (colorfulness) of the color, C\*<sub>ab</sub>
+	pub fn chroma(&self) -> f32 {
+		libm::hypotf(self.a, self.b)
+	}
+
+	/// The CIE 1976 color difference, which is the euclidean distance
+	///
+	/// ```
+	/// use achroma::difference::Lab;
+	///
+	/// let a = Lab::new(50.0, 0.0, 0.0);
+	/// let b = Lab::new(50.0, 3.0, 4.0);
+	/// assert_eq!(a.delta_e_76(&b), 5.0);
+	/// ```
+	pub fn delta_e_76(&self, other: &Self) -> f32 {
+		let dl = self.l - other.l;
+		let da = self.a - other.a;
+		let db = self.b - other.b;
+		libm::sqrtf(dl * dl + da * da + db * db)
+	}
+
+	/// The CIEDE2000 color difference, following
+	/// [Sharma, Wu & Dalal (2005)][sharma-2005]
+	///
+	/// [sharma-2005]: <https://doi.org/10.1002/col.20070>
+	pub fn delta_e_2000(&self, other: &Self) -> f32 {
+		let (l1, a1, b1) = (self.l, self.a, self.b);
+		let (l2, a2, b2) = (other.l, other.a, other.b);
+
+		let c_mean = (self.chroma() + other.chroma()) / 2.0;
+		let c_mean7 = libm::powf(c_mean, 7.0);
+		let g = 0.5 * (1.0 - libm::sqrtf(c_mean7 / (c_mean7 + libm::powf(25.0, 7.0))));
+
+		let a1p = (1.0 + g) * a1;
+		let a2p = (1.0 + g) * a2;
+		let c1p = libm::hypotf(a1p, b1);
+		let c2p = libm::hypotf(a2p, b2);
+		let h1p = hue_degrees(a1p, b1);
+		let h2p = hue_degrees(a2p, b2);
+
+		let dlp = l2 - l1;
+		let dcp = c2p - c1p;
+		let dhp = if c1p * c2p == 0.0 {
+			0.0
+		} else if libm::fabsf(h2p - h1p) <= 180.0 {
+			h2p - h1p
+		} else if h2p - h1p > 180.0 {
+			h2p - h1p - 360.0
+		} else {
+			h2p - h1p + 360.0
+		};
+		let dhp_big = 2.0 * libm::sqrtf(c1p * c2p) * libm::sinf(radians(dhp / 2.0));
+
+		let lp_mean = (l1 + l2) / 2.0;
+		let cp_mean = (c1p + c2p) / 2.0;
+		let hp_mean = if c1p * c2p == 0.0 {
+			h1p + h2p
+		} else if libm::fabsf(h1p - h2p) <= 180.0 {
+			(h1p + h2p) / 2.0
+		} else if h1p + h2p < 360.0 {
+			(h1p + h2p + 360.0) / 2.0
+		} else {
+			(h1p + h2p - 360.0) / 2.0
+		};
+
+		let t = 1.0 - 0.17 * libm::cosf(radians(hp_mean - 30.0))
+			+ 0.24 * libm::cosf(radians(2.0 * hp_mean))
+			+ 0.32 * libm::cosf(radians(3.0 * hp_mean + 6.0))
+			- 0.20 * libm::cosf(radians(4.0 * hp_mean - 63.0));
+		let d_theta = 30.0 * libm::expf(-libm::powf((hp_mean - 275.0) / 25.0, 2.0));
+		let cp_mean7 = libm::powf(cp_mean, 7.0);
+		let rc = 2.0 * libm::sqrtf(cp_mean7 / (cp_mean7 + libm::powf(25.0, 7.0)));
+		let lp_offset = (lp_mean - 50.0) * (lp_mean - 50.0);
+		let sl = 1.0 + 0.015 * lp_offset / libm::sqrtf(20.0 + lp_offset);
+		let sc = 1.0 + 0.045 * cp_mean;
+		let sh = 1.0 + 0.015 * cp_mean * t;
+		let rt = -libm::sinf(radians(2.0 * d_theta)) * rc;
+
+		let dl = dlp / sl;
+		let dc = dcp / sc;
+		let dh = dhp_big / sh;
+		libm::sqrtf(dl * dl + dc * dc + dh * dh + rt * dc * dh)
+	}
+}
+
+fn radians(degrees: f32) -> f32 {
+	degrees * PI / 180.0
+}
+
+/// The hue angle of an opponent color in degrees, in the range `0.0..360.0`
+fn hue_degrees(a: f32, b: f32) -> f32 {
+	if a == 0.0 && b == 0.0 {
+		return 0.0;
+	}
+	let h = libm::atan2f(b, a) * 180.0 / PI;
+	if h < 0.0 {
+		h + 360.0
+	} else {
+		h
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_delta_e(a: [f32; 3], b: [f32; 3], expected: f32) {
+		let a = Lab::new(a[0], a[1], a[2]);
+		let b = Lab::new(b[0], b[1], b[2]);
+		let actual = a.delta_e_2000(&b);
+		assert!(
+			libm::fabsf(actual - expected) < 1e-3,
+			"expected {}, got {}",
+			expected,
+			actual
+		);
+		// the difference is symmetric
+		assert!(libm::fabsf(b.delta_e_2000(&a) - actual) < 1e-4);
+	}
+
+	#[test]
+	fn test_delta_e_2000_sharma_data() {
+		assert_delta_e([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425);
+		assert_delta_e([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669);
+		assert_delta_e([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492);
+		assert_delta_e(
+			[60.2574, -34.0099, 36.2677],
+			[60.4626, -34.1751, 39.4387],
+			1.2644,
+		);
+	}
+
+	#[test]
+	fn test_delta_e_identical() {
+		let color = Lab::from_srgb8([12, 200, 99]);
+		assert_eq!(color.delta_e_2000(&color), 0.0);
+		assert_eq!(color.delta_e_76(&color), 0.0);
+	}
+
+	#[test]
+	fn test_from_srgb8_black() {
+		assert_eq!(Lab::from_srgb8([0, 0, 0]), Lab::new(0.0, 0.0, 0.0));
+	}
+}
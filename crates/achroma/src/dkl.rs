@@ -0,0 +1,357 @@
+//! MacLeod-Boynton chromaticity, and the cardinal color space of Derrington,
+//! Krauskopf & Lennie (DKL)
+//!
+//! The MacLeod-Boynton diagram gives the chromaticity of a color by the
+//! excitations of the cone cells per unit of luminance, where luminance is
+//! the sum of the excitations of the L and M cone cells. It was introduced
+//! by MacLeod & Boynton, "Chromaticity diagram showing cone excitation by
+//! stimuli of equal luminance", Journal of the Optical Society of America
+//! 69 (1979).
+//!
+//! The DKL space describes colors by their difference to a background along
+//! three cardinal [axes][Axis] of the early visual system: luminance, L−M,
+//! which only L and M cone cells see, and S−(L+M), which only S cone cells
+//! see. It follows Derrington, Krauskopf & Lennie, "Chromatic mechanisms in
+//! lateral geniculate nucleus of macaque", Journal of Physiology 357 (1984),
+//! and is scaled as in Brainard, "Cone contrast and opponent modulation color
+//! spaces", in Human Color Vision (1996), so that a unit along each axis has
+//! a cone contrast of length 1.0.
+//!
+//! Protan and deutan defects lose the L−M axis, and tritan defects lose the
+//! S−(L+M) axis, so that chromatic thresholds of color vision deficiencies
+//! are naturally expressed along these axes.
+//!
+//! ```
+//! use achroma::cone_contrast::Characterization;
+//! use achroma::dkl::{Axis, DklSpace};
+//! use achroma::ConeCell;
+//!
+//! let gray = [0.5; 3];
+//! let space = DklSpace::new(Characterization::SRGB, gray).unwrap();
+//!
+//! // an S cone isolating color only moves along the S−(L+M) axis
+//! let test = Characterization::SRGB.isolate(gray, ConeCell::Short, 0.2).unwrap();
+//! let dkl = space.dkl(test);
+//! assert!((dkl[Axis::SMinusLm] - 0.2).abs() < 1e-4);
+//! assert!(dkl[Axis::LMinusM].abs() < 1e-4 && dkl[Axis::Luminance].abs() < 1e-4);
+//! ```
+
+use core::fmt;
+use core::ops::Index;
+use core::str::FromStr;
+
+use crate::cone_contrast::{invert, Characterization};
+use crate::simulation::{mul, Matrix3};
+use crate::{ColorVision, ConeCell};
+
+/// A cardinal axis of the DKL space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+	/// The sum of the L and M cone excitations, L+M
+	Luminance,
+	/// The difference of the L and M cone excitations at constant luminance,
+	/// which leaves S cones silent
+	LMinusM,
+	/// The S cone excitation at constant L and M cone excitations
+	SMinusLm,
+}
+
+impl Axis {
+	/// All cardinal axes, in the order of the coordinates of [Dkl]
+	pub const ALL: [Self; 3] = [Self::Luminance, Self::LMinusM, Self::SMinusLm];
+
+	/// Gets the name of the axis, such as `"l-m"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Luminance => "luminance",
+			Self::LMinusM => "l-m",
+			Self::SMinusLm => "s-(l+m)",
+		}
+	}
+
+	/// The cone cells whose excitations the axis is built from
+	///
+	/// ```
+	/// use achroma::dkl::Axis;
+	/// use achroma::ConeCell;
+	///
+	/// assert_eq!(Axis::LMinusM.cones(), &[ConeCell::Long, ConeCell::Medium]);
+	/// ```
+	pub const fn cones(&self) -> &'static [ConeCell] {
+		match self {
+			Self::Luminance | Self::LMinusM => &[ConeCell::Long, ConeCell::Medium],
+			Self::SMinusLm => &[ConeCell::Short, ConeCell::Long, ConeCell::Medium],
+		}
+	}
+
+	/// The chromatic axis that is lost when a type of cone cell is missing
+	///
+	/// ```
+	/// use achroma::dkl::Axis;
+	/// use achroma::ConeCell;
+	///
+	/// assert_eq!(Axis::opponent(ConeCell::Medium), Axis::LMinusM);
+	/// assert_eq!(Axis::opponent(ConeCell::Short), Axis::SMinusLm);
+	/// ```
+	pub const fn opponent(cone: ConeCell) -> Self {
+		match cone {
+			ConeCell::Long | ConeCell::Medium => Self::LMinusM,
+			ConeCell::Short => Self::SMinusLm,
+		}
+	}
+
+	/// The chromatic axis along which a type of color vision loses
+	/// discrimination, or [None] for normal vision and monochromacies
+	pub const fn impaired(vision: ColorVision) -> Option<Self> {
+		if vision.is_protan() || vision.is_deutan() {
+			Some(Self::LMinusM)
+		} else if vision.is_tritan() {
+			Some(Self::SMinusLm)
+		} else {
+			None
+		}
+	}
+}
+
+impl fmt::Display for Axis {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Axis {
+	type Err = ();
+	/// Attempt to parse the name of an axis, ignoring ASCII case
+	///
+	/// ```
+	/// use achroma::dkl::Axis;
+	///
+	/// assert_eq!("L-M".parse(), Ok(Axis::LMinusM));
+	/// assert_eq!("s-(l+m)".parse(), Ok(Axis::SMinusLm));
+	/// assert_eq!("red-green".parse::<Axis>(), Err(()));
+	/// ```
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|axis| axis.name().eq_ignore_ascii_case(s.trim()))
+			.ok_or(())
+	}
+}
+
+/// A chromaticity in the MacLeod-Boynton diagram, with its luminance
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacLeodBoynton {
+	/// The L cone excitation per unit of luminance, L/(L+M)
+	pub l: f32,
+	/// The S cone excitation per unit of luminance, S/(L+M)
+	pub s: f32,
+	/// The luminance, L+M
+	pub luminance: f32,
+}
+
+impl MacLeodBoynton {
+	/// Creates a new chromaticity
+	pub const fn new(l: f32, s: f32, luminance: f32) -> Self {
+		Self { l, s, luminance }
+	}
+
+	/// Computes the chromaticity of the excitations of the L, M and S cone
+	/// cells, scaled so that L+M is the luminance, or [None] for black
+	///
+	/// ```
+	/// use achroma::cone_contrast::Characterization;
+	/// use achroma::dkl::MacLeodBoynton;
+	///
+	/// let white = Characterization::SRGB.lms([1.0; 3]);
+	/// let chromaticity = MacLeodBoynton::from_lms(white).unwrap();
+	/// assert!((chromaticity.luminance - 100.0).abs() < 0.01);
+	/// assert!(chromaticity.l > 0.6 && chromaticity.l < 0.7);
+	/// ```
+	pub fn from_lms(lms: [f32; 3]) -> Option<Self> {
+		let [l, m, s] = lms;
+		let luminance = l + m;
+		(luminance > 0.0).then(|| Self::new(l / luminance, s / luminance, luminance))
+	}
+
+	/// The excitations of the L, M and S cone cells
+	pub fn lms(&self) -> [f32; 3] {
+		[
+			self.l * self.luminance,
+			(1.0 - self.l) * self.luminance,
+			self.s * self.luminance,
+		]
+	}
+}
+
+/// Coordinates in the DKL space
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dkl {
+	/// The coordinates along the luminance, L−M and S−(L+M) axes
+	pub axes: [f32; 3],
+}
+
+impl Dkl {
+	/// Creates new coordinates along the luminance, L−M and S−(L+M) axes
+	pub const fn new(luminance: f32, l_minus_m: f32, s_minus_lm: f32) -> Self {
+		Self {
+			axes: [luminance, l_minus_m, s_minus_lm],
+		}
+	}
+
+	/// The angle in degrees in the isoluminant plane, counterclockwise from
+	/// the L−M axis towards the S−(L+M) axis
+	///
+	/// ```
+	/// use achroma::dkl::Dkl;
+	///
+	/// assert_eq!(Dkl::new(0.0, 0.0, 0.1).azimuth(), 90.0);
+	/// ```
+	pub fn azimuth(&self) -> f32 {
+		let [_, l_minus_m, s_minus_lm] = self.axes;
+		libm::atan2f(s_minus_lm, l_minus_m).to_degrees()
+	}
+
+	/// The angle in degrees above the isoluminant plane
+	///
+	/// ```
+	/// use achroma::dkl::Dkl;
+	///
+	/// assert_eq!(Dkl::new(0.1, 0.1, 0.0).elevation(), 45.0);
+	/// ```
+	pub fn elevation(&self) -> f32 {
+		let [luminance, l_minus_m, s_minus_lm] = self.axes;
+		let chromatic = libm::sqrtf(l_minus_m * l_minus_m + s_minus_lm * s_minus_lm);
+		libm::atan2f(luminance, chromatic).to_degrees()
+	}
+}
+
+impl Index<Axis> for Dkl {
+	type Output = f32;
+
+	/// Index the coordinates by [Axis]
+	fn index(&self, axis: Axis) -> &Self::Output {
+		match axis {
+			Axis::Luminance => &self.axes[0],
+			Axis::LMinusM => &self.axes[1],
+			Axis::SMinusLm => &self.axes[2],
+		}
+	}
+}
+
+/// The DKL space around a background color, for a display whose L and M cone
+/// excitations add up to its luminance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DklSpace {
+	characterization: Characterization,
+	background: [f32; 3],
+	matrix: Matrix3,
+	inverse: Matrix3,
+}
+
+impl DklSpace {
+	/// Creates a new space around a background color in linear RGB, or
+	/// [None] when the background doesn't excite every type of cone cell
+	pub fn new(characterization: Characterization, background: [f32; 3]) -> Option<Self> {
+		let background = characterization.lms(background);
+		let [l, m, s] = background;
+		if l <= 0.0 || m <= 0.0 || s <= 0.0 {
+			return None;
+		}
+
+		// the responses of the mechanisms to changes of the cone excitations
+		let mut matrix = [
+			[1.0, 1.0, 0.0],
+			[1.0, -l / m, 0.0],
+			[-s / (l + m), -s / (l + m), 1.0],
+		];
+		// scale each mechanism so that the change isolating it at a unit
+		// response has a cone contrast of length 1.0
+		let inverse = invert(&matrix)?;
+		for (axis, row) in matrix.iter_mut().enumerate() {
+			let contrast = (0..3).map(|cone| inverse[cone][axis] / background[cone]);
+			let length = libm::sqrtf(contrast.map(|c| c * c).sum());
+			*row = row.map(|value| value * length);
+		}
+		Some(Self {
+			characterization,
+			background,
+			matrix,
+			inverse: invert(&matrix)?,
+		})
+	}
+
+	/// The chromaticity of the background
+	pub fn background(&self) -> MacLeodBoynton {
+		let [l, m, s] = self.background;
+		MacLeodBoynton::new(l / (l + m), s / (l + m), l + m)
+	}
+
+	/// The DKL coordinates of a color in linear RGB
+	pub fn dkl(&self, rgb: [f32; 3]) -> Dkl {
+		let lms = self.characterization.lms(rgb);
+		let change = [0, 1, 2].map(|cone| lms[cone] - self.background[cone]);
+		Dkl {
+			axes: mul(&self.matrix, change),
+		}
+	}
+
+	/// The color in linear RGB at DKL coordinates, which may be outside the
+	/// gamut, or [None] when the characterization can't be inverted
+	pub fn rgb(&self, dkl: Dkl) -> Option<[f32; 3]> {
+		let change = mul(&self.inverse, dkl.axes);
+		let lms = [0, 1, 2].map(|cone| self.background[cone] + change[cone]);
+		self.characterization.rgb(lms)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_round_trip() {
+		let space = DklSpace::new(Characterization::SRGB, [0.3, 0.4, 0.5]).unwrap();
+		for rgb in [[0.3, 0.4, 0.5], [0.1, 0.9, 0.2], [1.0, 0.0, 0.6]] {
+			let back = space.rgb(space.dkl(rgb)).unwrap();
+			for (a, b) in back.iter().zip(rgb) {
+				assert!((a - b).abs() < 1e-4);
+			}
+		}
+		let chromaticity = MacLeodBoynton::from_lms([3.0, 1.0, 0.5]).unwrap();
+		assert_eq!(chromaticity.lms(), [3.0, 1.0, 0.5]);
+		assert_eq!(MacLeodBoynton::from_lms([0.0; 3]), None);
+	}
+
+	#[test]
+	fn test_luminance_axis() {
+		let space = DklSpace::new(Characterization::SRGB, [0.5; 3]).unwrap();
+		assert_eq!(space.dkl([0.5; 3]), Dkl::default());
+		// a brighter gray only moves along the luminance axis
+		let brighter = space.dkl([0.6; 3]);
+		assert!(brighter[Axis::Luminance] > 0.0);
+		assert!(brighter[Axis::LMinusM].abs() < 1e-5);
+		assert!(brighter[Axis::SMinusLm].abs() < 1e-5);
+		assert!((brighter.elevation() - 90.0).abs() < 1e-3);
+	}
+
+	#[test]
+	fn test_unit_contrast() {
+		let space = DklSpace::new(Characterization::SRGB, [0.5; 3]).unwrap();
+		for axis in Axis::ALL {
+			let mut unit = Dkl::default();
+			unit.axes[axis as usize] = 0.01;
+			let rgb = space.rgb(unit).unwrap();
+			let contrast = Characterization::SRGB.contrast([0.5; 3], rgb);
+			assert!((contrast.magnitude() - 0.01).abs() < 1e-5);
+		}
+		assert_eq!(DklSpace::new(Characterization::SRGB, [0.0; 3]), None);
+		assert_eq!(
+			Axis::impaired(ColorVision::Deuteranomaly),
+			Some(Axis::LMinusM)
+		);
+		assert_eq!(Axis::impaired(ColorVision::Achromatopsia), None);
+	}
+}
@@ -0,0 +1,530 @@
+//! Scoring of the Farnsworth-Munsell 100 Hue test
+//!
+//! In the FM 100 Hue test, the subject arranges 85 caps of gradually
+//! changing hue, split across four trays with fixed caps at each end.
+//! Each cap is scored by the difference between its number and the numbers
+//! of its neighbors, as described by Farnsworth (1943),
+//! with partial scores for the red-green and blue-yellow regions of the hue
+//! circle from Smith, Pokorny & Pass (1985).
+//!
+//! ```
+//! use achroma::fm100::{Discrimination, Fm100};
+//!
+//! // swap two neighboring caps in the first tray
+//! let mut order = Fm100::PERFECT;
+//! order.swap(5, 6);
+//!
+//! let score = Fm100::score(&order).unwrap();
+//! assert_eq!(score.total_error(), 4);
+//! assert_eq!(score.discrimination(), Discrimination::Superior);
+//! ```
+
+use crate::{Classification, ColorVision};
+use core::f32::consts::PI;
+
+/// The number of caps in the test
+pub const CAPS: usize = 85;
+
+/// The highest total error score of superior color discrimination
+pub const SUPERIOR_LIMIT: u16 = 16;
+
+/// The highest total error score of average color discrimination
+pub const AVERAGE_LIMIT: u16 = 100;
+
+/// The total error score at which a subject with a confusion axis is
+/// classified as a dichromat
+pub const DICHROMAT_TOTAL_ERROR: u16 = 300;
+
+/// The lowest bipolarity of errors that indicates a confusion axis
+pub const BIPOLARITY_LIMIT: f32 = 0.3;
+
+/// A cap of the FM 100 Hue test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cap {
+	/// The number of the cap, from 1 to 85
+	pub number: u8,
+	/// The tray holding the cap, from 1 to 4
+	pub tray: u8,
+	/// Whether the cap is fixed at an end of its tray
+	pub fixed: bool,
+}
+
+impl Cap {
+	/// Gets a cap by its number, from 1 to 85
+	///
+	/// ```
+	/// use achroma::fm100::Cap;
+	///
+	/// let cap = Cap::new(85).unwrap();
+	/// assert_eq!(cap.tray, 1);
+	/// assert!(cap.fixed);
+	/// assert!(!Cap::new(30).unwrap().fixed);
+	/// assert_eq!(Cap::new(86), None);
+	/// ```
+	pub const fn new(number: u8) -> Option<Self> {
+		let mut tray = 0;
+		while tray < TRAYS.len() {
+			let (first, last) = TRAYS[tray];
+			let in_tray = if first > last {
+				number >= first || number <= last
+			} else {
+				number >= first && number <= last
+			};
+			if number >= 1 && number as usize <= CAPS && in_tray {
+				return Some(Self {
+					number,
+					tray: tray as u8 + 1,
+					fixed: number == first || number == last,
+				});
+			}
+			tray += 1;
+		}
+		None
+	}
+
+	/// The position of the cap around the hue circle in degrees,
+	/// starting from cap 1 at 0°
+	pub fn angle(&self) -> f32 {
+		(self.number - 1) as f32 * 360.0 / CAPS as f32
+	}
+
+	/// Whether the cap counts towards the red-green partial error score,
+	/// which covers caps 13 to 33 and 55 to 75
+	pub const fn is_red_green(&self) -> bool {
+		matches!(self.number, 13..=33 | 55..=75)
+	}
+
+	/// Whether the cap counts towards the blue-yellow partial error score,
+	/// which covers caps 1 to 12, 34 to 54 and 76 to 85
+	pub const fn is_blue_yellow(&self) -> bool {
+		!self.is_red_green()
+	}
+}
+
+/// The fixed first and last caps of each tray
+const TRAYS: [(u8, u8); 4] = [(85, 21), (22, 42), (43, 63), (64, 84)];
+
+/// An error in a subject's arrangement of caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArrangementError {
+	/// The arrangement doesn't have exactly 85 caps
+	Length(usize),
+	/// A cap number isn't between 1 and 85
+	InvalidCap(u8),
+	/// A cap appears more than once
+	DuplicateCap(u8),
+	/// A cap is placed in a different tray than its own, or a fixed cap
+	/// isn't at its end of the tray
+	MisplacedCap(u8),
+}
+
+/// The Farnsworth-Munsell 100 Hue test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fm100;
+
+impl Fm100 {
+	/// A perfect arrangement of all four trays, starting with the fixed cap 85
+	pub const PERFECT: [u8; CAPS] = {
+		let mut order = [0; CAPS];
+		order[0] = 85;
+		let mut i = 1;
+		while i < CAPS {
+			order[i] = i as u8;
+			i += 1;
+		}
+		order
+	};
+
+	/// Scores a subject's arrangement, as the cap numbers of the four trays
+	/// in the order placed, starting with the fixed cap 85 of tray 1.
+	///
+	/// Each tray must hold its own caps, with its fixed caps at its ends.
+	///
+	/// ```
+	/// use achroma::fm100::{ArrangementError, Fm100};
+	///
+	/// let score = Fm100::score(&Fm100::PERFECT).unwrap();
+	/// assert_eq!(score.total_error(), 0);
+	///
+	/// assert_eq!(Fm100::score(&[85, 1, 2]), Err(ArrangementError::Length(3)));
+	///
+	/// // cap 22 belongs to tray 2
+	/// let mut order = Fm100::PERFECT;
+	/// order.swap(20, 22);
+	/// assert_eq!(Fm100::score(&order), Err(ArrangementError::MisplacedCap(22)));
+	/// ```
+	pub fn score(order: &[u8]) -> Result<Score, ArrangementError> {
+		if order.len() != CAPS {
+			return Err(ArrangementError::Length(order.len()));
+		}
+		let mut seen = [false; CAPS];
+		for &number in order {
+			if number == 0 || number as usize > CAPS {
+				return Err(ArrangementError::InvalidCap(number));
+			}
+			if seen[number as usize - 1] {
+				return Err(ArrangementError::DuplicateCap(number));
+			}
+			seen[number as usize - 1] = true;
+		}
+		for (&number, &expected) in order.iter().zip(&Self::PERFECT) {
+			// each tray holds its own caps, with the fixed caps at its ends
+			let misplaced = match (Cap::new(number), Cap::new(expected)) {
+				(Some(cap), Some(slot)) => {
+					cap.tray != slot.tray || (slot.fixed && number != expected)
+				}
+				_ => true,
+			};
+			if misplaced {
+				return Err(ArrangementError::MisplacedCap(number));
+			}
+		}
+
+		// caps are scored against their neighbors around the whole circle,
+		// including across the ends of trays
+		let mut errors = [0; CAPS];
+		for (i, &number) in order.iter().enumerate() {
+			let previous = order[(i + CAPS - 1) % CAPS];
+			let next = order[(i + 1) % CAPS];
+			let score = distance(number, previous) + distance(number, next);
+			errors[number as usize - 1] = score - 2;
+		}
+		Ok(Score { errors })
+	}
+}
+
+/// The distance between two caps around the hue circle
+const fn distance(a: u8, b: u8) -> u8 {
+	let d = a.abs_diff(b);
+	if d > CAPS as u8 / 2 {
+		CAPS as u8 - d
+	} else {
+		d
+	}
+}
+
+/// A grade of color discrimination by total error score, from Farnsworth (1943)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Discrimination {
+	/// A total error score up to 16
+	Superior,
+	/// A total error score from 17 to 100
+	Average,
+	/// A total error score above 100
+	Low,
+}
+
+/// An axis of the hue circle along which errors cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+	/// Errors around caps 21 and 64
+	Protan,
+	/// Errors around caps 15 and 58
+	Deutan,
+	/// Errors around caps 5 and 47
+	Tritan,
+}
+
+impl Axis {
+	/// The cap at the center of the axis on the first half of the hue circle,
+	/// with the opposite center 42.5 caps away
+	pub const fn cap(&self) -> f32 {
+		match self {
+			Self::Protan => 21.0,
+			Self::Deutan => 15.0,
+			Self::Tritan => 5.0,
+		}
+	}
+}
+
+/// A point of a polar error plot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolarPoint {
+	pub cap: u8,
+	/// The angle of the cap in degrees, starting from cap 1 at 0°
+	pub angle: f32,
+	/// The error score of the cap, where 0 lies on the inner circle
+	pub error: u8,
+}
+
+/// The error scores of an arrangement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Score {
+	errors: [u8; CAPS],
+}
+
+impl Score {
+	/// The error scores of each cap, indexed by cap number minus one
+	pub const fn errors(&self) -> &[u8; CAPS] {
+		&self.errors
+	}
+
+	/// The error score of a cap, from 1 to 85
+	///
+	/// # Panics
+	/// Panics if the cap number is out of range.
+	pub const fn error(&self, cap: u8) -> u8 {
+		self.errors[cap as usize - 1]
+	}
+
+	/// The total error score (TES) of all caps
+	pub fn total_error(&self) -> u16 {
+		self.errors.iter().map(|&e| u16::from(e)).sum()
+	}
+
+	/// The square root of the total error score, which is closer to
+	/// normally distributed and better suited for statistics
+	pub fn sqrt_total_error(&self) -> f32 {
+		libm::sqrtf(self.total_error() as f32)
+	}
+
+	/// The partial error score of the red-green region of the hue circle
+	///
+	/// ```
+	/// use achroma::fm100::Fm100;
+	///
+	/// let mut order = Fm100::PERFECT;
+	/// order.swap(19, 20); // caps 19 and 20
+	/// let score = Fm100::score(&order).unwrap();
+	/// assert_eq!(score.red_green_error(), 4);
+	/// assert_eq!(score.blue_yellow_error(), 0);
+	/// ```
+	pub fn red_green_error(&self) -> u16 {
+		self.partial_error(Cap::is_red_green)
+	}
+
+	/// The partial error score of the blue-yellow region of the hue circle
+	pub fn blue_yellow_error(&self) -> u16 {
+		self.partial_error(Cap::is_blue_yellow)
+	}
+
+	fn partial_error(&self, region: fn(&Cap) -> bool) -> u16 {
+		self.caps()
+			.filter(|(cap, _)| region(cap))
+			.map(|(_, error)| u16::from(error))
+			.sum()
+	}
+
+	fn caps(&self) -> impl Iterator<Item = (Cap, u8)> + '_ {
+		self.errors
+			.iter()
+			.enumerate()
+			.filter_map(|(i, &error)| Cap::new(i as u8 + 1).map(|cap| (cap, error)))
+	}
+
+	/// Points of a polar error plot, from cap 1 to cap 85
+	pub fn polar(&self) -> impl Iterator<Item = PolarPoint> + '_ {
+		self.caps().map(|(cap, error)| PolarPoint {
+			cap: cap.number,
+			angle: cap.angle(),
+			error,
+		})
+	}
+
+	/// The grade of color discrimination
+	pub fn discrimination(&self) -> Discrimination {
+		let total = self.total_error();
+		if total <= SUPERIOR_LIMIT {
+			Discrimination::Superior
+		} else if total <= AVERAGE_LIMIT {
+			Discrimination::Average
+		} else {
+			Discrimination::Low
+		}
+	}
+
+	/// How strongly the errors cluster on two opposite sides of the hue
+	/// circle, from 0 (evenly spread) to 1 (all on a single axis)
+	pub fn bipolarity(&self) -> f32 {
+		let (c, s) = self.second_harmonic();
+		let total = self.total_error();
+		if total == 0 {
+			return 0.0;
+		}
+		libm::hypotf(c, s) / total as f32
+	}
+
+	/// The position of the confusion axis as a cap number from 1 to 43.5,
+	/// with the opposite end 42.5 caps away
+	pub fn axis_cap(&self) -> f32 {
+		let (c, s) = self.second_harmonic();
+		let angle = libm::atan2f(s, c) / 2.0;
+		let cap = angle / (2.0 * PI) * CAPS as f32;
+		let half = CAPS as f32 / 2.0;
+		(cap % half + half) % half + 1.0
+	}
+
+	fn second_harmonic(&self) -> (f32, f32) {
+		let (mut c, mut s) = (0.0, 0.0);
+		for (cap, error) in self.caps() {
+			let angle = 2.0 * cap.angle() * (PI / 180.0);
+			c += error as f32 * libm::cosf(angle);
+			s += error as f32 * libm::sinf(angle);
+		}
+		(c, s)
+	}
+
+	/// The axis that errors cluster along, or `None` when the errors are
+	/// too few or not bipolar enough
+	///
+	/// ```
+	/// use achroma::fm100::{Axis, Fm100};
+	///
+	/// // reverse runs of caps on opposite sides of the circle
+	/// let mut order = Fm100::PERFECT;
+	/// order[13..18].reverse(); // caps 13 to 17
+	/// order[56..61].reverse(); // caps 56 to 60
+	/// let score = Fm100::score(&order).unwrap();
+	/// assert_eq!(score.axis(), Some(Axis::Deutan));
+	/// ```
+	pub fn axis(&self) -> Option<Axis> {
+		if self.total_error() <= SUPERIOR_LIMIT || self.bipolarity() < BIPOLARITY_LIMIT {
+			return None;
+		}
+
+		let cap = self.axis_cap();
+		let half = CAPS as f32 / 2.0;
+		let offset = |axis: Axis| {
+			let d = libm::fabsf(cap - axis.cap()) % half;
+			d.min(half - d)
+		};
+		[Axis::Protan, Axis::Deutan, Axis::Tritan]
+			.into_iter()
+			.min_by(|a, b| offset(*a).total_cmp(&offset(*b)))
+	}
+
+	/// Classifies the color vision of the subject from the confusion axis,
+	/// with a severity proportional to the total error score above
+	/// average discrimination, up to [`DICHROMAT_TOTAL_ERROR`].
+	///
+	/// Returns `None` when discrimination is low but without an axis.
+	pub fn classify(&self) -> Option<Classification> {
+		if self.discrimination() != Discrimination::Low {
+			return Some(Classification::NORMAL);
+		}
+		let (anomaly, dichromacy) = match self.axis()? {
+			Axis::Protan => (ColorVision::Protanomaly, ColorVision::Protanopia),
+			Axis::Deutan => (ColorVision::Deuteranomaly, ColorVision::Deuteranopia),
+			Axis::Tritan => (ColorVision::Tritanomaly, ColorVision::Tritanopia),
+		};
+
+		let severity = (self.total_error() - AVERAGE_LIMIT) as f32
+			/ (DICHROMAT_TOTAL_ERROR - AVERAGE_LIMIT) as f32;
+		let severity = severity.clamp(0.0, 1.0);
+		let vision = if severity >= 1.0 { dichromacy } else { anomaly };
+		Some(Classification::new(vision, severity))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Interleaves the caps around two opposite centers, the way a
+	/// dichromat confuses caps across the hue circle, keeping each cap in
+	/// its tray and the fixed caps in place
+	fn confuse(center: usize, width: usize) -> [u8; CAPS] {
+		let mut order = Fm100::PERFECT;
+		let slot = |i: usize| Cap::new(Fm100::PERFECT[i]).unwrap();
+		for start in [center, center + 42] {
+			let (mut start, end) = (start - width / 2, start + width - width / 2);
+			while start < end {
+				let tray = slot(start).tray;
+				let mut run = start;
+				while run < end && !slot(run).fixed && slot(run).tray == tray {
+					run += 1;
+				}
+				if run > start {
+					order[start..run].reverse();
+					for i in (start..run).step_by(2) {
+						order.swap(i, (i + 3).min(run - 1));
+					}
+				}
+				start = run.max(start + 1);
+			}
+		}
+		order
+	}
+
+	#[test]
+	fn test_trays() {
+		let trays = (1..=85).map(|n| Cap::new(n).unwrap().tray);
+		let mut counts = [0; 4];
+		for tray in trays {
+			counts[tray as usize - 1] += 1;
+		}
+		assert_eq!(counts, [22, 21, 21, 21]);
+
+		let fixed = (1..=85).filter(|&n| Cap::new(n).unwrap().fixed).count();
+		assert_eq!(fixed, 8);
+	}
+
+	#[test]
+	fn test_regions() {
+		let red_green = (1..=85)
+			.filter(|&n| Cap::new(n).unwrap().is_red_green())
+			.count();
+		assert_eq!(red_green, 42);
+	}
+
+	#[test]
+	fn test_distance_wraps() {
+		assert_eq!(distance(85, 1), 1);
+		assert_eq!(distance(1, 85), 1);
+		assert_eq!(distance(10, 52), 42);
+		assert_eq!(distance(10, 53), 42);
+	}
+
+	#[test]
+	fn test_perfect_arrangement() {
+		let score = Fm100::score(&Fm100::PERFECT).unwrap();
+		assert_eq!(score.total_error(), 0);
+		assert_eq!(score.sqrt_total_error(), 0.0);
+		assert_eq!(score.bipolarity(), 0.0);
+		assert_eq!(score.axis(), None);
+		assert_eq!(score.classify(), Some(Classification::NORMAL));
+		assert_eq!(score.polar().count(), CAPS);
+	}
+
+	#[test]
+	fn test_axes() {
+		// the protan axis lies between trays 1 and 2, whose fixed caps stay in place
+		for (center, axis) in [(22, Axis::Protan), (15, Axis::Deutan), (5, Axis::Tritan)] {
+			let score = Fm100::score(&confuse(center, 10)).unwrap();
+			assert!(score.bipolarity() > BIPOLARITY_LIMIT);
+			assert_eq!(score.axis(), Some(axis), "axis at cap {}", score.axis_cap());
+		}
+	}
+
+	#[test]
+	fn test_classify() {
+		let score = Fm100::score(&confuse(15, 14)).unwrap();
+		assert_eq!(score.discrimination(), Discrimination::Low);
+		let classification = score.classify().unwrap();
+		assert_eq!(classification.vision, ColorVision::Deuteranomaly);
+		assert!(classification.severity > 0.0);
+		assert!(score.red_green_error() > score.blue_yellow_error());
+	}
+
+	#[test]
+	fn test_invalid_arrangements() {
+		let mut order = Fm100::PERFECT;
+		order[1] = 2;
+		assert_eq!(Fm100::score(&order), Err(ArrangementError::DuplicateCap(2)));
+		order[1] = 0;
+		assert_eq!(Fm100::score(&order), Err(ArrangementError::InvalidCap(0)));
+
+		// a fixed cap moved within its tray
+		let mut order = Fm100::PERFECT;
+		order.swap(0, 1);
+		assert_eq!(Fm100::score(&order), Err(ArrangementError::MisplacedCap(1)));
+
+		// caps swapped between trays
+		let mut order = Fm100::PERFECT;
+		order.swap(30, 50);
+		assert_eq!(
+			Fm100::score(&order),
+			Err(ArrangementError::MisplacedCap(50))
+		);
+	}
+}
@@ -0,0 +1,489 @@
+//! Inheritance of color vision deficiencies
+//!
+//! Red-green deficiencies and blue cone monochromacy are caused by the
+//! array of [OPN1LW][pubchem-opn1lw] and [OPN1MW][pubchem-opn1mw] opsin
+//! genes on the X chromosome, and are inherited as X-linked recessive
+//! traits. Tritan deficiencies are caused by the
+//! [OPN1SW][pubchem-opn1sw] opsin gene on chromosome 7, and are inherited
+//! as an autosomal dominant trait.
+//!
+//! A [Genotype] holds the alleles of both, and [cross] computes the
+//! probability of each phenotype among the daughters or sons of two parents.
+//!
+//! ```
+//! use achroma::genetics::{cross, Genotype, LmAllele, Sex};
+//! use achroma::ColorVision;
+//!
+//! // a mother with normal color vision, carrying deuteranomaly
+//! let mother = Genotype::mother(ColorVision::Normal, Some(LmAllele::Deuteranomalous)).unwrap();
+//! let father = Genotype::father(ColorVision::Normal).unwrap();
+//!
+//! let sons = cross(&mother, &father, Sex::Male).unwrap();
+//! assert_eq!(sons.vision(ColorVision::Deuteranomaly), 0.5);
+//! assert_eq!(sons.vision(ColorVision::Normal), 0.5);
+//!
+//! // daughters have normal color vision, but half of them are carriers
+//! let daughters = cross(&mother, &father, Sex::Female).unwrap();
+//! assert_eq!(daughters.vision(ColorVision::Normal), 1.0);
+//! assert_eq!(daughters.carriers(), 0.5);
+//! ```
+//!
+//! The model assumes complete penetrance of the tritan alleles, and that
+//! each cone type is present in a daughter if either of her X chromosomes
+//! expresses it, as X-inactivation leaves her with a mosaic of both.
+//!
+//! [pubchem-opn1lw]: <https://pubchem.ncbi.nlm.nih.gov/gene/5956>
+//! [pubchem-opn1mw]: <https://pubchem.ncbi.nlm.nih.gov/gene/2652>
+//! [pubchem-opn1sw]: <https://pubchem.ncbi.nlm.nih.gov/gene/611>
+
+use crate::{ColorVision, ConeCellCond, ConeCellSummary};
+
+/// The biological sex of a person, which determines their X chromosomes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sex {
+	/// Two X chromosomes
+	Female,
+	/// One X chromosome and one Y chromosome
+	Male,
+}
+
+/// An allele of the L/M opsin gene array on the X chromosome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LmAllele {
+	/// An array expressing normal L and M opsins
+	#[default]
+	Normal,
+	/// An array expressing an anomalous opsin in place of the L opsin
+	Protanomalous,
+	/// An array lacking a functional L opsin
+	Protanopic,
+	/// An array expressing an anomalous opsin in place of the M opsin
+	Deuteranomalous,
+	/// An array lacking a functional M opsin
+	Deuteranopic,
+	/// An array lacking both functional L and M opsins, such as from a
+	/// deletion of the locus control region
+	BlueConeMonochromatic,
+}
+
+impl LmAllele {
+	/// All alleles of the L/M opsin gene array
+	pub const ALL: [Self; 6] = [
+		Self::Normal,
+		Self::Protanomalous,
+		Self::Protanopic,
+		Self::Deuteranomalous,
+		Self::Deuteranopic,
+		Self::BlueConeMonochromatic,
+	];
+
+	/// The conditions of the L and M cone cells expressed by the allele
+	pub const fn cones(&self) -> [ConeCellCond; 2] {
+		use ConeCellCond::*;
+		match self {
+			Self::Normal => [Normal, Normal],
+			Self::Protanomalous => [Anomalous, Normal],
+			Self::Protanopic => [Missing, Normal],
+			Self::Deuteranomalous => [Normal, Anomalous],
+			Self::Deuteranopic => [Normal, Missing],
+			Self::BlueConeMonochromatic => [Missing, Missing],
+		}
+	}
+
+	/// Reports if the allele causes a deficiency
+	pub const fn is_deficient(&self) -> bool {
+		!matches!(self, Self::Normal)
+	}
+
+	/// The allele expressing the L and M cone cells of a color vision, if any
+	///
+	/// ```
+	/// use achroma::genetics::LmAllele;
+	/// use achroma::ColorVision;
+	///
+	/// assert_eq!(LmAllele::from_vision(ColorVision::Protanopia), Some(LmAllele::Protanopic));
+	/// assert_eq!(LmAllele::from_vision(ColorVision::Tritanopia), Some(LmAllele::Normal));
+	/// assert_eq!(LmAllele::from_vision(ColorVision::Achromatopsia), None);
+	/// ```
+	pub fn from_vision(vision: ColorVision) -> Option<Self> {
+		// achromatopsia is caused by autosomal genes of the phototransduction cascade
+		if vision == ColorVision::Achromatopsia {
+			return None;
+		}
+		let summary = ConeCellSummary::from(vision);
+		Self::ALL
+			.into_iter()
+			.find(|allele| allele.cones() == [summary.l, summary.m])
+	}
+}
+
+/// An allele of the S opsin gene on chromosome 7
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SAllele {
+	/// A gene expressing a normal S opsin
+	#[default]
+	Normal,
+	/// A gene expressing an anomalous S opsin
+	Tritanomalous,
+	/// A gene expressing a non-functional S opsin
+	Tritanopic,
+}
+
+impl SAllele {
+	/// The condition of the S cone cells expressed by the allele
+	pub const fn cone(&self) -> ConeCellCond {
+		match self {
+			Self::Normal => ConeCellCond::Normal,
+			Self::Tritanomalous => ConeCellCond::Anomalous,
+			Self::Tritanopic => ConeCellCond::Missing,
+		}
+	}
+
+	/// The allele expressing the condition of a S cone cell
+	pub const fn from_cone(cond: ConeCellCond) -> Self {
+		match cond {
+			ConeCellCond::Normal => Self::Normal,
+			ConeCellCond::Anomalous => Self::Tritanomalous,
+			ConeCellCond::Missing => Self::Tritanopic,
+		}
+	}
+}
+
+/// An error when building a genotype or crossing two genotypes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeneticsError {
+	/// The inheritance of the color vision is not modeled, such as the
+	/// autosomal recessive inheritance of achromatopsia
+	Unsupported(ColorVision),
+	/// The carried allele is inconsistent with the color vision of the carrier
+	Carrier(LmAllele),
+	/// The parent does not have the expected sex
+	Sex(Sex),
+}
+
+/// The alleles of a person for the opsin genes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Genotype {
+	/// The L/M opsin gene array on the X chromosome inherited from the mother
+	pub maternal_x: LmAllele,
+	/// The L/M opsin gene array on the X chromosome inherited from the father,
+	/// or [None] for males, who inherit a Y chromosome instead
+	pub paternal_x: Option<LmAllele>,
+	/// The S opsin genes on both copies of chromosome 7
+	pub s: [SAllele; 2],
+}
+
+impl Genotype {
+	/// Creates a new female genotype
+	pub const fn female(maternal_x: LmAllele, paternal_x: LmAllele, s: [SAllele; 2]) -> Self {
+		Self {
+			maternal_x,
+			paternal_x: Some(paternal_x),
+			s,
+		}
+	}
+
+	/// Creates a new male genotype
+	pub const fn male(x: LmAllele, s: [SAllele; 2]) -> Self {
+		Self {
+			maternal_x: x,
+			paternal_x: None,
+			s,
+		}
+	}
+
+	/// The most likely genotype of a mother with a color vision, carrying an allele
+	/// on her other X chromosome. Without a carried allele, a deficient mother is
+	/// assumed to have the same allele on both X chromosomes. A tritan mother is
+	/// assumed to have one normal S opsin gene, as tritan alleles are rare.
+	///
+	/// ```
+	/// use achroma::genetics::{Genotype, GeneticsError, LmAllele};
+	/// use achroma::ColorVision;
+	///
+	/// let mother = Genotype::mother(ColorVision::Protanomaly, Some(LmAllele::Protanopic)).unwrap();
+	/// assert_eq!(mother.vision(), Some(ColorVision::Protanomaly));
+	///
+	/// // a protan and a deutan array together express both L and M opsins
+	/// let error = Genotype::mother(ColorVision::Protanomaly, Some(LmAllele::Deuteranomalous));
+	/// assert_eq!(error, Err(GeneticsError::Carrier(LmAllele::Deuteranomalous)));
+	/// ```
+	pub fn mother(
+		vision: ColorVision,
+		carrier: Option<LmAllele>,
+	) -> Result<Self, GeneticsError> {
+		let allele =
+			LmAllele::from_vision(vision).ok_or(GeneticsError::Unsupported(vision))?;
+		let s = Self::s_alleles(vision);
+		let genotype = Self::female(allele, carrier.unwrap_or(allele), s);
+		match carrier {
+			Some(carrier) if genotype.phenotype() != vision.into() => {
+				Err(GeneticsError::Carrier(carrier))
+			}
+			_ => Ok(genotype),
+		}
+	}
+
+	/// The most likely genotype of a father with a color vision. A tritan father
+	/// is assumed to have one normal S opsin gene, as tritan alleles are rare.
+	pub fn father(vision: ColorVision) -> Result<Self, GeneticsError> {
+		let allele =
+			LmAllele::from_vision(vision).ok_or(GeneticsError::Unsupported(vision))?;
+		Ok(Self::male(allele, Self::s_alleles(vision)))
+	}
+
+	fn s_alleles(vision: ColorVision) -> [SAllele; 2] {
+		[
+			SAllele::from_cone(ConeCellSummary::from(vision).s),
+			SAllele::Normal,
+		]
+	}
+
+	/// The sex of the person
+	pub const fn sex(&self) -> Sex {
+		match self.paternal_x {
+			Some(_) => Sex::Female,
+			None => Sex::Male,
+		}
+	}
+
+	/// Reports if the person carries a deficient L/M allele without expressing it
+	///
+	/// ```
+	/// use achroma::genetics::{Genotype, LmAllele, SAllele};
+	///
+	/// let s = [SAllele::Normal; 2];
+	/// assert!(Genotype::female(LmAllele::Normal, LmAllele::Deuteranopic, s).is_carrier());
+	/// assert!(!Genotype::male(LmAllele::Deuteranopic, s).is_carrier());
+	/// ```
+	pub fn is_carrier(&self) -> bool {
+		let [l, m] = self.lm_cones();
+		let expressed = LmAllele::ALL
+			.into_iter()
+			.find(|allele| allele.cones() == [l, m]);
+		[Some(self.maternal_x), self.paternal_x]
+			.into_iter()
+			.flatten()
+			.any(|allele| allele.is_deficient() && Some(allele) != expressed)
+	}
+
+	fn lm_cones(&self) -> [ConeCellCond; 2] {
+		let [l, m] = self.maternal_x.cones();
+		match self.paternal_x {
+			Some(paternal) => {
+				let [paternal_l, paternal_m] = paternal.cones();
+				[l.healthier(paternal_l), m.healthier(paternal_m)]
+			}
+			None => [l, m],
+		}
+	}
+
+	/// The cone cells expressed by the genotype
+	///
+	/// ```
+	/// use achroma::genetics::{Genotype, LmAllele, SAllele};
+	/// use achroma::ConeCellSummary;
+	///
+	/// let genotype = Genotype::male(LmAllele::Protanopic, [SAllele::Tritanomalous, SAllele::Normal]);
+	/// let summary = genotype.phenotype();
+	/// assert!(summary.l.is_missing());
+	/// assert!(summary.s.is_anomalous());
+	/// ```
+	pub fn phenotype(&self) -> ConeCellSummary {
+		let [l, m] = self.lm_cones();
+		ConeCellSummary::new(l, m, self.s[0].cone().impaired(self.s[1].cone()))
+	}
+
+	/// The color vision expressed by the genotype, or [None] when it has no name,
+	/// such as with both a red-green and a tritan deficiency
+	pub fn vision(&self) -> Option<ColorVision> {
+		ColorVision::try_from(self.phenotype()).ok()
+	}
+}
+
+/// The probabilities of the phenotypes among children
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+	outcomes: [(ConeCellSummary, f32); 8],
+	len: usize,
+	carriers: f32,
+}
+
+impl Distribution {
+	fn add(&mut self, summary: ConeCellSummary, probability: f32) {
+		match self.outcomes[..self.len]
+			.iter_mut()
+			.find(|(outcome, _)| *outcome == summary)
+		{
+			Some((_, total)) => *total += probability,
+			None => {
+				self.outcomes[self.len] = (summary, probability);
+				self.len += 1;
+			}
+		}
+	}
+
+	/// Iterates over each distinct phenotype and its probability
+	pub fn iter(&self) -> impl Iterator<Item = (ConeCellSummary, f32)> + '_ {
+		self.outcomes[..self.len].iter().copied()
+	}
+
+	/// The number of distinct phenotypes
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Reports if there are no phenotypes
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// The probability of a phenotype
+	pub fn probability(&self, summary: ConeCellSummary) -> f32 {
+		self.iter()
+			.filter(|&(outcome, _)| outcome == summary)
+			.map(|(_, probability)| probability)
+			.sum()
+	}
+
+	/// The probability of a color vision
+	pub fn vision(&self, vision: ColorVision) -> f32 {
+		self.probability(vision.into())
+	}
+
+	/// The probability of carrying a deficient L/M allele without expressing it
+	pub const fn carriers(&self) -> f32 {
+		self.carriers
+	}
+}
+
+/// Computes the probabilities of the phenotypes among the children of a sex
+///
+/// ```
+/// use achroma::genetics::{cross, Genotype, Sex};
+/// use achroma::ColorVision;
+///
+/// // all daughters of a protanopic father are carriers
+/// let mother = Genotype::mother(ColorVision::Normal, None).unwrap();
+/// let father = Genotype::father(ColorVision::Protanopia).unwrap();
+/// let daughters = cross(&mother, &father, Sex::Female).unwrap();
+/// assert_eq!(daughters.carriers(), 1.0);
+///
+/// // tritan deficiencies are inherited by half of the children of either sex
+/// let father = Genotype::father(ColorVision::Tritanopia).unwrap();
+/// let sons = cross(&mother, &father, Sex::Male).unwrap();
+/// assert_eq!(sons.vision(ColorVision::Tritanopia), 0.5);
+/// ```
+pub fn cross(
+	mother: &Genotype,
+	father: &Genotype,
+	sex: Sex,
+) -> Result<Distribution, GeneticsError> {
+	let (Some(mother_x), None) = (mother.paternal_x, father.paternal_x) else {
+		return Err(GeneticsError::Sex(match mother.sex() {
+			Sex::Female => Sex::Male,
+			Sex::Male => Sex::Female,
+		}));
+	};
+
+	let mut distribution = Distribution {
+		outcomes: [(ConeCellSummary::NORMAL, 0.0); 8],
+		len: 0,
+		carriers: 0.0,
+	};
+	let probability = 1.0 / 8.0;
+	for maternal_x in [mother.maternal_x, mother_x] {
+		for mother_s in mother.s {
+			for father_s in father.s {
+				let s = [mother_s, father_s];
+				let child = match sex {
+					Sex::Female => {
+						Genotype::female(maternal_x, father.maternal_x, s)
+					}
+					Sex::Male => Genotype::male(maternal_x, s),
+				};
+				distribution.add(child.phenotype(), probability);
+				if child.is_carrier() {
+					distribution.carriers += probability;
+				}
+			}
+		}
+	}
+	Ok(distribution)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_distribution_sums_to_one() {
+		let visions = [
+			ColorVision::Normal,
+			ColorVision::Protanomaly,
+			ColorVision::Deuteranopia,
+			ColorVision::Tritanomaly,
+			ColorVision::Achromatomaly,
+		];
+		for mother in visions {
+			for father in visions {
+				let mother = Genotype::mother(mother, None).unwrap();
+				let father = Genotype::father(father).unwrap();
+				for sex in [Sex::Female, Sex::Male] {
+					let distribution = cross(&mother, &father, sex).unwrap();
+					let total: f32 = distribution.iter().map(|(_, p)| p).sum();
+					assert!((total - 1.0).abs() < 1e-6);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_affected_mother() {
+		let mother = Genotype::mother(ColorVision::Deuteranopia, None).unwrap();
+		let father = Genotype::father(ColorVision::Normal).unwrap();
+
+		let sons = cross(&mother, &father, Sex::Male).unwrap();
+		assert_eq!(sons.vision(ColorVision::Deuteranopia), 1.0);
+		assert_eq!(sons.len(), 1);
+
+		let daughters = cross(&mother, &father, Sex::Female).unwrap();
+		assert_eq!(daughters.vision(ColorVision::Normal), 1.0);
+		assert_eq!(daughters.carriers(), 1.0);
+	}
+
+	#[test]
+	fn test_compound_heterozygote() {
+		let s = [SAllele::Normal; 2];
+		let mother = Genotype::female(LmAllele::Protanopic, LmAllele::Deuteranopic, s);
+		assert_eq!(mother.vision(), Some(ColorVision::Normal));
+		assert!(mother.is_carrier());
+
+		let father = Genotype::father(ColorVision::Protanopia).unwrap();
+		let daughters = cross(&mother, &father, Sex::Female).unwrap();
+		assert_eq!(daughters.vision(ColorVision::Protanopia), 0.5);
+		assert_eq!(daughters.vision(ColorVision::Normal), 0.5);
+	}
+
+	#[test]
+	fn test_errors() {
+		assert_eq!(
+			Genotype::father(ColorVision::Achromatopsia),
+			Err(GeneticsError::Unsupported(ColorVision::Achromatopsia))
+		);
+		let father = Genotype::father(ColorVision::Normal).unwrap();
+		assert_eq!(
+			cross(&father, &father, Sex::Male),
+			Err(GeneticsError::Sex(Sex::Female))
+		);
+		let mother = Genotype::mother(ColorVision::Normal, None).unwrap();
+		assert_eq!(
+			cross(&mother, &mother, Sex::Female),
+			Err(GeneticsError::Sex(Sex::Male))
+		);
+	}
+}
@@ -26,11 +26,41 @@
 //! ```
 #![no_std]
 
+use core::fmt;
 use core::ops::{Index, IndexMut};
+use core::str::FromStr;
+
+pub mod acquired;
+pub mod adaptive;
+pub mod alpha_opic;
+pub mod anomaloscope;
+pub mod cct;
+pub mod cone_contrast;
+pub mod contrast;
+pub mod d15;
+pub mod difference;
+pub mod dkl;
+pub mod fm100;
+pub mod genetics;
+pub mod mesopic;
+pub mod opsin;
+pub mod pedigree;
+pub mod photoreceptor;
+pub mod plate;
+pub mod plate_test;
+pub mod prevalence;
+#[cfg(feature = "serde")]
+mod serde_array;
+pub mod session;
+pub mod shader;
+pub mod simulation;
+pub mod species;
+pub mod spectral;
 
 /// A type of photoreceptor cell which exists in the retina
 /// of a vertebrate's eye, and responsible for color vision
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConeCell {
 	/// A cone cell with a long wavelength known as **L**.
 	/// Responds mostly to red wavelength, and expresses the [OPN1LW][pubchem-ospin] ospin
@@ -89,6 +119,7 @@ impl TryFrom<char> for ConeCell {
 
 /// The condition (or state of health) for a cone cell
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConeCellCond {
 	/// A cone cell which exists and is in a healthy state
 	#[default]
@@ -144,10 +175,38 @@ impl ConeCellCond {
 	pub const fn is_missing(&self) -> bool {
 		matches!(self, Self::Missing)
 	}
+
+	/// Ranks the impairment of the condition, from normal at 0 to missing at 2
+	pub(crate) const fn impairment(&self) -> u8 {
+		match self {
+			Self::Normal => 0,
+			Self::Anomalous => 1,
+			Self::Missing => 2,
+		}
+	}
+
+	/// The less impaired of two conditions
+	pub(crate) const fn healthier(self, other: Self) -> Self {
+		if other.impairment() < self.impairment() {
+			other
+		} else {
+			self
+		}
+	}
+
+	/// The more impaired of two conditions
+	pub(crate) const fn impaired(self, other: Self) -> Self {
+		if other.impairment() > self.impairment() {
+			other
+		} else {
+			self
+		}
+	}
 }
 
 /// A discrete representation of the conditions for all three cone cells
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConeCellSummary {
 	pub l: ConeCellCond,
 	pub m: ConeCellCond,
@@ -589,6 +648,7 @@ impl From<ColorVision> for ConeCellSummary {
 /// | Achromatopsia | ⭕ Missing     | ⭕ Missing     | ⭕ Missing     |
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorVision {
 	/// Normal trichromatic vision, where all three cone cells are normal.
 	///
@@ -679,6 +739,47 @@ pub enum ColorVision {
 }
 
 impl ColorVision {
+	/// All types of color vision, in the order they are declared
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	///
+	/// assert_eq!(ColorVision::ALL.len(), 9);
+	/// assert_eq!(ColorVision::ALL[0], ColorVision::Normal);
+	/// ```
+	pub const ALL: [Self; 9] = [
+		Self::Normal,
+		Self::Protanomaly,
+		Self::Protanopia,
+		Self::Deuteranomaly,
+		Self::Deuteranopia,
+		Self::Tritanomaly,
+		Self::Tritanopia,
+		Self::Achromatomaly,
+		Self::Achromatopsia,
+	];
+
+	/// The lowercase name of the color vision
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	///
+	/// assert_eq!(ColorVision::Deuteranopia.name(), "deuteranopia");
+	/// ```
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Normal => "normal",
+			Self::Protanomaly => "protanomaly",
+			Self::Protanopia => "protanopia",
+			Self::Deuteranomaly => "deuteranomaly",
+			Self::Deuteranopia => "deuteranopia",
+			Self::Tritanomaly => "tritanomaly",
+			Self::Tritanopia => "tritanopia",
+			Self::Achromatomaly => "achromatomaly",
+			Self::Achromatopsia => "achromatopsia",
+		}
+	}
+
 	/// Returns whether the color vision is red-green color vision deficiency (CVD).
 	///
 	/// This returns true if the color vision is either protanomaly, protanopia,
@@ -805,6 +906,52 @@ impl ColorVision {
 	}
 }
 
+impl fmt::Display for ColorVision {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for ColorVision {
+	type Err = ();
+	/// Attempt to parse the name of a color vision, ignoring ASCII case.
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	///
+	/// assert_eq!("protanopia".parse(), Ok(ColorVision::Protanopia));
+	/// assert_eq!("Tritanomaly".parse(), Ok(ColorVision::Tritanomaly));
+	/// assert_eq!("trichromacy".parse::<ColorVision>(), Err(()));
+	/// ```
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|vision| vision.name().eq_ignore_ascii_case(s))
+			.ok_or(())
+	}
+}
+
+/// A type of color vision along with its severity, as estimated by a test
+///
+/// The severity ranges from `0.0` (no deficiency) to `1.0` (the full
+/// deficiency), in the same sense as [`simulation::Simulation`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Classification {
+	pub vision: ColorVision,
+	pub severity: f32,
+}
+
+impl Classification {
+	/// A classification of normal color vision
+	pub const NORMAL: Self = Self::new(ColorVision::Normal, 0.0);
+
+	/// Creates a new classification of a color vision with a severity
+	pub const fn new(vision: ColorVision, severity: f32) -> Self {
+		Self { vision, severity }
+	}
+}
+
 impl TryFrom<ConeCellSummary> for ColorVision {
 	type Error = ();
 	fn try_from(summary: ConeCellSummary) -> Result<Self, Self::Error> {
@@ -823,6 +970,278 @@ impl TryFrom<ConeCellSummary> for ColorVision {
 	}
 }
 
+/// An eye, as recorded in per-eye findings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Eye {
+	/// The left eye, abbreviated as OS (oculus sinister)
+	Left,
+	/// The right eye, abbreviated as OD (oculus dexter)
+	Right,
+}
+
+/// The color vision of each eye, as deficiencies can affect one eye more than the other
+///
+/// ```
+/// use achroma::{BinocularColorVision, ColorVision, ConeCellSummary, Eye};
+///
+/// // a deficiency acquired in the left eye
+/// let vision = BinocularColorVision::new(ConeCellSummary::TRITANOMALY, ConeCellSummary::NORMAL);
+/// assert!(!vision.is_symmetric());
+/// assert_eq!(vision.worse_eye(), Some(Eye::Left));
+/// assert_eq!(ColorVision::try_from(vision), Ok(ColorVision::Normal));
+///
+/// assert_eq!(vision.to_string(), "OD normal, OS tritanomaly");
+/// assert_eq!("OD normal, OS tritanomaly".parse(), Ok(vision));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinocularColorVision {
+	pub left: ConeCellSummary,
+	pub right: ConeCellSummary,
+}
+
+impl BinocularColorVision {
+	/// Normal color vision in both eyes
+	pub const NORMAL: Self = Self::both(ConeCellSummary::NORMAL);
+
+	/// Creates a new instance from the summaries of the left and right eyes
+	pub const fn new(left: ConeCellSummary, right: ConeCellSummary) -> Self {
+		Self { left, right }
+	}
+
+	/// Creates a new instance with the same summary in both eyes
+	pub const fn both(summary: ConeCellSummary) -> Self {
+		Self::new(summary, summary)
+	}
+
+	/// Gets the summary of an eye
+	pub const fn eye(&self, eye: Eye) -> ConeCellSummary {
+		match eye {
+			Eye::Left => self.left,
+			Eye::Right => self.right,
+		}
+	}
+
+	/// Reports if both eyes have the same cone cell conditions
+	///
+	/// ```
+	/// use achroma::{BinocularColorVision, ColorVision};
+	///
+	/// let vision = BinocularColorVision::from(ColorVision::Deuteranomaly);
+	/// assert!(vision.is_symmetric());
+	/// ```
+	pub fn is_symmetric(&self) -> bool {
+		self.left == self.right
+	}
+
+	/// The eye with more impaired cone cells, counting a missing cone cell as
+	/// more impaired than an anomalous one, or [None] when both are equally impaired
+	///
+	/// ```
+	/// use achroma::{BinocularColorVision, ConeCellSummary, Eye};
+	///
+	/// let vision = BinocularColorVision::new(ConeCellSummary::PROTANOMALY, ConeCellSummary::DEUTERANOPIA);
+	/// assert_eq!(vision.worse_eye(), Some(Eye::Right));
+	///
+	/// let vision = BinocularColorVision::new(ConeCellSummary::PROTANOMALY, ConeCellSummary::TRITANOMALY);
+	/// assert_eq!(vision.worse_eye(), None);
+	/// ```
+	pub fn worse_eye(&self) -> Option<Eye> {
+		let impairment = |summary: ConeCellSummary| {
+			summary.as_array()
+				.iter()
+				.map(ConeCellCond::impairment)
+				.sum::<u8>()
+		};
+		match impairment(self.left).cmp(&impairment(self.right)) {
+			core::cmp::Ordering::Greater => Some(Eye::Left),
+			core::cmp::Ordering::Less => Some(Eye::Right),
+			core::cmp::Ordering::Equal => None,
+		}
+	}
+
+	/// The cone cell conditions with both eyes open, which are those of the
+	/// better eye as ranked by [BinocularColorVision::worse_eye], or of the
+	/// left eye when both are equally impaired
+	///
+	/// ```
+	/// use achroma::{BinocularColorVision, ConeCellSummary};
+	///
+	/// let vision = BinocularColorVision::new(ConeCellSummary::PROTANOPIA, ConeCellSummary::PROTANOMALY);
+	/// assert_eq!(vision.combined(), ConeCellSummary::PROTANOMALY);
+	///
+	/// let vision = BinocularColorVision::new(ConeCellSummary::PROTANOPIA, ConeCellSummary::DEUTERANOPIA);
+	/// assert_eq!(vision.combined(), ConeCellSummary::PROTANOPIA);
+	/// ```
+	pub fn combined(&self) -> ConeCellSummary {
+		match self.worse_eye() {
+			Some(Eye::Left) => self.right,
+			Some(Eye::Right) | None => self.left,
+		}
+	}
+}
+
+impl From<ConeCellSummary> for BinocularColorVision {
+	fn from(summary: ConeCellSummary) -> Self {
+		Self::both(summary)
+	}
+}
+
+impl From<ColorVision> for BinocularColorVision {
+	fn from(vision: ColorVision) -> Self {
+		Self::both(vision.into())
+	}
+}
+
+impl TryFrom<BinocularColorVision> for ColorVision {
+	type Error = ();
+	/// Attempt to classify the combined color vision of both eyes, which is
+	/// that of the better eye
+	fn try_from(vision: BinocularColorVision) -> Result<Self, Self::Error> {
+		Self::try_from(vision.combined())
+	}
+}
+
+impl fmt::Display for BinocularColorVision {
+	/// Formats the color vision of each eye by its name, or as a tuple of
+	/// cone cell conditions when it has no name, such as `OD protanomaly,
+	/// OS (normal, normal, missing)`, or as `OU protanomaly` when symmetric.
+	///
+	/// ```
+	/// use achroma::{BinocularColorVision, ConeCellCond, ConeCellSummary};
+	///
+	/// let left = ConeCellSummary::new(ConeCellCond::Anomalous, ConeCellCond::Normal, ConeCellCond::Missing);
+	/// let vision = BinocularColorVision::new(left, ConeCellSummary::NORMAL);
+	/// assert_eq!(vision.to_string(), "OD normal, OS (anomalous, normal, missing)");
+	/// assert_eq!(vision.to_string().parse(), Ok(vision));
+	/// ```
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fn summary(f: &mut fmt::Formatter<'_>, summary: ConeCellSummary) -> fmt::Result {
+			match ColorVision::try_from(summary) {
+				Ok(vision) => f.write_str(vision.name()),
+				Err(()) => {
+					let [l, m, s] = summary.as_array().map(cond_name);
+					write!(f, "({}, {}, {})", l, m, s)
+				}
+			}
+		}
+
+		if self.is_symmetric() {
+			f.write_str("OU ")?;
+			return summary(f, self.left);
+		}
+		f.write_str("OD ")?;
+		summary(f, self.right)?;
+		f.write_str(", OS ")?;
+		summary(f, self.left)
+	}
+}
+
+impl FromStr for BinocularColorVision {
+	type Err = ();
+	/// Attempt to parse the color vision of each eye, ignoring ASCII case.
+	///
+	/// Each eye is labelled as `OD` or `right`, `OS` or `left`, or `OU` or
+	/// `both`, followed by the name of a color vision or a tuple of cone
+	/// cell conditions, and eyes are separated by commas.
+	///
+	/// ```
+	/// use achroma::{BinocularColorVision, ColorVision, ConeCellCond, ConeCellSummary};
+	///
+	/// let vision: BinocularColorVision = "left (normal, normal, missing), right normal".parse().unwrap();
+	/// assert_eq!(vision.right, ConeCellSummary::NORMAL);
+	/// assert_eq!(vision.left.s, ConeCellCond::Missing);
+	///
+	/// assert_eq!("OU deuteranopia".parse(), Ok(BinocularColorVision::from(ColorVision::Deuteranopia)));
+	/// assert_eq!("OD protanopia".parse::<BinocularColorVision>(), Err(()));
+	/// ```
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (mut left, mut right) = (None, None);
+		let mut rest = s.trim();
+		while !rest.is_empty() {
+			// split at the first comma outside of a tuple
+			let mut depth = 0;
+			let end = rest
+				.char_indices()
+				.find(|&(_, c)| {
+					match c {
+						'(' => depth += 1,
+						')' => depth -= 1,
+						_ => {}
+					}
+					c == ',' && depth == 0
+				})
+				.map_or(rest.len(), |(index, _)| index);
+			let (part, remainder) = rest.split_at(end);
+			rest = remainder
+				.strip_prefix(',')
+				.unwrap_or(remainder)
+				.trim_start();
+
+			let (label, summary) =
+				part.trim().split_once(char::is_whitespace).ok_or(())?;
+			let summary = parse_summary(summary.trim())?;
+			let eyes = if label.eq_ignore_ascii_case("od")
+				|| label.eq_ignore_ascii_case("right")
+			{
+				(false, true)
+			} else if label.eq_ignore_ascii_case("os")
+				|| label.eq_ignore_ascii_case("left")
+			{
+				(true, false)
+			} else if label.eq_ignore_ascii_case("ou")
+				|| label.eq_ignore_ascii_case("both")
+			{
+				(true, true)
+			} else {
+				return Err(());
+			};
+			for (is_eye, slot) in [(eyes.0, &mut left), (eyes.1, &mut right)] {
+				if is_eye {
+					if slot.is_some() {
+						return Err(());
+					}
+					*slot = Some(summary);
+				}
+			}
+		}
+		Ok(Self::new(left.ok_or(())?, right.ok_or(())?))
+	}
+}
+
+const fn cond_name(cond: ConeCellCond) -> &'static str {
+	match cond {
+		ConeCellCond::Normal => "normal",
+		ConeCellCond::Anomalous => "anomalous",
+		ConeCellCond::Missing => "missing",
+	}
+}
+
+/// Parses the name of a color vision, or a tuple of cone cell conditions
+fn parse_summary(s: &str) -> Result<ConeCellSummary, ()> {
+	let Some(tuple) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) else {
+		return s.parse::<ColorVision>().map(ConeCellSummary::from);
+	};
+	let mut conds = [ConeCellCond::Normal; 3];
+	let mut parts = tuple.split(',');
+	for cond in &mut conds {
+		let part = parts.next().ok_or(())?.trim();
+		*cond = [
+			ConeCellCond::Normal,
+			ConeCellCond::Anomalous,
+			ConeCellCond::Missing,
+		]
+		.into_iter()
+		.find(|&cond| cond_name(cond).eq_ignore_ascii_case(part))
+		.ok_or(())?;
+	}
+	if parts.next().is_some() {
+		return Err(());
+	}
+	Ok(conds.into())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -847,6 +1266,54 @@ mod tests {
 		assert_eq!(ConeCell::try_from('x'), Err(()));
 	}
 
+	#[test]
+	fn test_binocular_parse_errors() {
+		assert_eq!("".parse::<BinocularColorVision>(), Err(()));
+		assert_eq!(
+			"OU normal, OD normal".parse::<BinocularColorVision>(),
+			Err(())
+		);
+		assert_eq!("OX normal".parse::<BinocularColorVision>(), Err(()));
+		assert_eq!(
+			"OU (normal, normal)".parse::<BinocularColorVision>(),
+			Err(())
+		);
+		assert_eq!("OU trichromacy".parse::<BinocularColorVision>(), Err(()));
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_binocular_serde() {
+		let vision = BinocularColorVision::new(
+			ConeCellSummary::PROTANOPIA,
+			ConeCellSummary::NORMAL,
+		);
+		let json = serde_json::to_string(&vision).unwrap();
+		assert_eq!(
+			serde_json::from_str::<BinocularColorVision>(&json).unwrap(),
+			vision
+		);
+	}
+
+	#[test]
+	fn test_binocular_combined() {
+		let vision = BinocularColorVision::new(
+			ConeCellSummary::PROTANOPIA,
+			ConeCellSummary::DEUTERANOPIA,
+		);
+		assert_eq!(vision.combined(), ConeCellSummary::PROTANOPIA);
+		assert_eq!(ColorVision::try_from(vision), Ok(ColorVision::Protanopia));
+		assert_eq!(vision.eye(Eye::Left), ConeCellSummary::PROTANOPIA);
+		assert_eq!(vision.worse_eye(), None);
+
+		let vision = BinocularColorVision::new(
+			ConeCellSummary::DEUTERANOPIA,
+			ConeCellSummary::TRITANOMALY,
+		);
+		assert_eq!(vision.combined(), ConeCellSummary::TRITANOMALY);
+		assert_eq!(ColorVision::try_from(vision), Ok(ColorVision::Tritanomaly));
+	}
+
 	#[test]
 	fn test_summary_default() {
 		let normal = ConeCellSummary::default();
@@ -922,7 +1389,7 @@ mod tests {
 			ConeCellCond::Anomalous,
 		);
 
-		tritanopia[3];
+		let _ = tritanopia[3];
 	}
 
 	#[test]
@@ -966,7 +1433,7 @@ mod tests {
 	#[should_panic]
 	fn test_sumary_index_char_panic() {
 		let tritanopia = ConeCellSummary::TRITANOMALY;
-		tritanopia['x'];
+		let _ = tritanopia['x'];
 	}
 
 	#[test]
@@ -1171,6 +1638,16 @@ mod tests {
 		assert!(!ColorVision::Achromatopsia.is_dichromacy());
 	}
 
+	#[test]
+	fn test_cv_display_from_str_roundtrip() {
+		for vision in ColorVision::ALL {
+			let name = vision.name();
+			assert_eq!(name.parse(), Ok(vision));
+			assert_eq!(name.to_ascii_uppercase().parse(), Ok(vision));
+		}
+		assert_eq!("".parse::<ColorVision>(), Err(()));
+	}
+
 	#[test]
 	fn test_cv_try_from_summary() {
 		assert_eq!(
@@ -0,0 +1,480 @@
+//! Rod vision, and color appearance from daylight to night
+//!
+//! Rod cells follow the CIE 1951 scotopic luminous efficiency function
+//! V'(λ), which peaks at 507 nm instead of the 555 nm of the photopic V(λ).
+//! Between the photopic and scotopic ranges, from about 0.005 to 5 cd/m²,
+//! both cones and rods contribute to vision, and luminance follows the
+//! mesopic photometry of CIE 191:2010, "Recommended system for mesopic
+//! photometry based on visual performance". It mixes both functions with a
+//! coefficient `m` that depends on the adaptation luminance.
+//!
+//! A [MesopicSimulation] approximates the appearance of colors at low light
+//! for a type of color vision by blending its cone based simulation with
+//! the gray seen by rods, weighted by the same coefficient. Reds darken and
+//! blues brighten as the light fades (the Purkinje shift), and colors fade
+//! out entirely in scotopic vision. People with achromatopsia lack working
+//! cone cells, and see through their rods at any luminance.
+//!
+//! ```
+//! use achroma::mesopic::{Adaptation, MesopicSimulation};
+//! use achroma::simulation::Simulation;
+//! use achroma::ColorVision;
+//!
+//! // a dashboard at night, adapted to about 0.1 cd/m²
+//! let deuteranomaly = Simulation::from(ColorVision::Deuteranomaly);
+//! let night = MesopicSimulation::new(deuteranomaly, &Adaptation::display(0.1));
+//! let day = MesopicSimulation::new(deuteranomaly, &Adaptation::display(100.0));
+//!
+//! // a red warning light loses contrast against a dark gray panel
+//! let (red, panel) = ([200, 0, 0], [40, 40, 40]);
+//! assert!(night.contrast_ratio_srgb8(red, panel) < day.contrast_ratio_srgb8(red, panel));
+//! ```
+
+use core::fmt;
+
+use crate::simulation::{
+	clamp_rgb, decode_srgb8, encode_srgb8, lerp_matrix, mul, Matrix3, Simulation, IDENTITY,
+	LUMINANCE,
+};
+use crate::species::PRIMARIES;
+use crate::ColorVision;
+
+/// The luminous efficacy of photopic vision at 555 nm, in lumens per watt
+pub const PHOTOPIC_EFFICACY: f32 = 683.0;
+
+/// The luminous efficacy of scotopic vision at 507 nm, in lumens per watt
+pub const SCOTOPIC_EFFICACY: f32 = 1699.0;
+
+/// The photopic luminance in cd/m² below which vision is scotopic
+pub const SCOTOPIC_LIMIT: f32 = 0.005;
+
+/// The photopic luminance in cd/m² above which vision is photopic
+pub const PHOTOPIC_LIMIT: f32 = 5.0;
+
+/// The shortest wavelength of the tabulated efficiency functions, in nanometers
+const FIRST_WAVELENGTH: f32 = 380.0;
+
+/// The step between tabulated wavelengths, in nanometers
+const STEP: f32 = 10.0;
+
+/// The CIE 1924 photopic luminous efficiency function V(λ), from 380 to 780 nm
+const PHOTOPIC: [f32; 41] = [
+	3.9e-5, 1.2e-4, 3.96e-4, 0.00121, 0.004, 0.0116, 0.023, 0.038, 0.06, 0.09098, 0.13902,
+	0.20802, 0.323, 0.503, 0.71, 0.862, 0.954, 0.99495, 0.995, 0.952, 0.87, 0.757, 0.631,
+	0.503, 0.381, 0.265, 0.175, 0.107, 0.061, 0.032, 0.017, 0.00821, 0.004102, 0.002091,
+	0.001047, 5.2e-4, 2.49e-4, 1.2e-4, 6e-5, 3e-5, 1.5e-5,
+];
+
+/// The CIE 1951 scotopic luminous efficiency function V'(λ), from 380 to 780 nm
+const SCOTOPIC: [f32; 41] = [
+	5.89e-4, 0.002209, 0.00929, 0.03484, 0.0966, 0.1998, 0.3281, 0.455, 0.567, 0.676, 0.793,
+	0.904, 0.982, 0.997, 0.935, 0.811, 0.65, 0.481, 0.3288, 0.2076, 0.1212, 0.0655, 0.03315,
+	0.01593, 0.00737, 0.003335, 0.001497, 6.77e-4, 3.129e-4, 1.48e-4, 7.15e-5, 3.533e-5,
+	1.78e-5, 9.14e-6, 4.78e-6, 2.546e-6, 1.379e-6, 7.6e-7, 4.25e-7, 2.41e-7, 1.39e-7,
+];
+
+/// Linearly interpolates a tabulated efficiency function, which is zero outside the table
+fn interpolate(table: &[f32; 41], wavelength: f32) -> f32 {
+	let position = (wavelength - FIRST_WAVELENGTH) / STEP;
+	if !(0.0..=40.0).contains(&position) {
+		return 0.0;
+	}
+	let lower = libm::floorf(position) as usize;
+	if lower >= 40 {
+		return table[40];
+	}
+	let t = position - lower as f32;
+	table[lower] + (table[lower + 1] - table[lower]) * t
+}
+
+/// The photopic luminous efficiency V(λ) at a wavelength in nanometers,
+/// which peaks at 1.0 around 555 nm
+///
+/// ```
+/// use achroma::mesopic::photopic_efficiency;
+///
+/// assert!(photopic_efficiency(555.0) > 0.99);
+/// assert_eq!(photopic_efficiency(800.0), 0.0);
+/// ```
+pub fn photopic_efficiency(wavelength: f32) -> f32 {
+	interpolate(&PHOTOPIC, wavelength)
+}
+
+/// The scotopic luminous efficiency V'(λ) of rod cells at a wavelength in
+/// nanometers, which peaks at 1.0 around 507 nm
+///
+/// ```
+/// use achroma::mesopic::{photopic_efficiency, scotopic_efficiency};
+///
+/// assert!(scotopic_efficiency(507.0) > 0.99);
+/// // rods are almost blind to red light
+/// assert!(scotopic_efficiency(650.0) < 0.01 * photopic_efficiency(650.0));
+/// ```
+pub fn scotopic_efficiency(wavelength: f32) -> f32 {
+	interpolate(&SCOTOPIC, wavelength)
+}
+
+/// The mesopic luminous efficiency at a wavelength in nanometers, mixing
+/// the photopic and scotopic functions with a coefficient between 0.0
+/// (scotopic) and 1.0 (photopic), normalized to a peak of 1.0
+pub fn mesopic_efficiency(coefficient: f32, wavelength: f32) -> f32 {
+	let m = coefficient.clamp(0.0, 1.0);
+	let mix = |wavelength| {
+		m * photopic_efficiency(wavelength) + (1.0 - m) * scotopic_efficiency(wavelength)
+	};
+	let mut peak: f32 = 0.0;
+	let mut sample = 500.0;
+	while sample <= 560.0 {
+		peak = peak.max(mix(sample));
+		sample += 1.0;
+	}
+	mix(wavelength) / peak
+}
+
+/// The range of luminances which a visual system is adapted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Regime {
+	/// Daylight vision, mediated by cone cells
+	Photopic,
+	/// Twilight vision, mediated by both cone cells and rod cells
+	Mesopic,
+	/// Night vision, mediated by rod cells
+	Scotopic,
+}
+
+impl Regime {
+	/// Gets the lowercase name of the regime, such as `"mesopic"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Photopic => "photopic",
+			Self::Mesopic => "mesopic",
+			Self::Scotopic => "scotopic",
+		}
+	}
+}
+
+impl fmt::Display for Regime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+/// The luminance which vision is adapted to, given as its photopic and
+/// scotopic luminance in cd/m²
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Adaptation {
+	photopic: f32,
+	scotopic: f32,
+}
+
+impl Adaptation {
+	/// Creates a new adaptation from a photopic luminance in cd/m², and the
+	/// scotopic to photopic (S/P) ratio of the light source
+	pub fn new(photopic: f32, sp_ratio: f32) -> Self {
+		Self {
+			photopic,
+			scotopic: photopic * sp_ratio,
+		}
+	}
+
+	/// Creates a new adaptation to an sRGB display showing gray at a photopic
+	/// luminance in cd/m², using the S/P ratio of the white of the display
+	pub fn display(photopic: f32) -> Self {
+		Self::new(photopic, Primaries::get().sp_ratio())
+	}
+
+	/// The photopic luminance in cd/m²
+	pub const fn photopic(&self) -> f32 {
+		self.photopic
+	}
+
+	/// The scotopic luminance in cd/m²
+	pub const fn scotopic(&self) -> f32 {
+		self.scotopic
+	}
+
+	/// The coefficient `m` between 0.0 (scotopic) and 1.0 (photopic), which
+	/// weighs the photopic efficiency function in mesopic photometry
+	///
+	/// ```
+	/// use achroma::mesopic::Adaptation;
+	///
+	/// assert_eq!(Adaptation::new(100.0, 1.0).coefficient(), 1.0);
+	/// assert_eq!(Adaptation::new(0.001, 1.0).coefficient(), 0.0);
+	/// let m = Adaptation::new(0.1, 1.5).coefficient();
+	/// assert!(m > 0.3 && m < 0.6);
+	/// ```
+	pub fn coefficient(&self) -> f32 {
+		self.solve().0
+	}
+
+	/// The mesopic luminance in cd/m², which equals the photopic luminance
+	/// in photopic vision and the scotopic luminance in scotopic vision
+	pub fn luminance(&self) -> f32 {
+		self.solve().1
+	}
+
+	/// The regime of vision at this adaptation
+	///
+	/// ```
+	/// use achroma::mesopic::{Adaptation, Regime};
+	///
+	/// assert_eq!(Adaptation::display(250.0).regime(), Regime::Photopic);
+	/// assert_eq!(Adaptation::display(0.5).regime(), Regime::Mesopic);
+	/// assert_eq!(Adaptation::display(0.001).regime(), Regime::Scotopic);
+	/// ```
+	pub fn regime(&self) -> Regime {
+		match self.coefficient() {
+			m if m >= 1.0 => Regime::Photopic,
+			m if m <= 0.0 => Regime::Scotopic,
+			_ => Regime::Mesopic,
+		}
+	}
+
+	/// Solves for the coefficient and the mesopic luminance by the iteration
+	/// of CIE 191:2010
+	fn solve(&self) -> (f32, f32) {
+		const A: f32 = 0.7670;
+		const B: f32 = 0.3334;
+		// the scotopic efficiency at 555 nm, relative to its peak
+		const V_PRIME_555: f32 = PHOTOPIC_EFFICACY / SCOTOPIC_EFFICACY;
+
+		let luminance = |m: f32| {
+			(m * self.photopic + (1.0 - m) * self.scotopic * V_PRIME_555)
+				/ (m + (1.0 - m) * V_PRIME_555)
+		};
+		let mut m = 0.5;
+		for _ in 0..100 {
+			let next = (A + B * libm::log10f(luminance(m))).clamp(0.0, 1.0);
+			let done = libm::fabsf(next - m) < 1e-6;
+			m = next;
+			if done {
+				break;
+			}
+		}
+		(m, luminance(m))
+	}
+}
+
+/// The photopic and scotopic luminance of each sRGB primary at full intensity,
+/// relative to the photopic luminance of white
+#[derive(Debug, Clone, Copy)]
+struct Primaries {
+	scotopic: [f32; 3],
+}
+
+impl Primaries {
+	/// Integrates the approximate emission spectra of the primaries, scaled
+	/// so that their photopic luminances match sRGB
+	fn get() -> Self {
+		let mut scotopic = [0.0; 3];
+		for (value, ((center, width), photopic)) in scotopic
+			.iter_mut()
+			.zip(PRIMARIES.into_iter().zip(LUMINANCE))
+		{
+			let (mut v, mut v_prime) = (0.0, 0.0);
+			let mut wavelength = FIRST_WAVELENGTH;
+			while wavelength <= 780.0 {
+				let distance = (wavelength - center) / width;
+				let emission = libm::expf(-0.5 * distance * distance);
+				v += emission * photopic_efficiency(wavelength);
+				v_prime += emission * scotopic_efficiency(wavelength);
+				wavelength += 1.0;
+			}
+			*value = photopic * v_prime / v * SCOTOPIC_EFFICACY / PHOTOPIC_EFFICACY;
+		}
+		Self { scotopic }
+	}
+
+	/// The S/P ratio of white
+	fn sp_ratio(&self) -> f32 {
+		self.scotopic.iter().sum()
+	}
+
+	/// The weight of each channel in the response of rods, adding up to 1.0
+	fn rods(&self) -> [f32; 3] {
+		let white = self.sp_ratio();
+		self.scotopic.map(|value| value / white)
+	}
+}
+
+/// A simulation of a type of color vision at an adaptation luminance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MesopicSimulation {
+	matrix: Matrix3,
+	coefficient: f32,
+	rods: [f32; 3],
+	sp_ratio: f32,
+}
+
+impl MesopicSimulation {
+	/// Creates a new simulation of a type of color vision at an adaptation
+	///
+	/// The cone based simulation is used for photopic vision. Achromatopsia
+	/// is simulated as rod monochromacy at its severity.
+	pub fn new(simulation: Simulation, adaptation: &Adaptation) -> Self {
+		let primaries = Primaries::get();
+		let rods = primaries.rods();
+		let rod_matrix = [rods, rods, rods];
+		let cones = match simulation.vision() {
+			ColorVision::Achromatopsia => {
+				lerp_matrix(&IDENTITY, &rod_matrix, simulation.severity())
+			}
+			_ => simulation.matrix(),
+		};
+		let coefficient = adaptation.coefficient();
+		Self {
+			matrix: lerp_matrix(&rod_matrix, &cones, coefficient),
+			coefficient,
+			rods,
+			sp_ratio: primaries.sp_ratio(),
+		}
+	}
+
+	/// The matrix transforming linear RGB into the simulated linear RGB
+	pub const fn matrix(&self) -> Matrix3 {
+		self.matrix
+	}
+
+	/// The coefficient `m` of the adaptation, between 0.0 (scotopic) and 1.0 (photopic)
+	pub const fn coefficient(&self) -> f32 {
+		self.coefficient
+	}
+
+	/// Simulates a color given in linear RGB, with each channel in `0.0..=1.0`
+	///
+	/// ```
+	/// use achroma::mesopic::{Adaptation, MesopicSimulation};
+	/// use achroma::simulation::Simulation;
+	/// use achroma::ColorVision;
+	///
+	/// // at night, every color vision sees the same grays
+	/// let night = Adaptation::display(0.001);
+	/// let normal = MesopicSimulation::new(Simulation::from(ColorVision::Normal), &night);
+	/// let protanopia = MesopicSimulation::new(Simulation::from(ColorVision::Protanopia), &night);
+	/// let rgb = [0.8, 0.3, 0.1];
+	/// assert_eq!(normal.simulate_linear(rgb), protanopia.simulate_linear(rgb));
+	/// ```
+	pub fn simulate_linear(&self, rgb: [f32; 3]) -> [f32; 3] {
+		clamp_rgb(mul(&self.matrix, rgb))
+	}
+
+	/// Simulates an 8-bit sRGB color
+	pub fn simulate_srgb(&self, rgb: [u8; 3]) -> [u8; 3] {
+		encode_srgb8(self.simulate_linear(decode_srgb8(rgb)))
+	}
+
+	/// The mesopic luminance of a linear RGB color relative to white, from
+	/// 0.0 (black) to 1.0 (white)
+	///
+	/// ```
+	/// use achroma::contrast::relative_luminance;
+	/// use achroma::mesopic::{Adaptation, MesopicSimulation};
+	/// use achroma::simulation::Simulation;
+	/// use achroma::ColorVision;
+	///
+	/// let normal = Simulation::from(ColorVision::Normal);
+	/// let simulation = MesopicSimulation::new(normal, &Adaptation::display(0.05));
+	/// let (red, blue) = ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+	/// // the Purkinje shift
+	/// assert!(relative_luminance(red) > relative_luminance(blue));
+	/// assert!(simulation.luminance(red) < simulation.luminance(blue));
+	/// ```
+	pub fn luminance(&self, rgb: [f32; 3]) -> f32 {
+		let m = self.coefficient;
+		let photopic: f32 = (0..3)
+			.map(|channel| LUMINANCE[channel] * rgb[channel])
+			.sum();
+		let scotopic: f32 = (0..3)
+			.map(|channel| self.rods[channel] * rgb[channel])
+			.sum();
+		// scotopic luminance in photopic units, where white has an S/P ratio
+		let scale = self.sp_ratio * PHOTOPIC_EFFICACY / SCOTOPIC_EFFICACY;
+		(m * photopic + (1.0 - m) * scale * scotopic) / (m + (1.0 - m) * scale)
+	}
+
+	/// The contrast ratio between two 8-bit sRGB colors, from their mesopic
+	/// luminances, as in [contrast_ratio](crate::contrast::contrast_ratio)
+	pub fn contrast_ratio_srgb8(&self, a: [u8; 3], b: [u8; 3]) -> f32 {
+		crate::contrast::contrast_ratio(
+			self.luminance(decode_srgb8(a)),
+			self.luminance(decode_srgb8(b)),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_tables_peak() {
+		let peak = |efficiency: fn(f32) -> f32| {
+			(380..=780)
+				.map(|wavelength| wavelength as f32)
+				.max_by(|&a, &b| efficiency(a).total_cmp(&efficiency(b)))
+				.unwrap()
+		};
+		assert!((peak(photopic_efficiency) - 555.0).abs() <= 5.0);
+		assert!((peak(scotopic_efficiency) - 507.0).abs() <= 5.0);
+		assert!((mesopic_efficiency(0.5, 530.0) - 1.0).abs() < 0.05);
+		assert!((mesopic_efficiency(1.0, 600.0) - 0.631 / 0.995).abs() < 1e-5);
+	}
+
+	#[test]
+	fn test_coefficient_limits() {
+		for sp_ratio in [0.6, 1.0, 2.0] {
+			let high = Adaptation::new(PHOTOPIC_LIMIT * 2.0, sp_ratio);
+			assert_eq!(high.coefficient(), 1.0);
+			assert!((high.luminance() - high.photopic()).abs() < 1e-4);
+			let low = Adaptation::new(SCOTOPIC_LIMIT / 2.0, sp_ratio);
+			assert_eq!(low.coefficient(), 0.0);
+			assert!((low.luminance() - low.scotopic()).abs() < 1e-6);
+		}
+		// with an S/P ratio of 1.0, all three luminances are equal
+		let equal = Adaptation::new(0.3, 1.0);
+		assert!((equal.luminance() - 0.3).abs() < 1e-5);
+		let mut previous = 0.0;
+		for photopic in [0.01, 0.03, 0.1, 0.3, 1.0, 3.0] {
+			let m = Adaptation::display(photopic).coefficient();
+			assert!(m > previous);
+			previous = m;
+		}
+	}
+
+	#[test]
+	fn test_display_primaries() {
+		let primaries = Primaries::get();
+		// the white of displays with narrow primaries has an S/P ratio of 2 to 3.5
+		assert!(primaries.sp_ratio() > 2.0 && primaries.sp_ratio() < 3.5);
+		let [red, green, blue] = primaries.rods();
+		// rods barely see the red primary, and blue weighs far more than in V(λ)
+		assert!(red < 0.05 && blue > 0.3 && green > 0.3);
+	}
+
+	#[test]
+	fn test_simulation_limits() {
+		let day = Adaptation::display(100.0);
+		for vision in ColorVision::ALL {
+			let simulation = Simulation::new(vision, 0.7);
+			let mesopic = MesopicSimulation::new(simulation, &day);
+			if vision != ColorVision::Achromatopsia {
+				let (a, b) = (mesopic.matrix(), simulation.matrix());
+				assert!(a
+					.iter()
+					.flatten()
+					.zip(b.iter().flatten())
+					.all(|(a, b)| (a - b).abs() < 1e-6));
+			}
+			let white = mesopic.simulate_linear([1.0; 3]);
+			assert!(white.iter().all(|&c| (c - 1.0).abs() < 1e-4));
+			assert!((mesopic.luminance([1.0; 3]) - 1.0).abs() < 1e-5);
+		}
+		let achromatopsia =
+			MesopicSimulation::new(Simulation::from(ColorVision::Achromatopsia), &day);
+		let [r, g, b] = achromatopsia.simulate_srgb([40, 180, 200]);
+		assert!(r == g && g == b);
+	}
+}
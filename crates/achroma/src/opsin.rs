@@ -0,0 +1,396 @@
+//! Model of the L/M opsin gene array and the spectral tuning of its pigments
+//!
+//! The X chromosome carries a head-to-tail array of one L opsin gene
+//! followed by one or more M opsin genes. Unequal recombination between the
+//! highly similar genes produces hybrid genes, and changes the number of
+//! genes. Only the first two genes of the array are expressed, the first in
+//! L cones and the second in M cones, and the locus control region (LCR)
+//! upstream of the array is needed to express either.
+//!
+//! The peak sensitivity (λmax) of each pigment is predicted from the amino
+//! acids at the spectral tuning sites, where each site with the residue of
+//! the L opsin shifts the peak from [M_PEAK] towards [L_PEAK]. The shifts are
+//! approximately additive, following the site-directed mutagenesis by
+//! Asenjo, Rim & Oprian, "Molecular determinants of human red/green color
+//! discrimination", Neuron 12 (1994).
+//!
+//! ```
+//! use achroma::opsin::{Gene, Opsin, OpsinArray};
+//! use achroma::ColorVision;
+//!
+//! // a hybrid with exons 1 to 4 of L and exon 5 of M, which isn't expressed
+//! // as the third gene of the array
+//! let hybrid = Gene::hybrid([Opsin::Long, Opsin::Long, Opsin::Long, Opsin::Long, Opsin::Medium, Opsin::Medium]);
+//! let genes = [Gene::LONG, Gene::MEDIUM, hybrid];
+//! let array = OpsinArray::new(&genes);
+//! assert_eq!(array.copy_number(), 3);
+//!
+//! let prediction = array.predict();
+//! let classification = prediction.classification().unwrap();
+//! assert_eq!(classification.vision, ColorVision::Normal);
+//!
+//! // a hybrid with exon 5 of L expresses an L-like pigment in M cones
+//! let hybrid = Gene::hybrid([Opsin::Medium, Opsin::Medium, Opsin::Medium, Opsin::Medium, Opsin::Long, Opsin::Long]);
+//! let genes = [Gene::LONG, hybrid];
+//! let classification = OpsinArray::new(&genes).predict().classification().unwrap();
+//! assert_eq!(classification.vision, ColorVision::Deuteranomaly);
+//! assert!(classification.severity > 0.5);
+//! ```
+
+use crate::anomaloscope::Pigments;
+use crate::genetics::LmAllele;
+use crate::spectral::{govardovskii_a1, L_PEAK, M_PEAK};
+use crate::{Classification, ColorVision, ConeCellCond, ConeCellSummary};
+
+/// The spectral tuning sites, by amino acid position
+pub const TUNING_SITES: [u16; 7] = [116, 180, 230, 233, 277, 285, 309];
+
+/// The approximate shift in nanometers of the peak sensitivity, for the
+/// residue of the L opsin at each of the [TUNING_SITES]. The shifts add up to
+/// the difference between [L_PEAK] and [M_PEAK].
+pub const TUNING_SHIFTS: [f32; 7] = [1.0, 4.0, 1.0, 1.0, 7.0, 14.0, 1.0];
+
+/// The exons, numbered from 1, encoding each of the [TUNING_SITES]
+const TUNING_EXONS: [usize; 7] = [2, 3, 4, 4, 5, 5, 5];
+
+/// The number of genes expressed from the start of the array
+pub const EXPRESSED_GENES: usize = 2;
+
+/// The smallest difference in nanometers between the peaks of two pigments
+/// for them to be distinguished as separate classes of cone cells
+pub const MIN_SEPARATION: f32 = 1.0;
+
+/// The opsin that a residue or exon originates from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Opsin {
+	/// The L opsin encoded by OPN1LW
+	Long,
+	/// The M opsin encoded by OPN1MW
+	Medium,
+}
+
+/// An error when describing a gene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpsinError {
+	/// The amino acid position is not one of the [TUNING_SITES]
+	Site(u16),
+}
+
+/// An opsin gene, described by the residues at its spectral tuning sites
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gene {
+	sites: [Opsin; 7],
+}
+
+impl Gene {
+	/// A gene of the L opsin
+	pub const LONG: Self = Self::new([Opsin::Long; 7]);
+
+	/// A gene of the M opsin
+	pub const MEDIUM: Self = Self::new([Opsin::Medium; 7]);
+
+	/// Creates a new gene from the residues at each of the [TUNING_SITES]
+	pub const fn new(sites: [Opsin; 7]) -> Self {
+		Self { sites }
+	}
+
+	/// Creates a new hybrid gene from the origin of each of its six exons.
+	/// Exon 1 and exon 6 are identical in both opsins and don't tune the pigment.
+	///
+	/// ```
+	/// use achroma::opsin::{Gene, Opsin};
+	///
+	/// let gene = Gene::hybrid([Opsin::Long, Opsin::Long, Opsin::Medium, Opsin::Medium, Opsin::Medium, Opsin::Medium]);
+	/// assert_eq!(gene.site(116), Ok(Opsin::Long));
+	/// assert_eq!(gene.site(180), Ok(Opsin::Medium));
+	/// ```
+	pub const fn hybrid(exons: [Opsin; 6]) -> Self {
+		let mut sites = [Opsin::Medium; 7];
+		let mut i = 0;
+		while i < sites.len() {
+			sites[i] = exons[TUNING_EXONS[i] - 1];
+			i += 1;
+		}
+		Self::new(sites)
+	}
+
+	/// The residues at each of the [TUNING_SITES]
+	pub const fn sites(&self) -> [Opsin; 7] {
+		self.sites
+	}
+
+	fn index(position: u16) -> Result<usize, OpsinError> {
+		TUNING_SITES
+			.iter()
+			.position(|&site| site == position)
+			.ok_or(OpsinError::Site(position))
+	}
+
+	/// The residue at an amino acid position
+	pub fn site(&self, position: u16) -> Result<Opsin, OpsinError> {
+		Ok(self.sites[Self::index(position)?])
+	}
+
+	/// Sets the residue at an amino acid position, such as from sequencing
+	///
+	/// ```
+	/// use achroma::opsin::{Gene, Opsin, OpsinError};
+	///
+	/// // the common serine/alanine polymorphism at position 180
+	/// let gene = Gene::LONG.with_site(180, Opsin::Medium).unwrap();
+	/// assert!(gene.peak() < Gene::LONG.peak());
+	///
+	/// assert_eq!(Gene::LONG.with_site(181, Opsin::Medium), Err(OpsinError::Site(181)));
+	/// ```
+	pub fn with_site(mut self, position: u16, opsin: Opsin) -> Result<Self, OpsinError> {
+		self.sites[Self::index(position)?] = opsin;
+		Ok(self)
+	}
+
+	/// The predicted peak sensitivity (λmax) in nanometers of the pigment
+	///
+	/// ```
+	/// use achroma::opsin::Gene;
+	/// use achroma::spectral::{L_PEAK, M_PEAK};
+	///
+	/// assert_eq!(Gene::LONG.peak(), L_PEAK);
+	/// assert_eq!(Gene::MEDIUM.peak(), M_PEAK);
+	/// ```
+	pub fn peak(&self) -> f32 {
+		let mut peak = M_PEAK;
+		for (site, shift) in self.sites.iter().zip(TUNING_SHIFTS) {
+			if *site == Opsin::Long {
+				peak += shift;
+			}
+		}
+		peak
+	}
+
+	/// The relative sensitivity of the pigment at a wavelength in nanometers
+	pub fn sensitivity(&self, wavelength: f32) -> f32 {
+		govardovskii_a1(self.peak(), wavelength)
+	}
+}
+
+/// The L/M opsin gene array of an X chromosome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OpsinArray<'a> {
+	genes: &'a [Gene],
+	lcr: bool,
+}
+
+impl<'a> OpsinArray<'a> {
+	/// Creates a new array from its genes in order, with an intact locus control region
+	pub const fn new(genes: &'a [Gene]) -> Self {
+		Self { genes, lcr: true }
+	}
+
+	/// Sets if the locus control region is intact. Without it, no gene is
+	/// expressed, as in blue cone monochromacy.
+	pub const fn with_lcr(mut self, lcr: bool) -> Self {
+		self.lcr = lcr;
+		self
+	}
+
+	/// The genes of the array in order
+	pub const fn genes(&self) -> &'a [Gene] {
+		self.genes
+	}
+
+	/// The number of genes in the array
+	pub const fn copy_number(&self) -> usize {
+		self.genes.len()
+	}
+
+	/// The genes expressed in cone cells, from the start of the array
+	pub fn expressed(&self) -> &'a [Gene] {
+		if !self.lcr {
+			return &[];
+		}
+		&self.genes[..self.genes.len().min(EXPRESSED_GENES)]
+	}
+
+	/// Predicts the pigments and cone cells expressed by the array
+	pub fn predict(&self) -> Prediction {
+		let mut peaks = self.expressed().iter().map(Gene::peak);
+		let (long, medium) = match (peaks.next(), peaks.next()) {
+			(Some(a), Some(b)) if libm::fabsf(a - b) < MIN_SEPARATION => {
+				(a.max(b), None)
+			}
+			(Some(a), Some(b)) => (a.max(b), Some(a.min(b))),
+			(Some(a), None) => (a, None),
+			(None, _) => return Prediction::default(),
+		};
+		let midpoint = (L_PEAK + M_PEAK) / 2.0;
+
+		// a single class of pigment replaces the cone cells it differs most from
+		let Some(medium) = medium else {
+			return if long >= midpoint {
+				Prediction::new(Some(long), None, 1.0)
+			} else {
+				Prediction::new(None, Some(long), 1.0)
+			};
+		};
+
+		// pigments closer together than the normal pigments are anomalous, and
+		// the one further from its normal peak belongs to the anomalous cone
+		// cells, with ties resolved towards the more common deuteranomaly
+		let severity = (1.0 - (long - medium) / (L_PEAK - M_PEAK)).clamp(0.0, 1.0);
+		let prediction = Prediction::new(Some(long), Some(medium), severity);
+		if severity <= 0.0 {
+			prediction
+		} else if libm::fabsf(long - L_PEAK) > libm::fabsf(medium - M_PEAK) {
+			prediction.with_cones(ConeCellCond::Anomalous, ConeCellCond::Normal)
+		} else {
+			prediction.with_cones(ConeCellCond::Normal, ConeCellCond::Anomalous)
+		}
+	}
+}
+
+/// The pigments and cone cells predicted from an opsin gene array
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Prediction {
+	/// The peak wavelength in nanometers of the pigment in L cones, if any
+	pub long: Option<f32>,
+	/// The peak wavelength in nanometers of the pigment in M cones, if any
+	pub medium: Option<f32>,
+	/// The predicted cone cells
+	pub summary: ConeCellSummary,
+	/// The severity of the deficiency, from 0.0 (normal) to 1.0 (a missing cone cell)
+	pub severity: f32,
+}
+
+impl Prediction {
+	fn new(long: Option<f32>, medium: Option<f32>, severity: f32) -> Self {
+		let cond = |peak: Option<f32>| match peak {
+			Some(_) => ConeCellCond::Normal,
+			None => ConeCellCond::Missing,
+		};
+		Self {
+			long,
+			medium,
+			summary: ConeCellSummary::new(
+				cond(long),
+				cond(medium),
+				ConeCellCond::Normal,
+			),
+			severity,
+		}
+	}
+
+	fn with_cones(mut self, l: ConeCellCond, m: ConeCellCond) -> Self {
+		self.summary.l = l;
+		self.summary.m = m;
+		self
+	}
+
+	/// The classification of the predicted cone cells, if they have a name
+	pub fn classification(&self) -> Option<Classification> {
+		let vision = ColorVision::try_from(self.summary).ok()?;
+		Some(Classification::new(vision, self.severity))
+	}
+
+	/// The allele of the array, as used in [inheritance][crate::genetics]
+	pub fn allele(&self) -> LmAllele {
+		LmAllele::ALL
+			.into_iter()
+			.find(|allele| allele.cones() == [self.summary.l, self.summary.m])
+			.unwrap_or_default()
+	}
+}
+
+impl Default for Prediction {
+	/// A prediction without any expressed pigments, as in blue cone monochromacy
+	fn default() -> Self {
+		Self::new(None, None, 1.0)
+	}
+}
+
+impl From<Prediction> for Pigments {
+	fn from(prediction: Prediction) -> Self {
+		Pigments::new(prediction.long, prediction.medium)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_shifts_span_peaks() {
+		let total: f32 = TUNING_SHIFTS.iter().sum();
+		assert_eq!(total, L_PEAK - M_PEAK);
+	}
+
+	#[test]
+	fn test_dichromats() {
+		let genes = [Gene::LONG];
+		let prediction = OpsinArray::new(&genes).predict();
+		assert_eq!(prediction.summary, ConeCellSummary::DEUTERANOPIA);
+		assert_eq!(prediction.allele(), LmAllele::Deuteranopic);
+
+		// a hybrid of mostly M opsin replacing the L gene
+		let hybrid = Gene::LONG.with_site(277, Opsin::Medium).unwrap();
+		let hybrid = hybrid.with_site(285, Opsin::Medium).unwrap();
+		let hybrid = hybrid.with_site(309, Opsin::Medium).unwrap();
+		let hybrid = hybrid.with_site(180, Opsin::Medium).unwrap();
+		let genes = [hybrid, Gene::MEDIUM];
+		let prediction = OpsinArray::new(&genes).predict();
+		assert_eq!(
+			prediction.classification().unwrap().vision,
+			ColorVision::Protanomaly
+		);
+
+		let genes = [Gene::MEDIUM, Gene::MEDIUM];
+		let prediction = OpsinArray::new(&genes).predict();
+		assert_eq!(prediction.summary, ConeCellSummary::PROTANOPIA);
+		assert_eq!(prediction.medium, Some(M_PEAK));
+	}
+
+	#[test]
+	fn test_pigments_straddling_midpoint() {
+		let gene = |sites: &[u16]| {
+			sites.iter().fold(Gene::MEDIUM, |gene, &site| {
+				gene.with_site(site, Opsin::Long).unwrap()
+			})
+		};
+
+		// 545 and 544 nm, each 14 nm from its normal peak
+		let genes = [gene(&[116, 285]), gene(&[285])];
+		let classification = OpsinArray::new(&genes).predict().classification().unwrap();
+		assert_eq!(classification.vision, ColorVision::Deuteranomaly);
+		assert!(classification.severity > 0.95);
+
+		// 548 and 537 nm, where the L-like pigment is further from its peak
+		let genes = [gene(&[180, 285]), gene(&[277])];
+		let classification = OpsinArray::new(&genes).predict().classification().unwrap();
+		assert_eq!(classification.vision, ColorVision::Protanomaly);
+		assert!(libm::fabsf(classification.severity - 18.0 / 29.0) < 1e-6);
+
+		// 549 and 545 nm, where the M-like pigment is further from its peak
+		let genes = [gene(&[180, 230, 285]), gene(&[116, 285])];
+		let classification = OpsinArray::new(&genes).predict().classification().unwrap();
+		assert_eq!(classification.vision, ColorVision::Deuteranomaly);
+		assert!(libm::fabsf(classification.severity - 25.0 / 29.0) < 1e-6);
+	}
+
+	#[test]
+	fn test_lcr_deletion() {
+		let genes = [Gene::LONG, Gene::MEDIUM];
+		let prediction = OpsinArray::new(&genes).with_lcr(false).predict();
+		assert_eq!(prediction.summary, ConeCellSummary::ACHROMATOMALY);
+		assert_eq!(prediction.allele(), LmAllele::BlueConeMonochromatic);
+		assert_eq!(OpsinArray::new(&[]).predict(), prediction);
+	}
+
+	#[test]
+	fn test_anomaloscope_pigments() {
+		let genes = [Gene::LONG, Gene::MEDIUM, Gene::MEDIUM];
+		let prediction = OpsinArray::new(&genes).predict();
+		assert_eq!(prediction.severity, 0.0);
+		assert_eq!(Pigments::from(prediction), Pigments::NORMAL);
+	}
+}
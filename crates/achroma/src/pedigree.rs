@@ -0,0 +1,659 @@
+//! Pedigree analysis of color vision deficiencies
+//!
+//! A [Pedigree] links individuals to their parents, and records the color
+//! vision observed in some of them. From these observations, it infers the
+//! probability that each individual carries or expresses a deficiency, with
+//! red-green deficiencies inherited as X-linked recessive traits and tritan
+//! deficiencies as autosomal dominant traits, as in [genetics][crate::genetics].
+//!
+//! The inference is exact, by enumerating every way the alleles of the
+//! founders could have been passed down, and weighting each by the
+//! frequency of the founder alleles. Founders can carry any deficient allele
+//! at its population frequency, whether or not it's observed in the
+//! pedigree. A pedigree where no such way explains the observations is
+//! inconsistent.
+//!
+//! ```
+//! use achroma::genetics::Sex;
+//! use achroma::pedigree::{Individual, Pedigree};
+//! use achroma::ColorVision;
+//!
+//! let individuals = [
+//!     // 0: a grandfather with deuteranopia
+//!     Individual::new(Sex::Male).with_vision(ColorVision::Deuteranopia),
+//!     // 1: a grandmother with normal color vision
+//!     Individual::new(Sex::Female).with_vision(ColorVision::Normal),
+//!     // 2: their daughter, with normal color vision
+//!     Individual::new(Sex::Female).with_parents(1, 0).with_vision(ColorVision::Normal),
+//!     // 3: her husband, with normal color vision
+//!     Individual::new(Sex::Male).with_vision(ColorVision::Normal),
+//!     // 4: their son, not yet tested
+//!     Individual::new(Sex::Male).with_parents(2, 3),
+//! ];
+//! let inferences = Pedigree::new(&individuals).unwrap().infer().unwrap();
+//!
+//! // the daughter of an affected father is an obligate carrier
+//! assert!((inferences[2].carrier - 1.0).abs() < 1e-6);
+//! // so her son has about half a chance of a red-green deficiency
+//! assert!((inferences[4].red_green - 0.5).abs() < 0.02);
+//! ```
+
+use core::ops::Index;
+
+use crate::genetics::{Genotype, LmAllele, SAllele, Sex};
+use crate::{ColorVision, ConeCellSummary};
+
+/// The largest number of individuals in a pedigree
+pub const MAX_INDIVIDUALS: usize = 32;
+
+/// The largest number of combinations of alleles enumerated for each gene
+pub const MAX_COMBINATIONS: u64 = 1 << 22;
+
+/// The approximate frequency of deficient L/M opsin gene arrays, which is
+/// about the prevalence of red-green deficiencies in males of European ancestry
+pub const LM_FREQUENCY: f32 = 0.08;
+
+/// The approximate frequency of deficient S opsin alleles
+pub const S_FREQUENCY: f32 = 0.0001;
+
+/// An individual in a pedigree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Individual {
+	/// The sex of the individual
+	pub sex: Sex,
+	/// The index of the mother in the pedigree, if known
+	pub mother: Option<usize>,
+	/// The index of the father in the pedigree, if known
+	pub father: Option<usize>,
+	/// The observed color vision, if tested
+	pub vision: Option<ColorVision>,
+}
+
+impl Individual {
+	/// Creates a new individual without known parents or color vision
+	pub const fn new(sex: Sex) -> Self {
+		Self {
+			sex,
+			mother: None,
+			father: None,
+			vision: None,
+		}
+	}
+
+	/// Links the individual to the indices of their mother and father
+	pub const fn with_parents(mut self, mother: usize, father: usize) -> Self {
+		self.mother = Some(mother);
+		self.father = Some(father);
+		self
+	}
+
+	/// Links the individual to the index of their mother
+	pub const fn with_mother(mut self, mother: usize) -> Self {
+		self.mother = Some(mother);
+		self
+	}
+
+	/// Links the individual to the index of their father
+	pub const fn with_father(mut self, father: usize) -> Self {
+		self.father = Some(father);
+		self
+	}
+
+	/// Records the observed color vision
+	pub const fn with_vision(mut self, vision: ColorVision) -> Self {
+		self.vision = Some(vision);
+		self
+	}
+}
+
+/// An error when building or analyzing a pedigree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PedigreeError {
+	/// The pedigree has more than [MAX_INDIVIDUALS]
+	TooManyIndividuals,
+	/// The individual at the index links to a parent that doesn't come before them
+	Parent(usize),
+	/// The individual at the index links to a parent of the wrong sex
+	Sex(usize),
+	/// The inheritance of the color vision observed in the individual at the index is not modeled
+	Unsupported(usize),
+	/// No combination of alleles explains the observed color vision
+	Inconsistent,
+	/// The pedigree needs more than [MAX_COMBINATIONS] to be enumerated
+	TooManyCombinations,
+}
+
+/// The inferred genetics of an individual
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Inference {
+	/// The probability of carrying a deficient L/M opsin gene array without expressing it
+	pub carrier: f32,
+	/// The probability of expressing a deficiency of the L/M opsin gene array,
+	/// which is a red-green deficiency or blue cone monochromacy
+	pub red_green: f32,
+	/// The probability of expressing a tritan deficiency
+	pub tritan: f32,
+}
+
+/// The inferences for all individuals of a pedigree, in the same order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inferences {
+	inferences: [Inference; MAX_INDIVIDUALS],
+	len: usize,
+}
+
+impl Inferences {
+	/// Gets the inference of the individual at an index
+	pub fn get(&self, index: usize) -> Option<&Inference> {
+		self.as_slice().get(index)
+	}
+
+	/// The inferences as a slice
+	pub fn as_slice(&self) -> &[Inference] {
+		&self.inferences[..self.len]
+	}
+}
+
+impl Index<usize> for Inferences {
+	type Output = Inference;
+
+	fn index(&self, index: usize) -> &Self::Output {
+		&self.as_slice()[index]
+	}
+}
+
+/// The source of one copy of a gene of an individual
+#[derive(Debug, Clone, Copy)]
+enum Source {
+	/// No copy, such as the paternal X chromosome of a male
+	None,
+	/// A founder allele chosen by a variable
+	Founder(usize),
+	/// One of the two copies of a parent, chosen by a variable
+	Parent(usize, usize),
+	/// The only X chromosome of a father
+	Father(usize),
+}
+
+/// The variables of an enumeration, and the copies of each individual they decide
+struct Enumeration {
+	sources: [[Source; 2]; MAX_INDIVIDUALS],
+	radices: [u8; 2 * MAX_INDIVIDUALS],
+	len: usize,
+}
+
+impl Enumeration {
+	fn new(
+		individuals: &[Individual],
+		alleles: u8,
+		x_linked: bool,
+	) -> Result<Self, PedigreeError> {
+		let mut enumeration = Self {
+			sources: [[Source::None; 2]; MAX_INDIVIDUALS],
+			radices: [0; 2 * MAX_INDIVIDUALS],
+			len: 0,
+		};
+		let mut combinations: u64 = 1;
+		for (index, individual) in individuals.iter().enumerate() {
+			let father = match (x_linked, individual.sex, individual.father) {
+				(true, Sex::Male, _) => Source::None,
+				(true, Sex::Female, Some(father)) => Source::Father(father),
+				(_, _, Some(father)) => Source::Parent(enumeration.push(2), father),
+				(_, _, None) => Source::Founder(enumeration.push(alleles)),
+			};
+			let mother = match individual.mother {
+				Some(mother) => Source::Parent(enumeration.push(2), mother),
+				None => Source::Founder(enumeration.push(alleles)),
+			};
+			enumeration.sources[index] = [mother, father];
+		}
+		for &radix in &enumeration.radices[..enumeration.len] {
+			combinations *= u64::from(radix);
+			if combinations > MAX_COMBINATIONS {
+				return Err(PedigreeError::TooManyCombinations);
+			}
+		}
+		Ok(enumeration)
+	}
+
+	fn push(&mut self, radix: u8) -> usize {
+		self.radices[self.len] = radix;
+		self.len += 1;
+		self.len - 1
+	}
+
+	/// Visits every combination of the variables, with the copies of each
+	/// individual as indices into the alleles
+	fn visit(
+		&self,
+		individuals: usize,
+		mut f: impl FnMut(&[u8; 2 * MAX_INDIVIDUALS], &[[Option<u8>; 2]]),
+	) {
+		let mut digits = [0u8; 2 * MAX_INDIVIDUALS];
+		let mut copies = [[None; 2]; MAX_INDIVIDUALS];
+		loop {
+			for index in 0..individuals {
+				for copy in 0..2 {
+					copies[index][copy] = match self.sources[index][copy] {
+						Source::None => None,
+						Source::Founder(variable) => Some(digits[variable]),
+						Source::Parent(variable, parent) => {
+							copies[parent]
+								[usize::from(digits[variable])]
+						}
+						Source::Father(father) => copies[father][0],
+					};
+				}
+			}
+			f(&digits, &copies[..individuals]);
+
+			// advance the digits like an odometer
+			let mut variable = 0;
+			loop {
+				if variable == self.len {
+					return;
+				}
+				digits[variable] += 1;
+				if digits[variable] < self.radices[variable] {
+					break;
+				}
+				digits[variable] = 0;
+				variable += 1;
+			}
+		}
+	}
+
+	/// The prior weight of the founder alleles in a combination
+	fn weight(&self, digits: &[u8; 2 * MAX_INDIVIDUALS], prior: impl Fn(u8) -> f32) -> f32 {
+		let mut weight = 1.0;
+		for sources in &self.sources {
+			for source in sources {
+				if let Source::Founder(variable) = *source {
+					weight *= prior(digits[variable]);
+				}
+			}
+		}
+		weight
+	}
+}
+
+/// The distinct alleles of a gene enumerated for a pedigree, where the first
+/// is normal, followed by the observed deficient alleles, and a deficient
+/// allele standing in for those that weren't observed
+struct Alleles<T> {
+	alleles: [T; 6],
+	/// The number of deficient alleles that each allele stands for
+	counts: [u8; 6],
+	len: u8,
+	/// The number of deficient alleles of the gene
+	deficient: u8,
+}
+
+impl<T: Copy + PartialEq> Alleles<T> {
+	/// Creates the alleles of a gene from all of its alleles, where the first is normal
+	fn new(all: &[T]) -> Self {
+		Self {
+			alleles: [all[0]; 6],
+			counts: [0; 6],
+			len: 1,
+			deficient: all.len() as u8 - 1,
+		}
+	}
+
+	fn contains(&self, allele: &T) -> bool {
+		self.alleles[..usize::from(self.len)].contains(allele)
+	}
+
+	fn insert(&mut self, allele: T, count: u8) {
+		if !self.contains(&allele) {
+			self.alleles[usize::from(self.len)] = allele;
+			self.counts[usize::from(self.len)] = count;
+			self.len += 1;
+		}
+	}
+
+	/// Inserts an unobserved deficient allele of each class, standing in for
+	/// the unobserved alleles of its class, so that founders can carry
+	/// deficient alleles whether or not anyone in the pedigree is observed
+	/// with them. Alleles of the same class impair the same cone cells, so
+	/// they're interchangeable for inferring deficiencies.
+	fn insert_unobserved(&mut self, all: &[T], class: impl Fn(&T) -> u8) {
+		for (index, allele) in all.iter().enumerate().skip(1) {
+			let unobserved =
+				|other: &&T| class(other) == class(allele) && !self.contains(other);
+			if all[1..index]
+				.iter()
+				.any(|other| class(other) == class(allele))
+			{
+				continue;
+			}
+			let count = all[1..].iter().filter(unobserved).count() as u8;
+			if let Some(&allele) = all[1..].iter().find(unobserved) {
+				self.insert(allele, count);
+			}
+		}
+	}
+
+	/// The prior probability of an allele, splitting the frequency of
+	/// deficient alleles evenly between all deficient alleles of the gene
+	fn prior(&self, frequency: f32) -> impl Fn(u8) -> f32 {
+		let (counts, deficient) = (self.counts, f32::from(self.deficient));
+		move |allele| match allele {
+			0 => 1.0 - frequency,
+			_ => frequency * f32::from(counts[usize::from(allele)]) / deficient,
+		}
+	}
+}
+
+/// A pedigree of individuals, where parents come before their children
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pedigree<'a> {
+	individuals: &'a [Individual],
+	lm_frequency: f32,
+	s_frequency: f32,
+}
+
+impl<'a> Pedigree<'a> {
+	/// Creates a new pedigree, checking that each parent comes before their
+	/// children and has the expected sex
+	///
+	/// ```
+	/// use achroma::genetics::Sex;
+	/// use achroma::pedigree::{Individual, Pedigree, PedigreeError};
+	///
+	/// let individuals = [
+	///     Individual::new(Sex::Male),
+	///     Individual::new(Sex::Male).with_parents(0, 0),
+	/// ];
+	/// assert_eq!(Pedigree::new(&individuals), Err(PedigreeError::Sex(1)));
+	/// ```
+	pub fn new(individuals: &'a [Individual]) -> Result<Self, PedigreeError> {
+		if individuals.len() > MAX_INDIVIDUALS {
+			return Err(PedigreeError::TooManyIndividuals);
+		}
+		for (index, individual) in individuals.iter().enumerate() {
+			for (parent, sex) in [
+				(individual.mother, Sex::Female),
+				(individual.father, Sex::Male),
+			] {
+				let Some(parent) = parent else { continue };
+				if parent >= index {
+					return Err(PedigreeError::Parent(index));
+				}
+				if individuals[parent].sex != sex {
+					return Err(PedigreeError::Sex(index));
+				}
+			}
+			if individual.vision == Some(ColorVision::Achromatopsia) {
+				return Err(PedigreeError::Unsupported(index));
+			}
+		}
+		Ok(Self {
+			individuals,
+			lm_frequency: LM_FREQUENCY,
+			s_frequency: S_FREQUENCY,
+		})
+	}
+
+	/// Uses other frequencies of deficient L/M and S opsin alleles among founders,
+	/// such as for a population with a different prevalence
+	pub const fn with_frequencies(mut self, lm: f32, s: f32) -> Self {
+		self.lm_frequency = lm;
+		self.s_frequency = s;
+		self
+	}
+
+	/// The individuals of the pedigree
+	pub const fn individuals(&self) -> &'a [Individual] {
+		self.individuals
+	}
+
+	/// Reports if some combination of alleles explains the observed color vision
+	///
+	/// ```
+	/// use achroma::genetics::Sex;
+	/// use achroma::pedigree::{Individual, Pedigree};
+	/// use achroma::ColorVision;
+	///
+	/// // a protanopic daughter needs a protan allele from her father
+	/// let individuals = [
+	///     Individual::new(Sex::Female).with_vision(ColorVision::Protanopia),
+	///     Individual::new(Sex::Male).with_vision(ColorVision::Normal),
+	///     Individual::new(Sex::Female).with_parents(0, 1).with_vision(ColorVision::Protanopia),
+	/// ];
+	/// assert!(!Pedigree::new(&individuals).unwrap().is_consistent());
+	/// ```
+	pub fn is_consistent(&self) -> bool {
+		self.infer().is_ok()
+	}
+
+	/// Infers the probabilities of carrying and expressing deficiencies for each individual
+	pub fn infer(&self) -> Result<Inferences, PedigreeError> {
+		let mut inferences = Inferences {
+			inferences: [Inference::default(); MAX_INDIVIDUALS],
+			len: self.individuals.len(),
+		};
+		self.infer_lm(&mut inferences.inferences)?;
+		self.infer_s(&mut inferences.inferences)?;
+		Ok(inferences)
+	}
+
+	fn observed(&self) -> impl Iterator<Item = (usize, ConeCellSummary)> + 'a {
+		self.individuals
+			.iter()
+			.enumerate()
+			.filter_map(|(index, individual)| Some((index, individual.vision?.into())))
+	}
+
+	fn infer_lm(&self, inferences: &mut [Inference]) -> Result<(), PedigreeError> {
+		let mut alleles = Alleles::new(&LmAllele::ALL);
+		for (_, summary) in self.observed() {
+			let allele = LmAllele::ALL
+				.into_iter()
+				.find(|allele| allele.cones() == [summary.l, summary.m])
+				.unwrap_or_default();
+			alleles.insert(allele, 1);
+		}
+		alleles.insert_unobserved(&LmAllele::ALL, |allele| {
+			let [l, m] = allele.cones();
+			u8::from(l.is_normal()) | u8::from(m.is_normal()) << 1
+		});
+
+		let enumeration = Enumeration::new(self.individuals, alleles.len, true)?;
+		let prior = alleles.prior(self.lm_frequency);
+		let mut totals = [Inference::default(); MAX_INDIVIDUALS];
+		let mut total = 0.0;
+		enumeration.visit(self.individuals.len(), |digits, copies| {
+			let genotype = |index: usize| {
+				let [maternal, paternal] = copies[index];
+				let maternal = alleles.alleles[usize::from(maternal.unwrap_or(0))];
+				let s = [SAllele::Normal; 2];
+				match paternal {
+					Some(paternal) => Genotype::female(
+						maternal,
+						alleles.alleles[usize::from(paternal)],
+						s,
+					),
+					None => Genotype::male(maternal, s),
+				}
+			};
+			let consistent = self.observed().all(|(index, summary)| {
+				let phenotype = genotype(index).phenotype();
+				[phenotype.l, phenotype.m] == [summary.l, summary.m]
+			});
+			if !consistent {
+				return;
+			}
+			let weight = enumeration.weight(digits, &prior);
+			total += weight;
+			for (index, totals) in totals[..copies.len()].iter_mut().enumerate() {
+				let genotype = genotype(index);
+				if genotype.is_carrier() {
+					totals.carrier += weight;
+				}
+				let phenotype = genotype.phenotype();
+				if !phenotype.l.is_normal() || !phenotype.m.is_normal() {
+					totals.red_green += weight;
+				}
+			}
+		});
+		if total <= 0.0 {
+			return Err(PedigreeError::Inconsistent);
+		}
+		for (inference, totals) in inferences.iter_mut().zip(totals) {
+			inference.carrier = totals.carrier / total;
+			inference.red_green = totals.red_green / total;
+		}
+		Ok(())
+	}
+
+	fn infer_s(&self, inferences: &mut [Inference]) -> Result<(), PedigreeError> {
+		const ALL: [SAllele; 3] =
+			[SAllele::Normal, SAllele::Tritanomalous, SAllele::Tritanopic];
+		let mut alleles = Alleles::new(&ALL);
+		for (_, summary) in self.observed() {
+			alleles.insert(SAllele::from_cone(summary.s), 1);
+		}
+		alleles.insert_unobserved(&ALL, |_| 0);
+
+		let enumeration = Enumeration::new(self.individuals, alleles.len, false)?;
+		let prior = alleles.prior(self.s_frequency);
+		let mut totals = [0.0; MAX_INDIVIDUALS];
+		let mut total = 0.0;
+		enumeration.visit(self.individuals.len(), |digits, copies| {
+			let cone = |index: usize| {
+				let [a, b] = copies[index].map(|copy| {
+					alleles.alleles[usize::from(copy.unwrap_or(0))]
+				});
+				a.cone().impaired(b.cone())
+			};
+			if !self.observed()
+				.all(|(index, summary)| cone(index) == summary.s)
+			{
+				return;
+			}
+			let weight = enumeration.weight(digits, &prior);
+			total += weight;
+			for (index, totals) in totals[..copies.len()].iter_mut().enumerate() {
+				if !cone(index).is_normal() {
+					*totals += weight;
+				}
+			}
+		});
+		if total <= 0.0 {
+			return Err(PedigreeError::Inconsistent);
+		}
+		for (inference, totals) in inferences.iter_mut().zip(totals) {
+			inference.tritan = totals / total;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_structure_errors() {
+		let individuals = [Individual::new(Sex::Female).with_mother(0)];
+		assert_eq!(Pedigree::new(&individuals), Err(PedigreeError::Parent(0)));
+
+		let individuals =
+			[Individual::new(Sex::Male).with_vision(ColorVision::Achromatopsia)];
+		assert_eq!(
+			Pedigree::new(&individuals),
+			Err(PedigreeError::Unsupported(0))
+		);
+
+		let individuals = [Individual::new(Sex::Male); MAX_INDIVIDUALS + 1];
+		assert_eq!(
+			Pedigree::new(&individuals),
+			Err(PedigreeError::TooManyIndividuals)
+		);
+	}
+
+	#[test]
+	fn test_affected_son_of_unknown_mother() {
+		let individuals = [
+			Individual::new(Sex::Female),
+			Individual::new(Sex::Male).with_vision(ColorVision::Normal),
+			Individual::new(Sex::Male)
+				.with_parents(0, 1)
+				.with_vision(ColorVision::Protanomaly),
+			Individual::new(Sex::Female).with_parents(0, 1),
+		];
+		let inferences = Pedigree::new(&individuals).unwrap().infer().unwrap();
+
+		// the mother must have passed on the allele, and is most likely a carrier
+		assert!(inferences[0].carrier + inferences[0].red_green > 0.999);
+		assert!(inferences[0].carrier > 0.9);
+		// the sister has about half a chance of being a carrier
+		assert!((inferences[3].carrier - 0.5).abs() < 0.05);
+		assert_eq!(inferences[1].red_green, 0.0);
+		assert_eq!(inferences[1].carrier, 0.0);
+	}
+
+	#[test]
+	fn test_prior_without_observations() {
+		let individuals = [
+			Individual::new(Sex::Female),
+			Individual::new(Sex::Male),
+			Individual::new(Sex::Male).with_parents(0, 1),
+			// an unrelated stranger
+			Individual::new(Sex::Male).with_vision(ColorVision::Deuteranopia),
+		];
+		let alone = Pedigree::new(&individuals[..3]).unwrap().infer().unwrap();
+		let with_stranger = Pedigree::new(&individuals).unwrap().infer().unwrap();
+
+		assert!((alone[2].red_green - LM_FREQUENCY).abs() < 1e-6);
+		assert!((alone[2].tritan - S_FREQUENCY * (2.0 - S_FREQUENCY)).abs() < 1e-6);
+		for (alone, with_stranger) in alone.as_slice().iter().zip(with_stranger.as_slice())
+		{
+			assert!((alone.red_green - with_stranger.red_green).abs() < 1e-6);
+			assert!((alone.tritan - with_stranger.tritan).abs() < 1e-6);
+			assert!((alone.carrier - with_stranger.carrier).abs() < 1e-3);
+		}
+	}
+
+	#[test]
+	fn test_tritan_dominant() {
+		let individuals = [
+			Individual::new(Sex::Female).with_vision(ColorVision::Tritanopia),
+			Individual::new(Sex::Male).with_vision(ColorVision::Normal),
+			Individual::new(Sex::Male).with_parents(0, 1),
+			Individual::new(Sex::Female)
+				.with_parents(0, 1)
+				.with_vision(ColorVision::Normal),
+		];
+		let inferences = Pedigree::new(&individuals).unwrap().infer().unwrap();
+		assert!((inferences[2].tritan - 0.5).abs() < 0.01);
+		assert_eq!(inferences[3].tritan, 0.0);
+		// the mother may still carry a red-green deficiency
+		assert!(inferences[2].red_green > 0.0);
+		assert!(inferences[2].red_green < LM_FREQUENCY);
+	}
+
+	#[test]
+	fn test_inconsistent_tritan() {
+		// a tritan child of two normal parents can't inherit a dominant allele
+		let individuals = [
+			Individual::new(Sex::Female).with_vision(ColorVision::Normal),
+			Individual::new(Sex::Male).with_vision(ColorVision::Normal),
+			Individual::new(Sex::Male)
+				.with_parents(0, 1)
+				.with_vision(ColorVision::Tritanomaly),
+		];
+		let pedigree = Pedigree::new(&individuals).unwrap();
+		assert_eq!(pedigree.infer(), Err(PedigreeError::Inconsistent));
+	}
+
+	#[test]
+	fn test_too_many_combinations() {
+		let individuals = [Individual::new(Sex::Female)
+			.with_vision(ColorVision::Tritanopia); MAX_INDIVIDUALS];
+		let pedigree = Pedigree::new(&individuals).unwrap();
+		assert_eq!(pedigree.infer(), Err(PedigreeError::TooManyCombinations));
+	}
+}
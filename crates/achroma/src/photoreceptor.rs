@@ -0,0 +1,448 @@
+//! Sets of cone photoreceptors, including a fourth anomalous cone
+//!
+//! As each cone cell expresses the opsin genes of only one X chromosome,
+//! a female carrying an anomalous L/M opsin gene array can have a fourth
+//! class of cone cells, with an anomalous L′ or M′ pigment besides the normal
+//! L, M and S pigments. A [ConeCellSummary] describes only one pigment for each
+//! cone cell, while [Photoreceptors] holds every expressed pigment.
+//!
+//! Discrimination is predicted by the receptor noise limited model of
+//! Vorobyev & Osorio, "Receptor noise as a determinant of colour thresholds",
+//! Proceedings of the Royal Society B 265 (1998), with the same noise in
+//! every class of cone cells and silent classes left out. A difference of
+//! 1.0 is about one just noticeable difference.
+//!
+//! ```
+//! use achroma::genetics::LmAllele;
+//! use achroma::photoreceptor::Photoreceptors;
+//! use achroma::ConeCellSummary;
+//!
+//! let carrier = Photoreceptors::carrier(LmAllele::Deuteranomalous, 0.5);
+//! assert!(carrier.is_tetrachromatic());
+//! assert_eq!(carrier.summary(), ConeCellSummary::NORMAL);
+//!
+//! // yellow, and a mixture of red and green which a trichromat can't tell apart from it
+//! let yellow = [(589.0, 1.0)];
+//! let mixture = |red: f32| [(670.0, red), (545.0, 1.0)];
+//! let trichromat = carrier.trichromatic();
+//! let red = (0..=10000)
+//!     .map(|step| step as f32 / 100.0)
+//!     .min_by(|&a, &b| {
+//!         let a = trichromat.difference(&yellow, &mixture(a));
+//!         let b = trichromat.difference(&yellow, &mixture(b));
+//!         a.total_cmp(&b)
+//!     })
+//!     .unwrap();
+//! assert!(trichromat.difference(&yellow, &mixture(red)) < 0.1);
+//!
+//! // but the fourth cone tells them apart
+//! assert!(carrier.difference(&yellow, &mixture(red)) > 1.0);
+//! ```
+
+use crate::anomaloscope::Pigments;
+use crate::genetics::LmAllele;
+use crate::opsin::{Prediction, MIN_SEPARATION};
+use crate::spectral::{govardovskii_a1, L_PEAK, M_PEAK, S_PEAK};
+use crate::{ConeCell, ConeCellCond, ConeCellSummary};
+
+/// The largest number of classes of cone cells in a set
+pub const MAX_RECEPTORS: usize = 4;
+
+/// The approximate Weber fraction of the noise in each class of cone cells
+pub const WEBER_FRACTION: f32 = 0.02;
+
+/// The response, relative to the strongest response, below which a class of
+/// cone cells is silent and doesn't contribute to discrimination
+pub const SILENT: f32 = 0.01;
+
+/// A class of cone cells, expressing a pigment
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Photoreceptor {
+	/// The type of cone cell
+	pub cone: ConeCell,
+	/// Whether the pigment is anomalous, such as the L′ or M′ pigment of a carrier
+	pub anomalous: bool,
+	/// The peak wavelength in nanometers of the pigment
+	pub peak: f32,
+}
+
+impl Photoreceptor {
+	/// Creates a new class of cone cells
+	pub const fn new(cone: ConeCell, anomalous: bool, peak: f32) -> Self {
+		Self {
+			cone,
+			anomalous,
+			peak,
+		}
+	}
+
+	/// The relative sensitivity of the pigment at a wavelength in nanometers
+	pub fn sensitivity(&self, wavelength: f32) -> f32 {
+		govardovskii_a1(self.peak, wavelength)
+	}
+}
+
+/// A set of classes of cone cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawPhotoreceptors"))]
+pub struct Photoreceptors {
+	receptors: [Photoreceptor; MAX_RECEPTORS],
+	len: usize,
+}
+
+/// The fields of [Photoreceptors] before they're validated
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawPhotoreceptors {
+	receptors: [Photoreceptor; MAX_RECEPTORS],
+	len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawPhotoreceptors> for Photoreceptors {
+	type Error = &'static str;
+
+	fn try_from(raw: RawPhotoreceptors) -> Result<Self, Self::Error> {
+		raw.receptors
+			.get(..raw.len)
+			.and_then(Self::new)
+			.ok_or("expected at most 4 photoreceptors")
+	}
+}
+
+impl Photoreceptors {
+	/// The normal L, M and S cone cells
+	pub const NORMAL: Self = Self {
+		receptors: [
+			Photoreceptor::new(ConeCell::Long, false, L_PEAK),
+			Photoreceptor::new(ConeCell::Medium, false, M_PEAK),
+			Photoreceptor::new(ConeCell::Short, false, S_PEAK),
+			Photoreceptor::new(ConeCell::Short, false, S_PEAK),
+		],
+		len: 3,
+	};
+
+	/// An empty set, without any cone cells
+	pub const EMPTY: Self = Self {
+		receptors: [Photoreceptor::new(ConeCell::Short, false, S_PEAK); MAX_RECEPTORS],
+		len: 0,
+	};
+
+	/// Creates a new set from classes of cone cells, or [None] when there are
+	/// more than [MAX_RECEPTORS]
+	pub fn new(receptors: &[Photoreceptor]) -> Option<Self> {
+		let mut set = Self::EMPTY;
+		for &receptor in receptors {
+			set = set.with(receptor)?;
+		}
+		Some(set)
+	}
+
+	/// Adds a class of cone cells, unless the same type of cone cell already
+	/// has a pigment within [MIN_SEPARATION] of its peak, or [None] when the
+	/// set is full
+	pub fn with(mut self, receptor: Photoreceptor) -> Option<Self> {
+		let duplicate = self.receptors().iter().any(|other| {
+			other.cone == receptor.cone
+				&& libm::fabsf(other.peak - receptor.peak) < MIN_SEPARATION
+		});
+		if duplicate {
+			return Some(self);
+		}
+		*self.receptors.get_mut(self.len)? = receptor;
+		self.len += 1;
+		Some(self)
+	}
+
+	/// The cone cells of a summary, where the pigments of anomalous L and M
+	/// cone cells are shifted by a severity, as in [Pigments::protanomalous]
+	/// and [Pigments::deuteranomalous]. Anomalous S cone cells keep the normal
+	/// pigment, as their spectral shift isn't modeled.
+	///
+	/// ```
+	/// use achroma::photoreceptor::Photoreceptors;
+	/// use achroma::ConeCellSummary;
+	///
+	/// let protanopia = Photoreceptors::from_summary(ConeCellSummary::PROTANOPIA, 1.0);
+	/// assert_eq!(protanopia.dimensions(), 2);
+	/// assert_eq!(protanopia.summary(), ConeCellSummary::PROTANOPIA);
+	/// ```
+	pub fn from_summary(summary: ConeCellSummary, severity: f32) -> Self {
+		let long = Pigments::protanomalous(severity).long.unwrap_or(L_PEAK);
+		let medium = Pigments::deuteranomalous(severity).medium.unwrap_or(M_PEAK);
+		let mut set = Self::EMPTY;
+		for (cone, cond, anomalous) in [
+			(ConeCell::Long, summary.l, long),
+			(ConeCell::Medium, summary.m, medium),
+			(ConeCell::Short, summary.s, S_PEAK),
+		] {
+			let normal = match cone {
+				ConeCell::Long => L_PEAK,
+				ConeCell::Medium => M_PEAK,
+				ConeCell::Short => S_PEAK,
+			};
+			let receptor = match cond {
+				ConeCellCond::Normal => Photoreceptor::new(cone, false, normal),
+				ConeCellCond::Anomalous => {
+					Photoreceptor::new(cone, true, anomalous)
+				}
+				ConeCellCond::Missing => continue,
+			};
+			set.receptors[set.len] = receptor;
+			set.len += 1;
+		}
+		set
+	}
+
+	/// The cone cells of a female carrying an allele on one X chromosome, and
+	/// a normal L/M opsin gene array on the other. An anomalous allele adds an
+	/// L′ or M′ pigment shifted by a severity, while the normal array makes up
+	/// for any missing cone cells.
+	pub fn carrier(allele: LmAllele, severity: f32) -> Self {
+		let [l, m] = allele.cones();
+		let anomalous = Self::from_summary(
+			ConeCellSummary::new(l, m, ConeCellCond::Missing),
+			severity,
+		);
+		let mut set = Self::NORMAL;
+		for &receptor in anomalous.receptors() {
+			set = set.with(receptor).unwrap_or(set);
+		}
+		set
+	}
+
+	/// The cone cells expressed by the opsin gene arrays of both X chromosomes,
+	/// along with normal S cone cells
+	///
+	/// ```
+	/// use achroma::opsin::{Gene, OpsinArray};
+	/// use achroma::photoreceptor::Photoreceptors;
+	///
+	/// let normal = [Gene::LONG, Gene::MEDIUM];
+	/// let deutan = [Gene::LONG, Gene::LONG.with_site(180, achroma::opsin::Opsin::Medium).unwrap()];
+	/// let maternal = OpsinArray::new(&normal).predict();
+	/// let paternal = OpsinArray::new(&deutan).predict();
+	/// let set = Photoreceptors::from_predictions(&maternal, &paternal);
+	/// assert!(set.is_tetrachromatic());
+	/// ```
+	pub fn from_predictions(maternal: &Prediction, paternal: &Prediction) -> Self {
+		let mut set = Self::EMPTY;
+		for prediction in [maternal, paternal] {
+			for (cone, peak, cond) in [
+				(ConeCell::Long, prediction.long, prediction.summary.l),
+				(ConeCell::Medium, prediction.medium, prediction.summary.m),
+			] {
+				if let Some(peak) = peak {
+					let receptor =
+						Photoreceptor::new(cone, cond.is_anomalous(), peak);
+					set = set.with(receptor).unwrap_or(set);
+				}
+			}
+		}
+		set.with(Photoreceptor::new(ConeCell::Short, false, S_PEAK))
+			.unwrap_or(set)
+	}
+
+	/// The classes of cone cells in the set
+	pub fn receptors(&self) -> &[Photoreceptor] {
+		&self.receptors[..self.len]
+	}
+
+	/// The number of classes of cone cells with distinct pigments
+	pub fn dimensions(&self) -> usize {
+		let receptors = self.receptors();
+		receptors
+			.iter()
+			.enumerate()
+			.filter(|&(index, receptor)| {
+				receptors[..index].iter().all(|other| {
+					libm::fabsf(other.peak - receptor.peak) >= MIN_SEPARATION
+				})
+			})
+			.count()
+	}
+
+	/// Reports if there are four classes of cone cells with distinct pigments
+	pub fn is_tetrachromatic(&self) -> bool {
+		self.dimensions() == 4
+	}
+
+	/// Reports if there are three classes of cone cells with distinct pigments
+	pub fn is_trichromatic(&self) -> bool {
+		self.dimensions() == 3
+	}
+
+	/// Reports if a type of cone cell expresses both a normal and an anomalous pigment
+	pub fn has_extra(&self, cone: ConeCell) -> bool {
+		let has = |anomalous: bool| {
+			self.receptors().iter().any(|receptor| {
+				receptor.cone == cone && receptor.anomalous == anomalous
+			})
+		};
+		has(false) && has(true)
+	}
+
+	/// The summary of the cone cells, where a type of cone cell is normal if
+	/// any of its pigments is normal
+	pub fn summary(&self) -> ConeCellSummary {
+		let mut summary = ConeCellSummary::new(
+			ConeCellCond::Missing,
+			ConeCellCond::Missing,
+			ConeCellCond::Missing,
+		);
+		for receptor in self.receptors() {
+			let cond = if receptor.anomalous {
+				ConeCellCond::Anomalous
+			} else {
+				ConeCellCond::Normal
+			};
+			summary[receptor.cone] = summary[receptor.cone].healthier(cond);
+		}
+		summary
+	}
+
+	/// The set without the extra anomalous pigments, which is the trichromacy
+	/// described by [summary][Self::summary]
+	pub fn trichromatic(&self) -> Self {
+		let mut set = Self::EMPTY;
+		for &receptor in self.receptors() {
+			if !(receptor.anomalous && self.has_extra(receptor.cone)) {
+				set.receptors[set.len] = receptor;
+				set.len += 1;
+			}
+		}
+		set
+	}
+
+	/// The difference between two lights, each given as pairs of a wavelength in
+	/// nanometers and its radiance, in units of just noticeable differences
+	///
+	/// Only differences in color are counted, not differences in brightness.
+	/// Classes of cone cells responding to neither light more than [SILENT]
+	/// are left out, such as S cone cells for long wavelengths.
+	///
+	/// ```
+	/// use achroma::photoreceptor::Photoreceptors;
+	///
+	/// let normal = Photoreceptors::NORMAL;
+	/// let red = [(650.0, 1.0)];
+	/// let green = [(530.0, 1.0)];
+	/// assert!(normal.difference(&red, &green) > 10.0);
+	///
+	/// // a brighter light of the same color isn't counted
+	/// assert!(normal.difference(&red, &[(650.0, 2.0)]) < 1e-3);
+	/// ```
+	pub fn difference(&self, a: &[(f32, f32)], b: &[(f32, f32)]) -> f32 {
+		let receptors = self.receptors();
+		let catch = |receptor: &Photoreceptor, light: &[(f32, f32)]| {
+			light.iter()
+				.map(|&(wavelength, radiance)| {
+					radiance * receptor.sensitivity(wavelength)
+				})
+				.sum::<f32>()
+		};
+		let mut responses = [(0.0, 0.0); MAX_RECEPTORS];
+		for (response, receptor) in responses.iter_mut().zip(receptors) {
+			*response = (catch(receptor, a), catch(receptor, b));
+		}
+		let responses = &responses[..receptors.len()];
+		let strongest = responses
+			.iter()
+			.fold(0.0f32, |max, &(a, b)| max.max(a).max(b));
+
+		// cone cells barely responding to either light are below their noise floor
+		let mut contrasts = [0.0; MAX_RECEPTORS];
+		let mut len = 0;
+		for &(a, b) in responses {
+			if a.max(b) > SILENT * strongest && a > 0.0 && b > 0.0 {
+				contrasts[len] = libm::logf(a / b);
+				len += 1;
+			}
+		}
+		let contrasts = &contrasts[..len];
+		if contrasts.is_empty() {
+			return 0.0;
+		}
+
+		// remove the achromatic component shared by all cone cells
+		let mean = contrasts.iter().sum::<f32>() / contrasts.len() as f32;
+		let squares: f32 = contrasts.iter().map(|c| (c - mean) * (c - mean)).sum();
+		libm::sqrtf(squares) / WEBER_FRACTION
+	}
+}
+
+impl From<ConeCellSummary> for Photoreceptors {
+	/// The cone cells of a summary, at the full severity
+	fn from(summary: ConeCellSummary) -> Self {
+		Self::from_summary(summary, 1.0)
+	}
+}
+
+impl Default for Photoreceptors {
+	fn default() -> Self {
+		Self::NORMAL
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_carriers() {
+		let protan = Photoreceptors::carrier(LmAllele::Protanomalous, 0.5);
+		assert!(protan.has_extra(ConeCell::Long));
+		assert!(!protan.has_extra(ConeCell::Medium));
+		assert_eq!(protan.trichromatic(), Photoreceptors::NORMAL);
+
+		// a dichromatic allele adds no pigment
+		let protanopic = Photoreceptors::carrier(LmAllele::Protanopic, 1.0);
+		assert_eq!(protanopic, Photoreceptors::NORMAL);
+		assert!(protanopic.is_trichromatic());
+
+		// a mild anomaly hardly differs from the normal pigment
+		let mild = Photoreceptors::carrier(LmAllele::Deuteranomalous, 0.01);
+		assert!(!mild.is_tetrachromatic());
+	}
+
+	#[test]
+	fn test_full() {
+		let mut set = Photoreceptors::carrier(LmAllele::Protanomalous, 0.5);
+		assert_eq!(set.receptors().len(), MAX_RECEPTORS);
+		set = set
+			.with(Photoreceptor::new(ConeCell::Long, false, L_PEAK))
+			.unwrap();
+		assert_eq!(set.receptors().len(), MAX_RECEPTORS);
+		assert_eq!(
+			set.with(Photoreceptor::new(ConeCell::Medium, true, 550.0)),
+			None
+		);
+	}
+
+	#[test]
+	fn test_monochromat_difference() {
+		let monochromat = Photoreceptors::from(ConeCellSummary::ACHROMATOMALY);
+		assert_eq!(monochromat.dimensions(), 1);
+		assert_eq!(
+			monochromat.difference(&[(650.0, 1.0)], &[(450.0, 1.0)]),
+			0.0
+		);
+		assert_eq!(
+			Photoreceptors::EMPTY.difference(&[(650.0, 1.0)], &[(450.0, 1.0)]),
+			0.0
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_deserialize_photoreceptors() {
+		let json = serde_json::to_string(&Photoreceptors::NORMAL).unwrap();
+		let set: Photoreceptors = serde_json::from_str(&json).unwrap();
+		assert_eq!(set, Photoreceptors::NORMAL);
+
+		let json = json.replace(r#""len":3"#, r#""len":5"#);
+		assert!(serde_json::from_str::<Photoreceptors>(&json).is_err());
+	}
+}
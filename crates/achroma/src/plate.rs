@@ -0,0 +1,610 @@
+//! Generation of pseudoisochromatic plates, in the style of the Ishihara test
+//!
+//! A [`Plate`] is a disc of randomly sized dots, where the dots inside a
+//! number (the figure) and outside of it (the background) differ only along
+//! a confusion line of a [`ColorVision`]. Viewers with that color vision
+//! can't tell the figure from the background, and the lightness of every dot
+//! is randomized so the number can't be read from luminance alone.
+//!
+//! Confusion lines are taken from the null space of the dichromat projection
+//! by [Viénot, Brettel & Mollon (1999)][vienot-1999], so colors along them
+//! simulate identically with [`Algorithm::Vienot1999`]. Plates are fully
+//! determined by their seed, and render to either an RGB buffer or SVG.
+//!
+//! ```
+//! use achroma::ColorVision;
+//! use achroma::plate::Plate;
+//!
+//! let plate = Plate::new(ColorVision::Deuteranopia, 74, 42).unwrap();
+//! let answers = plate.answers();
+//! assert_eq!(answers.normal, Some(74));
+//! assert_eq!(answers.deficient, None);
+//!
+//! let svg = plate.svg(400).to_string();
+//! assert!(svg.starts_with("<svg"));
+//! ```
+//!
+//! [vienot-1999]: <https://doi.org/10.1002/(SICI)1520-6378(199908)24:4%3C243::AID-COL5%3E3.0.CO;2-3>
+
+use crate::simulation::{encode_srgb8, Algorithm, Matrix3, Simulation};
+use crate::ColorVision;
+use core::fmt;
+
+/// The base color of a plate in linear RGB, which the figure and background
+/// colors are spread around
+const BASE: [f32; 3] = [0.32, 0.26, 0.12];
+
+/// The radius of the disc of dots, as a fraction of the plate's size
+const DISC_RADIUS: f32 = 0.48;
+
+/// How much the lightness of each dot varies, as a fraction of its color
+const LIGHTNESS_JITTER: f32 = 0.2;
+
+/// How much darker the figure of a demonstration plate is than its background
+const DEMONSTRATION_DARKENING: f32 = 0.4;
+
+/// The width and height of a digit, and the gap between two digits,
+/// as fractions of the plate's size
+const DIGIT_WIDTH: f32 = 0.2;
+const DIGIT_HEIGHT: f32 = 0.36;
+const DIGIT_GAP: f32 = 0.08;
+
+/// The width of the strokes of a digit, as a fraction of the plate's size
+const STROKE_WIDTH: f32 = 0.07;
+
+/// Endpoints of the seven segments of a digit (a to g), in units of the
+/// digit's width and height
+const SEGMENTS: [[(f32, f32); 2]; 7] = [
+	[(0.0, 0.0), (1.0, 0.0)],
+	[(1.0, 0.0), (1.0, 0.5)],
+	[(1.0, 0.5), (1.0, 1.0)],
+	[(0.0, 1.0), (1.0, 1.0)],
+	[(0.0, 0.5), (0.0, 1.0)],
+	[(0.0, 0.0), (0.0, 0.5)],
+	[(0.0, 0.5), (1.0, 0.5)],
+];
+
+/// The segments lit for each digit, with bit 0 as segment a
+const DIGITS: [u8; 10] = [
+	0b0111111, 0b0000110, 0b1011011, 0b1001111, 0b1100110, 0b1101101, 0b1111101, 0b0000111,
+	0b1111111, 0b1101111,
+];
+
+/// The design of a plate, which determines who can read its number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum Design {
+	/// The figure differs from the background only along a confusion line,
+	/// so the number vanishes for viewers with the target color vision
+	#[default]
+	Vanishing,
+	/// The figure is also darker than the background, so everyone can read
+	/// the number. This is used to introduce the test and detect malingering.
+	Demonstration,
+}
+
+/// The number that a viewer is expected to read on a plate, where `None`
+/// means that no number is seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Answers {
+	/// The expected answer with normal color vision
+	pub normal: Option<u8>,
+	/// The expected answer with the color vision the plate targets, at full
+	/// severity. Anomalous trichromats may still see a faint number.
+	pub deficient: Option<u8>,
+}
+
+/// A single dot of a plate, in coordinates from `0.0` to `1.0` across the plate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dot {
+	pub x: f32,
+	pub y: f32,
+	pub radius: f32,
+	/// The 8-bit sRGB color of the dot
+	pub color: [u8; 3],
+	/// Whether the dot belongs to the figure rather than the background
+	pub figure: bool,
+}
+
+/// A pseudoisochromatic plate showing a number from 0 to 99
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Plate {
+	vision: ColorVision,
+	number: u8,
+	seed: u64,
+	design: Design,
+	density: u16,
+}
+
+impl Plate {
+	/// Creates a plate targeting a type of color vision, showing a number
+	/// from 0 to 99, with dots placed randomly from a seed.
+	///
+	/// Returns `None` for normal color vision, which has no confusion lines,
+	/// or for numbers larger than 99.
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::plate::Plate;
+	///
+	/// assert!(Plate::new(ColorVision::Protanopia, 12, 0).is_some());
+	/// assert!(Plate::new(ColorVision::Protanopia, 100, 0).is_none());
+	/// assert!(Plate::new(ColorVision::Normal, 12, 0).is_none());
+	/// ```
+	pub const fn new(vision: ColorVision, number: u8, seed: u64) -> Option<Self> {
+		if number > 99 || matches!(vision, ColorVision::Normal) {
+			return None;
+		}
+		Some(Self {
+			vision,
+			number,
+			seed,
+			design: Design::Vanishing,
+			density: 48,
+		})
+	}
+
+	/// Uses a different design for the plate
+	pub const fn with_design(mut self, design: Design) -> Self {
+		self.design = design;
+		self
+	}
+
+	/// The largest number of dots across a plate, beyond which the dots are
+	/// smaller than a pixel at any practical rendering size
+	pub const MAX_DENSITY: u16 = 1024;
+
+	/// Uses a different number of dots across the plate, which is at least 8
+	/// and at most [`Plate::MAX_DENSITY`]
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::plate::Plate;
+	///
+	/// let plate = Plate::new(ColorVision::Protanopia, 12, 0).unwrap();
+	/// assert_eq!(plate.with_density(2).density(), 8);
+	/// assert_eq!(plate.with_density(u16::MAX).density(), Plate::MAX_DENSITY);
+	/// ```
+	pub const fn with_density(mut self, density: u16) -> Self {
+		self.density = if density < 8 {
+			8
+		} else if density > Self::MAX_DENSITY {
+			Self::MAX_DENSITY
+		} else {
+			density
+		};
+		self
+	}
+
+	/// The type of color vision the plate targets
+	pub const fn vision(&self) -> ColorVision {
+		self.vision
+	}
+
+	/// The number shown on the plate
+	pub const fn number(&self) -> u8 {
+		self.number
+	}
+
+	/// The seed that the dots are placed from
+	pub const fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// The design of the plate
+	pub const fn design(&self) -> Design {
+		self.design
+	}
+
+	/// The number of dots across the plate
+	pub const fn density(&self) -> u16 {
+		self.density
+	}
+
+	/// The numbers that viewers are expected to read
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::plate::{Design, Plate};
+	///
+	/// let plate = Plate::new(ColorVision::Tritanopia, 5, 0)
+	///     .unwrap()
+	///     .with_design(Design::Demonstration);
+	/// assert_eq!(plate.answers().normal, Some(5));
+	/// assert_eq!(plate.answers().deficient, Some(5));
+	/// ```
+	pub const fn answers(&self) -> Answers {
+		Answers {
+			normal: Some(self.number),
+			deficient: match self.design {
+				Design::Vanishing => None,
+				Design::Demonstration => Some(self.number),
+			},
+		}
+	}
+
+	/// The average figure and background colors in linear RGB, before
+	/// the lightness of each dot is randomized
+	pub fn colors(&self) -> ([f32; 3], [f32; 3]) {
+		let direction = confusion_direction(self.vision);
+		let margin = 1.0 + LIGHTNESS_JITTER;
+
+		// spread the colors as far apart as the gamut allows
+		let mut spread = f32::INFINITY;
+		for (base, d) in BASE.iter().zip(direction) {
+			let d = libm::fabsf(d);
+			if d > 1e-6 {
+				spread = spread.min(base / d).min((1.0 / margin - base) / d);
+			}
+		}
+		spread *= 0.95;
+
+		let figure = [0, 1, 2].map(|i| BASE[i] + spread * direction[i]);
+		let background = [0, 1, 2].map(|i| BASE[i] - spread * direction[i]);
+		match self.design {
+			Design::Vanishing => (figure, background),
+			Design::Demonstration => {
+				(figure.map(|c| c * DEMONSTRATION_DARKENING), background)
+			}
+		}
+	}
+
+	/// The dots of the plate, from top to bottom
+	pub fn dots(&self) -> Dots {
+		let cell = 1.0 / self.density as f32;
+		let rows = libm::ceilf(1.0 / (cell * ROW_SPACING)) as u32;
+		Dots {
+			plate: *self,
+			colors: self.colors(),
+			cell,
+			columns: self.density as u32,
+			rows,
+			index: 0,
+		}
+	}
+
+	/// Renders the plate into a square RGB buffer with a white background,
+	/// with 3 bytes per pixel and rows from top to bottom
+	///
+	/// # Panics
+	/// Panics if the buffer is smaller than `size * size * 3` bytes.
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::plate::Plate;
+	///
+	/// let plate = Plate::new(ColorVision::Protanopia, 8, 1).unwrap();
+	/// let mut buffer = vec![0; 64 * 64 * 3];
+	/// plate.render_rgb8(&mut buffer, 64);
+	/// assert_eq!(&buffer[..3], &[255, 255, 255]);
+	/// ```
+	pub fn render_rgb8(&self, buffer: &mut [u8], size: usize) {
+		let buffer = &mut buffer[..size * size * 3];
+		buffer.fill(255);
+
+		let scale = size as f32;
+		for dot in self.dots() {
+			let (cx, cy, r) = (dot.x * scale, dot.y * scale, dot.radius * scale);
+			let min_x = libm::floorf(cx - r).max(0.0) as usize;
+			let min_y = libm::floorf(cy - r).max(0.0) as usize;
+			let max_x = (libm::ceilf(cx + r) as usize).min(size);
+			let max_y = (libm::ceilf(cy + r) as usize).min(size);
+			for y in min_y..max_y {
+				for x in min_x..max_x {
+					let dx = x as f32 + 0.5 - cx;
+					let dy = y as f32 + 0.5 - cy;
+					if dx * dx + dy * dy <= r * r {
+						let i = (y * size + x) * 3;
+						buffer[i..i + 3].copy_from_slice(&dot.color);
+					}
+				}
+			}
+		}
+	}
+
+	/// SVG markup of the plate with a given width and height, which is
+	/// generated when formatted
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::plate::Plate;
+	///
+	/// let plate = Plate::new(ColorVision::Protanopia, 3, 0).unwrap();
+	/// let svg = plate.svg(100).to_string();
+	/// assert_eq!(svg.matches("<circle").count(), plate.dots().count());
+	/// assert!(svg.ends_with("</svg>\n"));
+	/// ```
+	pub const fn svg(&self, size: u32) -> Svg {
+		Svg { plate: *self, size }
+	}
+}
+
+/// The vertical spacing between rows of dots, relative to the horizontal
+/// spacing, so that the dots are hexagonally packed
+const ROW_SPACING: f32 = 0.866;
+
+/// An iterator over the dots of a plate, created by [`Plate::dots()`]
+#[derive(Debug, Clone)]
+pub struct Dots {
+	plate: Plate,
+	colors: ([f32; 3], [f32; 3]),
+	cell: f32,
+	columns: u32,
+	rows: u32,
+	index: u32,
+}
+
+impl Iterator for Dots {
+	type Item = Dot;
+
+	fn next(&mut self) -> Option<Dot> {
+		while self.index < self.columns * self.rows {
+			let index = self.index;
+			self.index += 1;
+
+			let row = index / self.columns;
+			let column = index % self.columns;
+			let offset = if row % 2 == 0 { 0.5 } else { 1.0 };
+			let cell_x = (column as f32 + offset) * self.cell;
+			let cell_y = (row as f32 + 0.5) * self.cell * ROW_SPACING;
+
+			// each dot stays within half a cell of its center, so that
+			// neighboring dots never overlap
+			let mut rng = Rng::new(self.plate.seed, index);
+			let radius = self.cell * (0.25 + 0.2 * rng.next_f32());
+			let jitter = (0.5 * self.cell - radius) * rng.next_f32();
+			let angle = core::f32::consts::TAU * rng.next_f32();
+			let x = cell_x + jitter * libm::cosf(angle);
+			let y = cell_y + jitter * libm::sinf(angle);
+
+			let distance = libm::hypotf(x - 0.5, y - 0.5);
+			if distance + radius > DISC_RADIUS {
+				continue;
+			}
+
+			let figure = in_number(self.plate.number, x, y);
+			let color = if figure { self.colors.0 } else { self.colors.1 };
+			let lightness = 1.0 + LIGHTNESS_JITTER * (2.0 * rng.next_f32() - 1.0);
+			return Some(Dot {
+				x,
+				y,
+				radius,
+				color: encode_srgb8(color.map(|c| c * lightness)),
+				figure,
+			});
+		}
+		None
+	}
+}
+
+/// SVG markup of a plate, created by [`Plate::svg()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Svg {
+	plate: Plate,
+	size: u32,
+}
+
+impl fmt::Display for Svg {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(
+			f,
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+			self.size
+		)?;
+		writeln!(f, r##"<rect width="100%" height="100%" fill="#ffffff"/>"##)?;
+		let scale = self.size as f32;
+		for dot in self.plate.dots() {
+			let [r, g, b] = dot.color;
+			writeln!(
+				f,
+				r##"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#{:02x}{:02x}{:02x}"/>"##,
+				dot.x * scale,
+				dot.y * scale,
+				dot.radius * scale,
+				r,
+				g,
+				b
+			)?;
+		}
+		writeln!(f, "</svg>")
+	}
+}
+
+/// A direction in linear RGB along which colors are confused with a type of
+/// color vision, scaled so that its largest component is 1
+fn confusion_direction(vision: ColorVision) -> [f32; 3] {
+	let m: Matrix3 = Simulation::from(vision)
+		.with_algorithm(Algorithm::Vienot1999)
+		.matrix();
+
+	// the null space of a rank 2 projection is orthogonal to its rows
+	let mut direction = [0.0; 3];
+	let mut norm = 0.0;
+	for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+		let candidate = cross(m[a], m[b]);
+		let candidate_norm = dot(candidate, candidate);
+		if candidate_norm > norm {
+			direction = candidate;
+			norm = candidate_norm;
+		}
+	}
+
+	// a rank 1 projection (achromatopsia) confuses all colors of the same
+	// luminance, so pick the one that is most like red and green
+	if norm < 1e-6 {
+		direction = cross(m[0], [0.0, 0.0, 1.0]);
+	}
+
+	let largest = direction
+		.iter()
+		.fold(0.0f32, |largest, c| largest.max(libm::fabsf(*c)));
+	let sign = if direction[0] < 0.0 { -1.0 } else { 1.0 };
+	direction.map(|c| sign * c / largest)
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Whether a point lies on the strokes of a number, centered on the plate
+fn in_number(number: u8, x: f32, y: f32) -> bool {
+	let (digits, count) = if number < 10 {
+		([number, 0], 1)
+	} else {
+		([number / 10, number % 10], 2)
+	};
+	let width = count as f32 * DIGIT_WIDTH + (count - 1) as f32 * DIGIT_GAP;
+	let left = 0.5 - width / 2.0;
+	let top = 0.5 - DIGIT_HEIGHT / 2.0;
+
+	digits[..count].iter().enumerate().any(|(i, &digit)| {
+		let origin_x = left + i as f32 * (DIGIT_WIDTH + DIGIT_GAP);
+		SEGMENTS.iter().enumerate().any(|(segment, &[start, end])| {
+			DIGITS[digit as usize] & (1 << segment) != 0
+				&& distance_to_segment(
+					(x, y),
+					(
+						origin_x + start.0 * DIGIT_WIDTH,
+						top + start.1 * DIGIT_HEIGHT,
+					),
+					(
+						origin_x + end.0 * DIGIT_WIDTH,
+						top + end.1 * DIGIT_HEIGHT,
+					),
+				) <= STROKE_WIDTH / 2.0
+		})
+	})
+}
+
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+	let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+	let length_squared = dx * dx + dy * dy;
+	let t = if length_squared > 0.0 {
+		(((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_squared).clamp(0.0, 1.0)
+	} else {
+		0.0
+	};
+	libm::hypotf(p.0 - (a.0 + t * dx), p.1 - (a.1 + t * dy))
+}
+
+/// A small deterministic random number generator (SplitMix64), seeded
+/// separately for each dot so that dots can be generated in any order
+struct Rng(u64);
+
+impl Rng {
+	fn new(seed: u64, index: u32) -> Self {
+		Self(seed ^ u64::from(index).wrapping_mul(0xd1b5_4a32_d192_ed03))
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+
+	/// A random number in `0.0..1.0`
+	fn next_f32(&mut self) -> f32 {
+		(self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::difference::Lab;
+	use crate::simulation::mul;
+
+	const VISIONS: [ColorVision; 8] = [
+		ColorVision::Protanomaly,
+		ColorVision::Protanopia,
+		ColorVision::Deuteranomaly,
+		ColorVision::Deuteranopia,
+		ColorVision::Tritanomaly,
+		ColorVision::Tritanopia,
+		ColorVision::Achromatomaly,
+		ColorVision::Achromatopsia,
+	];
+
+	#[test]
+	fn test_colors_lie_on_confusion_line() {
+		for vision in VISIONS {
+			let plate = Plate::new(vision, 12, 0).unwrap();
+			let (figure, background) = plate.colors();
+			for c in figure.into_iter().chain(background) {
+				assert!((0.0..=1.0 / (1.0 + LIGHTNESS_JITTER)).contains(&c));
+			}
+
+			let m = Simulation::from(vision)
+				.with_algorithm(Algorithm::Vienot1999)
+				.matrix();
+			let (a, b) = (mul(&m, figure), mul(&m, background));
+			for i in 0..3 {
+				assert!(
+					libm::fabsf(a[i] - b[i]) < 1e-4,
+					"{:?}: {:?} {:?}",
+					vision,
+					a,
+					b
+				);
+			}
+
+			// still clearly different with normal color vision
+			let delta_e = Lab::from_linear_srgb(figure)
+				.delta_e_2000(&Lab::from_linear_srgb(background));
+			assert!(delta_e > 15.0, "{:?}: ΔE {}", vision, delta_e);
+		}
+	}
+
+	#[test]
+	fn test_dots_do_not_overlap() {
+		let plate = Plate::new(ColorVision::Deuteranopia, 8, 7)
+			.unwrap()
+			.with_density(20);
+		for (i, a) in plate.dots().enumerate() {
+			assert!(libm::hypotf(a.x - 0.5, a.y - 0.5) + a.radius <= DISC_RADIUS);
+			for b in plate.dots().skip(i + 1) {
+				let distance = libm::hypotf(a.x - b.x, a.y - b.y);
+				assert!(distance >= a.radius + b.radius - 1e-6);
+			}
+		}
+		assert!(plate.dots().any(|dot| dot.figure));
+		assert!(plate.dots().any(|dot| !dot.figure));
+	}
+
+	#[test]
+	fn test_max_density() {
+		let plate = Plate::new(ColorVision::Protanopia, 8, 0)
+			.unwrap()
+			.with_density(u16::MAX);
+		assert_eq!(plate.density(), Plate::MAX_DENSITY);
+		assert!(plate.dots().count() > 1 << 19);
+	}
+
+	#[test]
+	fn test_dots_are_deterministic() {
+		let plate = Plate::new(ColorVision::Protanopia, 42, 1234).unwrap();
+		assert!(plate.dots().eq(plate.dots()));
+
+		let other = Plate::new(ColorVision::Protanopia, 42, 1235).unwrap();
+		assert!(!plate.dots().eq(other.dots()));
+	}
+
+	#[test]
+	fn test_in_number() {
+		// the middle of a 1 is on its right stroke
+		assert!(!in_number(1, 0.5, 0.5));
+		assert!(in_number(1, 0.5 + DIGIT_WIDTH / 2.0, 0.5));
+		// an 8 has a middle bar, but a 0 does not
+		assert!(in_number(8, 0.5, 0.5));
+		assert!(!in_number(0, 0.5, 0.5));
+	}
+}
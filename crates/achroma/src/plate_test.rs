@@ -0,0 +1,579 @@
+//! Scoring of plate tests by their answer keys
+//!
+//! Plate tests, such as the Ishihara, HRR or City University tests, show a
+//! series of plates where the expected reading differs between normal and
+//! deficient color vision. A [Key] lists the expected readings of each plate,
+//! and scores the readings of a subject into a screening [Verdict], a suspected
+//! type of color vision, and a severity [Grade] for tests that grade it.
+//!
+//! Only the keys of the Ishihara 38 and 24 plate editions are bundled.
+//! The keys of the HRR 4th edition and the City University test are not yet,
+//! as their plate figures and grading tables haven't been transcribed from
+//! the test manuals. Keys are plain data, so keys of these and other tests
+//! can be built with [Key::new] from the tables in their manuals, with
+//! [Item::with_grade] for graded diagnostic plates.
+//!
+//! ```
+//! use achroma::plate_test::{Key, Verdict};
+//! use achroma::ColorVision;
+//!
+//! // a subject reading every plate of the Ishihara 24 plate edition as a protan
+//! let answers: Vec<_> = Key::ISHIHARA_24.items.iter().map(|item| item.protan).collect();
+//! let score = Key::ISHIHARA_24.score(&answers).unwrap();
+//! assert_eq!(score.verdict, Verdict::Fail);
+//! assert_eq!(score.suspected, Some(ColorVision::Protanomaly));
+//! ```
+
+use crate::ColorVision;
+
+/// The purpose of a plate within a test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+	/// A plate read by everyone, which checks that the subject understands the test
+	Demonstration,
+	/// A plate that separates normal and deficient color vision
+	Screening,
+	/// A plate that separates types or grades of deficient color vision
+	Diagnostic,
+}
+
+/// The severity grade of a deficiency, as graded by the HRR test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Grade {
+	/// Mild deficiency
+	Mild,
+	/// Medium deficiency
+	Medium,
+	/// Strong deficiency
+	Strong,
+}
+
+/// The expected readings of a plate, or of one figure of a plate showing several
+///
+/// A reading of [None] means nothing is seen on the plate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Item<'a> {
+	/// The number of the plate in the test
+	pub plate: u8,
+	/// The purpose of the plate
+	pub kind: Kind,
+	/// The reading of normal color vision
+	pub normal: Option<&'a str>,
+	/// The reading of protan color vision
+	pub protan: Option<&'a str>,
+	/// The reading of deutan color vision
+	pub deutan: Option<&'a str>,
+	/// The reading of tritan color vision, if the plate tests it
+	pub tritan: Option<&'a str>,
+	/// The grade of deficiency detected when the plate is misread
+	pub grade: Option<Grade>,
+}
+
+impl<'a> Item<'a> {
+	/// Creates a new plate read the same by everyone
+	pub const fn demonstration(plate: u8, reading: &'a str) -> Self {
+		Self::new(plate, Kind::Demonstration, Some(reading), Some(reading))
+	}
+
+	/// Creates a new plate with a normal reading and a reading of both
+	/// protan and deutan color vision
+	pub const fn new(
+		plate: u8,
+		kind: Kind,
+		normal: Option<&'a str>,
+		deficient: Option<&'a str>,
+	) -> Self {
+		Self {
+			plate,
+			kind,
+			normal,
+			protan: deficient,
+			deutan: deficient,
+			tritan: normal,
+			grade: None,
+		}
+	}
+
+	/// Creates a new diagnostic plate with different protan and deutan readings
+	pub const fn diagnostic(
+		plate: u8,
+		normal: &'a str,
+		protan: Option<&'a str>,
+		deutan: Option<&'a str>,
+	) -> Self {
+		Self {
+			protan,
+			deutan,
+			..Self::new(plate, Kind::Diagnostic, Some(normal), None)
+		}
+	}
+
+	/// Sets the tritan reading of the plate
+	pub const fn with_tritan(self, tritan: Option<&'a str>) -> Self {
+		Self { tritan, ..self }
+	}
+
+	/// Sets the grade detected when the plate is misread
+	pub const fn with_grade(self, grade: Grade) -> Self {
+		Self {
+			grade: Some(grade),
+			..self
+		}
+	}
+}
+
+/// The screening verdict of a plate test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Verdict {
+	/// Few enough screening plates are misread for normal color vision
+	Pass,
+	/// More screening plates are misread than normal, but fewer than deficient
+	Inconclusive,
+	/// Enough screening plates are misread for deficient color vision
+	Fail,
+	/// A demonstration plate is misread, so the test isn't valid
+	Invalid,
+}
+
+/// An error when the answers don't fit a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnswerError {
+	/// The number of answers differs from the number of items in the key
+	Length,
+}
+
+/// The answer key of a plate test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key<'a> {
+	/// The name of the test and its edition
+	pub name: &'a str,
+	/// The expected readings in the order the plates are shown
+	pub items: &'a [Item<'a>],
+	/// The most misread screening plates of normal color vision
+	pub normal_errors: u8,
+	/// The fewest misread screening plates of deficient color vision
+	pub deficient_errors: u8,
+}
+
+impl<'a> Key<'a> {
+	/// The Ishihara test, 38 plate edition, scored by plates 1 to 25
+	pub const ISHIHARA_38: Key<'static> =
+		Key::new("Ishihara 38 plate", &ISHIHARA_38_ITEMS, 4, 8);
+
+	/// The Ishihara test, 24 plate edition, scored by plates 1 to 17
+	pub const ISHIHARA_24: Key<'static> =
+		Key::new("Ishihara 24 plate", &ISHIHARA_24_ITEMS, 2, 6);
+
+	/// Creates a new answer key with the limits of misread screening plates
+	pub const fn new(
+		name: &'a str,
+		items: &'a [Item<'a>],
+		normal_errors: u8,
+		deficient_errors: u8,
+	) -> Self {
+		Self {
+			name,
+			items,
+			normal_errors,
+			deficient_errors,
+		}
+	}
+
+	/// Scores the readings of a subject, one for each item of the key,
+	/// where [None] means nothing is seen.
+	///
+	/// Readings are compared ignoring surrounding whitespace and ASCII case.
+	///
+	/// ```
+	/// use achroma::plate_test::{Key, Verdict, AnswerError};
+	/// use achroma::ColorVision;
+	///
+	/// let answers: Vec<_> = Key::ISHIHARA_38.items.iter().map(|item| item.normal).collect();
+	/// let score = Key::ISHIHARA_38.score(&answers).unwrap();
+	/// assert_eq!(score.verdict, Verdict::Pass);
+	/// assert_eq!(score.suspected, Some(ColorVision::Normal));
+	///
+	/// assert_eq!(Key::ISHIHARA_38.score(&answers[1..]), Err(AnswerError::Length));
+	/// ```
+	pub fn score(&self, answers: &[Option<&str>]) -> Result<Score, AnswerError> {
+		if answers.len() != self.items.len() {
+			return Err(AnswerError::Length);
+		}
+
+		let mut tally = Tally::new();
+		for (item, &answer) in self.items.iter().zip(answers) {
+			tally.record(item, answer);
+		}
+		Ok(tally.score(self))
+	}
+}
+
+/// Running counts of the readings of a subject, for scoring a test one
+/// plate at a time, which saturate rather than overflow
+///
+/// ```
+/// use achroma::plate_test::{Key, Tally, Verdict};
+///
+/// let key = Key::ISHIHARA_24;
+/// let mut tally = Tally::new();
+/// for item in key.items {
+///     tally.record(item, item.normal);
+/// }
+/// assert_eq!(tally.score(&key).verdict, Verdict::Pass);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tally {
+	invalid: bool,
+	screening_errors: u16,
+	diagnostic_errors: u16,
+	protan: u16,
+	deutan: u16,
+	tritan: u16,
+	skipped: u16,
+	grade: Option<Grade>,
+}
+
+impl Tally {
+	/// Creates a new tally without any readings
+	pub const fn new() -> Self {
+		Self {
+			invalid: false,
+			screening_errors: 0,
+			diagnostic_errors: 0,
+			protan: 0,
+			deutan: 0,
+			tritan: 0,
+			skipped: 0,
+			grade: None,
+		}
+	}
+
+	/// Records the reading of an item, where [None] means nothing is seen
+	pub fn record(&mut self, item: &Item, answer: Option<&str>) {
+		let misread = !same_reading(answer, item.normal);
+		match item.kind {
+			Kind::Demonstration => self.invalid |= misread,
+			Kind::Screening => {
+				self.screening_errors =
+					self.screening_errors.saturating_add(misread as u16)
+			}
+			Kind::Diagnostic => {
+				self.diagnostic_errors =
+					self.diagnostic_errors.saturating_add(misread as u16)
+			}
+		}
+		if !misread {
+			return;
+		}
+
+		let matches = |reading| reading != item.normal && same_reading(answer, reading);
+		self.protan = self.protan.saturating_add(matches(item.protan) as u16);
+		self.deutan = self.deutan.saturating_add(matches(item.deutan) as u16);
+		self.tritan = self.tritan.saturating_add(matches(item.tritan) as u16);
+		if item.kind == Kind::Diagnostic {
+			self.grade = self.grade.max(item.grade);
+		}
+	}
+
+	/// Records an item the subject skipped, which counts neither as read
+	/// nor as misread
+	pub fn skip(&mut self) {
+		self.skipped = self.skipped.saturating_add(1);
+	}
+
+	/// Scores the recorded readings by the limits of a key
+	pub fn score(&self, key: &Key) -> Score {
+		self.score_with(key.normal_errors, key.deficient_errors)
+	}
+
+	/// Scores the recorded readings by the most misread screening plates of
+	/// normal color vision and the fewest of deficient color vision, as in
+	/// [Key::normal_errors] and [Key::deficient_errors]
+	pub fn score_with(&self, normal_errors: u8, deficient_errors: u8) -> Score {
+		let verdict = if self.invalid {
+			Verdict::Invalid
+		} else if self.screening_errors <= u16::from(normal_errors) {
+			Verdict::Pass
+		} else if self.screening_errors >= u16::from(deficient_errors) {
+			Verdict::Fail
+		} else {
+			Verdict::Inconclusive
+		};
+		let mut score = Score {
+			verdict,
+			screening_errors: self.screening_errors,
+			diagnostic_errors: self.diagnostic_errors,
+			skipped: self.skipped,
+			protan: self.protan,
+			deutan: self.deutan,
+			tritan: self.tritan,
+			suspected: None,
+			grade: self.grade,
+		};
+		score.suspected = match verdict {
+			Verdict::Invalid => None,
+			Verdict::Pass => Some(ColorVision::Normal),
+			Verdict::Inconclusive | Verdict::Fail => score.axis(),
+		};
+		if verdict == Verdict::Pass {
+			score.grade = None;
+		}
+		score
+	}
+}
+
+/// The score of a plate test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Score {
+	/// The screening verdict
+	pub verdict: Verdict,
+	/// The number of misread screening plates
+	pub screening_errors: u16,
+	/// The number of misread diagnostic plates
+	pub diagnostic_errors: u16,
+	/// The number of skipped plates
+	pub skipped: u16,
+	/// The number of misread plates read as expected of protan color vision
+	pub protan: u16,
+	/// The number of misread plates read as expected of deutan color vision
+	pub deutan: u16,
+	/// The number of misread plates read as expected of tritan color vision
+	pub tritan: u16,
+	/// The suspected type of color vision, if the readings point to one.
+	///
+	/// Plate tests don't separate anomalous trichromacy from dichromacy,
+	/// so deficiencies are suspected as anomalous trichromacy.
+	pub suspected: Option<ColorVision>,
+	/// The strongest grade of the misread diagnostic plates, if graded
+	pub grade: Option<Grade>,
+}
+
+impl Score {
+	/// The type of deficiency with strictly the most matching readings
+	fn axis(&self) -> Option<ColorVision> {
+		let (protan, deutan, tritan) = (self.protan, self.deutan, self.tritan);
+		if protan > deutan && protan > tritan {
+			Some(ColorVision::Protanomaly)
+		} else if deutan > protan && deutan > tritan {
+			Some(ColorVision::Deuteranomaly)
+		} else if tritan > protan && tritan > deutan {
+			Some(ColorVision::Tritanomaly)
+		} else {
+			None
+		}
+	}
+}
+
+/// Compares two readings, ignoring surrounding whitespace and ASCII case,
+/// where an empty reading is the same as nothing seen
+fn same_reading(a: Option<&str>, b: Option<&str>) -> bool {
+	fn normalize(reading: Option<&str>) -> Option<&str> {
+		reading.map(str::trim).filter(|reading| !reading.is_empty())
+	}
+	match (normalize(a), normalize(b)) {
+		(Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+		(a, b) => a.is_none() && b.is_none(),
+	}
+}
+
+const fn screening(plate: u8, normal: &'static str, deficient: &'static str) -> Item<'static> {
+	Item::new(plate, Kind::Screening, Some(normal), Some(deficient))
+}
+
+const fn vanishing(plate: u8, normal: &'static str) -> Item<'static> {
+	Item::new(plate, Kind::Screening, Some(normal), None)
+}
+
+const fn hidden(plate: u8, deficient: &'static str) -> Item<'static> {
+	Item::new(plate, Kind::Screening, None, Some(deficient))
+}
+
+const ISHIHARA_38_ITEMS: [Item<'static>; 25] = [
+	Item::demonstration(1, "12"),
+	screening(2, "8", "3"),
+	screening(3, "6", "5"),
+	screening(4, "29", "70"),
+	screening(5, "57", "35"),
+	screening(6, "5", "2"),
+	screening(7, "3", "5"),
+	screening(8, "15", "17"),
+	screening(9, "74", "21"),
+	vanishing(10, "2"),
+	vanishing(11, "6"),
+	vanishing(12, "97"),
+	vanishing(13, "45"),
+	vanishing(14, "5"),
+	vanishing(15, "7"),
+	vanishing(16, "16"),
+	vanishing(17, "73"),
+	hidden(18, "5"),
+	hidden(19, "2"),
+	hidden(20, "45"),
+	hidden(21, "73"),
+	Item::diagnostic(22, "26", Some("6"), Some("2")),
+	Item::diagnostic(23, "42", Some("2"), Some("4")),
+	Item::diagnostic(24, "35", Some("5"), Some("3")),
+	Item::diagnostic(25, "96", Some("6"), Some("9")),
+];
+
+const ISHIHARA_24_ITEMS: [Item<'static>; 17] = [
+	Item::demonstration(1, "12"),
+	screening(2, "8", "3"),
+	screening(3, "29", "70"),
+	screening(4, "5", "2"),
+	screening(5, "3", "5"),
+	screening(6, "15", "17"),
+	screening(7, "74", "21"),
+	vanishing(8, "6"),
+	vanishing(9, "45"),
+	vanishing(10, "5"),
+	vanishing(11, "7"),
+	vanishing(12, "16"),
+	vanishing(13, "73"),
+	hidden(14, "5"),
+	hidden(15, "45"),
+	Item::diagnostic(16, "26", Some("6"), Some("2")),
+	Item::diagnostic(17, "42", Some("2"), Some("4")),
+];
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn readings<'a>(
+		key: &Key<'a>,
+		reading: impl Fn(&Item<'a>) -> Option<&'a str>,
+	) -> [Option<&'a str>; 25] {
+		let mut answers = [None; 25];
+		for (answer, item) in answers.iter_mut().zip(key.items) {
+			*answer = reading(item);
+		}
+		answers
+	}
+
+	#[test]
+	fn test_keys_are_ordered() {
+		for key in [Key::ISHIHARA_38, Key::ISHIHARA_24] {
+			for (index, item) in key.items.iter().enumerate() {
+				assert_eq!(item.plate as usize, index + 1);
+			}
+			assert!(key.normal_errors < key.deficient_errors);
+		}
+	}
+
+	#[test]
+	fn test_deutan_readings() {
+		let key = Key::ISHIHARA_38;
+		let answers = readings(&key, |item| item.deutan);
+		let score = key.score(&answers).unwrap();
+		assert_eq!(score.verdict, Verdict::Fail);
+		assert_eq!(score.screening_errors, 20);
+		assert_eq!(score.diagnostic_errors, 4);
+		assert_eq!(score.suspected, Some(ColorVision::Deuteranomaly));
+		assert_eq!(score.grade, None);
+	}
+
+	#[test]
+	fn test_inconclusive() {
+		let key = Key::ISHIHARA_38;
+		let mut answers = readings(&key, |item| item.normal);
+		for answer in &mut answers[1..7] {
+			*answer = None;
+		}
+		let score = key.score(&answers).unwrap();
+		assert_eq!(score.screening_errors, 6);
+		assert_eq!(score.verdict, Verdict::Inconclusive);
+		assert_eq!(score.suspected, None);
+	}
+
+	#[test]
+	fn test_invalid_demonstration() {
+		let key = Key::ISHIHARA_24;
+		let mut answers = readings(&key, |item| item.normal);
+		answers[0] = Some("21");
+		let score = key.score(&answers[..17]).unwrap();
+		assert_eq!(score.verdict, Verdict::Invalid);
+		assert_eq!(score.suspected, None);
+	}
+
+	#[test]
+	fn test_tally_saturates() {
+		let item = Item::new(2, Kind::Screening, Some("8"), Some("3"));
+		let mut tally = Tally::new();
+		for _ in 0..=u16::MAX as u32 {
+			tally.record(&item, Some("3"));
+			tally.skip();
+		}
+		let score = tally.score(&Key::ISHIHARA_38);
+		assert_eq!(score.screening_errors, u16::MAX);
+		assert_eq!(score.protan, u16::MAX);
+		assert_eq!(score.skipped, u16::MAX);
+		assert_eq!(score.verdict, Verdict::Fail);
+	}
+
+	#[test]
+	fn test_reading_normalization() {
+		assert!(same_reading(Some(" 12 "), Some("12")));
+		assert!(same_reading(Some("Circle"), Some("circle")));
+		assert!(same_reading(Some(""), None));
+		assert!(!same_reading(Some("12"), None));
+	}
+
+	#[test]
+	fn test_graded_key() {
+		// a key laid out like the HRR test, with one figure per defect axis
+		let items = [
+			Item::demonstration(1, "cross"),
+			Item::new(5, Kind::Screening, Some("triangle"), Some("triangle"))
+				.with_tritan(None),
+			Item::new(7, Kind::Screening, Some("circle"), None),
+			Item::diagnostic(11, "cross", None, Some("cross")).with_grade(Grade::Mild),
+			Item::diagnostic(11, "circle", Some("circle"), None)
+				.with_grade(Grade::Mild),
+			Item::diagnostic(16, "triangle", None, Some("triangle"))
+				.with_grade(Grade::Medium),
+			Item::diagnostic(16, "cross", Some("cross"), None)
+				.with_grade(Grade::Medium),
+			Item::diagnostic(19, "circle", None, Some("circle"))
+				.with_grade(Grade::Strong),
+			Item::diagnostic(19, "cross", Some("cross"), None)
+				.with_grade(Grade::Strong),
+		];
+		let key = Key::new("HRR-like", &items, 0, 1);
+
+		let protan = [
+			Some("cross"),
+			Some("triangle"),
+			None,
+			None,
+			Some("circle"),
+			None,
+			Some("cross"),
+			Some("circle"),
+			Some("cross"),
+		];
+		let score = key.score(&protan).unwrap();
+		assert_eq!(score.verdict, Verdict::Fail);
+		assert_eq!(score.suspected, Some(ColorVision::Protanomaly));
+		assert_eq!(score.grade, Some(Grade::Medium));
+
+		let tritan = [
+			Some("cross"),
+			None,
+			Some("circle"),
+			Some("cross"),
+			Some("circle"),
+			Some("triangle"),
+			Some("cross"),
+			Some("circle"),
+			Some("cross"),
+		];
+		let score = key.score(&tritan).unwrap();
+		assert_eq!(score.suspected, Some(ColorVision::Tritanomaly));
+		assert_eq!(score.grade, None);
+	}
+}
@@ -0,0 +1,335 @@
+//! Prevalence of color vision deficiencies, and sampling of synthetic cohorts
+//!
+//! The prevalence of each type of color vision among people of European
+//! ancestry follows the table compiled by Sharpe, Stockman, Jägle & Nathans,
+//! "Opsin genes, cone photopigments, color vision, and color blindness", in
+//! Color Vision: From Genes to Perception (1999). Tritan defects are
+//! estimated at about 1 in 13,000 to 1 in 65,000 people by Kalmus, "The
+//! familial distribution of congenital tritanopia", Annals of Human Genetics
+//! 20 (1955).
+//!
+//! Red-green deficiencies are less common in other populations, as reviewed
+//! by Birch, "Worldwide prevalence of red-green color deficiency", Journal of
+//! the Optical Society of America A 29 (2012). As they are X-linked, their
+//! prevalence in females is about the square of their prevalence in males.
+//! All values are approximate, and vary between studies.
+//!
+//! ```
+//! use achroma::genetics::Sex;
+//! use achroma::prevalence::Population;
+//! use achroma::ColorVision;
+//!
+//! let population = Population::European;
+//! assert!(population.prevalence(ColorVision::Deuteranomaly, Sex::Male) > 0.04);
+//!
+//! // estimate how many of 2 million users, half of them female, can't tell red from green
+//! let red_green = |vision: ColorVision| vision.is_protan() || vision.is_deutan();
+//! let affected = population.affected(0.5, red_green) * 2_000_000.0;
+//! assert!(affected > 75_000.0 && affected < 90_000.0);
+//! ```
+
+use crate::genetics::Sex;
+use crate::{Classification, ColorVision};
+
+/// The prevalence of each color vision among males and females of European
+/// ancestry, as fractions between 0.0 and 1.0. Normal color vision is the remainder.
+pub const EUROPEAN: [(ColorVision, f32, f32); 8] = [
+	(ColorVision::Protanomaly, 0.0108, 0.0003),
+	(ColorVision::Protanopia, 0.0101, 0.0002),
+	(ColorVision::Deuteranomaly, 0.0463, 0.0036),
+	(ColorVision::Deuteranopia, 0.0127, 0.0001),
+	(ColorVision::Tritanomaly, 0.00001, 0.00001),
+	(ColorVision::Tritanopia, 0.00005, 0.00005),
+	(ColorVision::Achromatomaly, 0.00001, 0.0),
+	(ColorVision::Achromatopsia, 0.00003, 0.00003),
+];
+
+/// A major population group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Population {
+	/// People of European ancestry
+	#[default]
+	European,
+	/// People of East Asian ancestry
+	EastAsian,
+	/// People of South Asian ancestry
+	SouthAsian,
+	/// People of Sub-Saharan African ancestry
+	African,
+	/// Indigenous peoples of the Americas
+	IndigenousAmerican,
+}
+
+impl Population {
+	/// All population groups
+	pub const ALL: [Self; 5] = [
+		Self::European,
+		Self::EastAsian,
+		Self::SouthAsian,
+		Self::African,
+		Self::IndigenousAmerican,
+	];
+
+	/// Gets the name of the population group, such as `"east asian"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::European => "european",
+			Self::EastAsian => "east asian",
+			Self::SouthAsian => "south asian",
+			Self::African => "african",
+			Self::IndigenousAmerican => "indigenous american",
+		}
+	}
+
+	/// The approximate prevalence of red-green deficiencies among males
+	pub const fn red_green_males(&self) -> f32 {
+		match self {
+			Self::European => 0.08,
+			Self::EastAsian => 0.055,
+			Self::SouthAsian => 0.05,
+			Self::African => 0.025,
+			Self::IndigenousAmerican => 0.02,
+		}
+	}
+
+	/// The prevalence of a color vision among people of a sex, as a fraction
+	/// between 0.0 and 1.0
+	///
+	/// The types of red-green deficiency keep the proportions of the
+	/// European population, scaled to the prevalence of the population.
+	///
+	/// ```
+	/// use achroma::genetics::Sex;
+	/// use achroma::prevalence::Population;
+	/// use achroma::ColorVision;
+	///
+	/// let total: f32 = ColorVision::ALL
+	///     .into_iter()
+	///     .map(|vision| Population::African.prevalence(vision, Sex::Female))
+	///     .sum();
+	/// assert!((total - 1.0).abs() < 1e-6);
+	/// ```
+	pub fn prevalence(&self, vision: ColorVision, sex: Sex) -> f32 {
+		if vision == ColorVision::Normal {
+			let deficient: f32 = EUROPEAN
+				.iter()
+				.map(|&(vision, ..)| self.prevalence(vision, sex))
+				.sum();
+			return 1.0 - deficient;
+		}
+		let Some(&(_, males, females)) =
+			EUROPEAN.iter().find(|(other, ..)| *other == vision)
+		else {
+			return 0.0;
+		};
+		if !(vision.is_protan() || vision.is_deutan()) {
+			return match sex {
+				Sex::Male => males,
+				Sex::Female => females,
+			};
+		}
+		let ratio = self.red_green_males() / Self::European.red_green_males();
+		match sex {
+			Sex::Male => males * ratio,
+			Sex::Female => females * ratio * ratio,
+		}
+	}
+
+	/// The prevalence of the color visions matching a predicate, in a group
+	/// with a fraction of females between 0.0 and 1.0
+	pub fn affected(
+		&self,
+		females: f32,
+		mut predicate: impl FnMut(ColorVision) -> bool,
+	) -> f32 {
+		let females = females.clamp(0.0, 1.0);
+		ColorVision::ALL
+			.into_iter()
+			.filter(|&vision| predicate(vision))
+			.map(|vision| {
+				let male = self.prevalence(vision, Sex::Male);
+				let female = self.prevalence(vision, Sex::Female);
+				(1.0 - females) * male + females * female
+			})
+			.sum()
+	}
+}
+
+/// A member of a synthetic cohort
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Member {
+	/// The sex of the member
+	pub sex: Sex,
+	/// The color vision of the member, and its severity
+	pub classification: Classification,
+}
+
+/// A deterministic generator of a synthetic cohort, sampling the sex, color
+/// vision and severity of each member from a seed
+///
+/// Dichromacies and monochromacies have a severity of 1.0. The severity of
+/// anomalous trichromacies is spread over the whole range, where deuteranomaly
+/// tends to be milder than protanomaly.
+///
+/// ```
+/// use achroma::prevalence::{Cohort, Population};
+/// use achroma::ColorVision;
+///
+/// let cohort = Cohort::new(42, Population::European);
+/// let affected = cohort
+///     .take(10_000)
+///     .filter(|member| member.classification.vision != ColorVision::Normal)
+///     .count();
+/// assert!(affected > 300 && affected < 550);
+///
+/// // the same seed always generates the same cohort
+/// let a: Vec<_> = Cohort::new(7, Population::EastAsian).take(100).collect();
+/// let b: Vec<_> = Cohort::new(7, Population::EastAsian).take(100).collect();
+/// assert_eq!(a, b);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cohort {
+	state: u64,
+	population: Population,
+	females: f32,
+}
+
+impl Cohort {
+	/// Creates a new cohort of a population from a seed, where half of the members are female
+	pub const fn new(seed: u64, population: Population) -> Self {
+		Self {
+			state: seed,
+			population,
+			females: 0.5,
+		}
+	}
+
+	/// Uses another fraction of females between 0.0 and 1.0
+	pub const fn with_females(mut self, females: f32) -> Self {
+		self.females = females;
+		self
+	}
+
+	/// The population of the cohort
+	pub const fn population(&self) -> Population {
+		self.population
+	}
+
+	/// Generates a uniformly distributed number between 0.0 and 1.0 with SplitMix64
+	fn uniform(&mut self) -> f32 {
+		self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^= z >> 31;
+		// the upper 24 bits fit exactly in the mantissa
+		(z >> 40) as f32 / (1u64 << 24) as f32
+	}
+
+	/// Samples a beta distribution with integer parameters, as the `a`-th
+	/// smallest of `a + b - 1` uniform numbers
+	fn beta(&mut self, a: usize, b: usize) -> f32 {
+		let mut samples = [0.0; 8];
+		let samples = &mut samples[..a + b - 1];
+		for sample in samples.iter_mut() {
+			*sample = self.uniform();
+		}
+		samples.sort_unstable_by(f32::total_cmp);
+		samples[a - 1]
+	}
+
+	fn severity(&mut self, vision: ColorVision) -> f32 {
+		match vision {
+			ColorVision::Normal => 0.0,
+			ColorVision::Protanomaly => self.beta(3, 2),
+			ColorVision::Deuteranomaly => self.beta(2, 3),
+			ColorVision::Tritanomaly => self.beta(2, 2),
+			_ => 1.0,
+		}
+	}
+}
+
+impl Iterator for Cohort {
+	type Item = Member;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let sex = if self.uniform() < self.females {
+			Sex::Female
+		} else {
+			Sex::Male
+		};
+		let mut remaining = self.uniform();
+		let mut vision = ColorVision::Normal;
+		for (deficiency, ..) in EUROPEAN {
+			let prevalence = self.population.prevalence(deficiency, sex);
+			if remaining < prevalence {
+				vision = deficiency;
+				break;
+			}
+			remaining -= prevalence;
+		}
+		let severity = self.severity(vision);
+		Some(Member {
+			sex,
+			classification: Classification::new(vision, severity),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_european_red_green() {
+		let males = Population::European
+			.affected(0.0, |vision| vision.is_protan() || vision.is_deutan());
+		assert!((males - Population::European.red_green_males()).abs() < 0.001);
+	}
+
+	#[test]
+	fn test_population_scaling() {
+		for population in Population::ALL {
+			let males = population.prevalence(ColorVision::Deuteranomaly, Sex::Male);
+			let females =
+				population.prevalence(ColorVision::Deuteranomaly, Sex::Female);
+			assert!(females < males);
+			assert_eq!(
+				population.prevalence(ColorVision::Tritanopia, Sex::Male),
+				Population::European.prevalence(ColorVision::Tritanopia, Sex::Male)
+			);
+		}
+	}
+
+	#[test]
+	fn test_cohort_severity() {
+		let mut cohort = Cohort::new(1, Population::European).with_females(0.0);
+		let (mut total, mut count) = (0.0, 0);
+		for member in cohort.by_ref().take(20_000) {
+			assert_eq!(member.sex, Sex::Male);
+			let Classification { vision, severity } = member.classification;
+			match vision {
+				ColorVision::Normal => assert_eq!(severity, 0.0),
+				ColorVision::Deuteranomaly => {
+					assert!((0.0..=1.0).contains(&severity));
+					total += severity;
+					count += 1;
+				}
+				vision if vision.is_dichromacy() => assert_eq!(severity, 1.0),
+				_ => {}
+			}
+		}
+		// the mean of the beta distribution with a = 2 and b = 3
+		assert!(count > 700);
+		assert!((total / count as f32 - 0.4).abs() < 0.03);
+	}
+
+	#[test]
+	fn test_cohort_seeds_differ() {
+		let a = Cohort::new(1, Population::European).map(|member| member.sex);
+		let b = Cohort::new(2, Population::European).map(|member| member.sex);
+		assert!(a.zip(b).take(100).any(|(a, b)| a != b));
+	}
+}
@@ -0,0 +1,50 @@
+//! Serialization of arrays longer than those supported by serde
+
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S, T, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+	S: Serializer,
+	T: Serialize,
+{
+	let mut tuple = serializer.serialize_tuple(N)?;
+	for element in array {
+		tuple.serialize_element(element)?;
+	}
+	tuple.end()
+}
+
+pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+	D: Deserializer<'de>,
+	T: Deserialize<'de> + Copy + Default,
+{
+	struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+	impl<'de, T, const N: usize> Visitor<'de> for ArrayVisitor<T, N>
+	where
+		T: Deserialize<'de> + Copy + Default,
+	{
+		type Value = [T; N];
+
+		fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+			write!(f, "an array of length {}", N)
+		}
+
+		fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+			let mut array = [T::default(); N];
+			for (index, element) in array.iter_mut().enumerate() {
+				*element = seq
+					.next_element()?
+					.ok_or_else(|| A::Error::invalid_length(index, &self))?;
+			}
+			Ok(array)
+		}
+	}
+
+	deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
@@ -0,0 +1,664 @@
+//! Screening sessions which sequence several tests into a report
+//!
+//! A [Session] runs the tests of a [Plan] in order: a plate test, then an
+//! arrangement test, then a threshold test along each cone axis. It records
+//! the responses of each stage, handles skipped and timed out stages, and
+//! finishes with a [Report] that weighs the evidence of every test.
+//!
+//! A session only keeps running counts and scores, so it can be copied or,
+//! with the `serde` feature, serialized to pause it and resume it later.
+//!
+//! ```
+//! use achroma::adaptive::{ConeTest, Staircase};
+//! use achroma::plate_test::Key;
+//! use achroma::session::{Confidence, Finding, PlateResponse, Plan, Session, Stage};
+//! use achroma::ColorVision;
+//!
+//! let key = Key::ISHIHARA_24;
+//! let tests = ConeTest::from_fn(|_| Staircase::new(0.1));
+//! let mut session = Session::new(Plan::SCREENING, tests);
+//!
+//! // a deutan subject
+//! while session.stage() == Stage::Plates {
+//!     let item = key.items[session.plates_answered() as usize];
+//!     session.respond_plate(&key, PlateResponse::Read(item.deutan)).unwrap();
+//! }
+//! session.submit_arrangement(&[1, 15, 2, 14, 3, 13, 4, 12, 5, 11, 10, 6, 9, 7, 8]).unwrap();
+//!
+//! let report = session.report().unwrap();
+//! assert_eq!(report.finding, Finding::Vision(ColorVision::Deuteranopia));
+//! assert_eq!(report.confidence, Confidence::High);
+//! ```
+
+use crate::adaptive::{ConeLimits, ConeTest, Procedure, Staircase, Trial};
+use crate::d15::{self, ArrangementError, Panel};
+use crate::plate_test::{self, Key, Tally};
+use crate::{ColorVision, ConeCell, ConeCellSummary};
+
+/// The tests of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plan {
+	/// Whether the session starts with a plate test
+	pub plates: bool,
+	/// The panel of the arrangement test, if any
+	pub arrangement: Option<Panel>,
+	/// Whether the session ends with a threshold test
+	pub threshold: bool,
+}
+
+impl Plan {
+	/// A plate test followed by the Farnsworth D-15
+	pub const SCREENING: Self = Self::new(true, Some(Panel::Farnsworth), false);
+
+	/// A plate test, the Farnsworth D-15 and a threshold test
+	pub const FULL: Self = Self::new(true, Some(Panel::Farnsworth), true);
+
+	/// Creates a new plan from its tests
+	pub const fn new(plates: bool, arrangement: Option<Panel>, threshold: bool) -> Self {
+		Self {
+			plates,
+			arrangement,
+			threshold,
+		}
+	}
+
+	const fn includes(&self, stage: Stage) -> bool {
+		match stage {
+			Stage::Plates => self.plates,
+			Stage::Arrangement => self.arrangement.is_some(),
+			Stage::Threshold => self.threshold,
+			Stage::Finished => true,
+		}
+	}
+}
+
+impl Default for Plan {
+	fn default() -> Self {
+		Self::SCREENING
+	}
+}
+
+/// A stage of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stage {
+	/// Reading the plates of a plate test
+	Plates,
+	/// Arranging the caps of an arrangement test
+	Arrangement,
+	/// Responding to the trials of a threshold test
+	Threshold,
+	/// Every test is done, and the report is ready
+	Finished,
+}
+
+/// The status of a test within a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Status {
+	/// The test isn't part of the plan
+	NotPlanned,
+	/// The test hasn't finished yet
+	#[default]
+	Pending,
+	/// The test finished normally
+	Completed,
+	/// The test was skipped, and its responses are ignored
+	Skipped,
+	/// The test ran out of time, and its responses so far are used
+	TimedOut,
+}
+
+/// The response to a plate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlateResponse<'r> {
+	/// The reading of the plate, where [None] means nothing is seen
+	Read(Option<&'r str>),
+	/// The subject skipped the plate
+	Skipped,
+	/// The subject didn't respond in time, which counts as nothing seen
+	TimedOut,
+}
+
+/// An error when a response doesn't fit a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionError {
+	/// The response belongs to a different stage than the current one
+	Stage(Stage),
+	/// The answer key is empty, has more than 255 plates, or has a different
+	/// number of plates than earlier responses
+	Key,
+	/// The arrangement of caps isn't valid
+	Arrangement(ArrangementError),
+}
+
+/// A screening session, generic over the adaptive procedure of its threshold test
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Session<P = Staircase> {
+	plan: Plan,
+	stage: Stage,
+	statuses: [Status; 3],
+	plates: u8,
+	key: Option<KeyLimits>,
+	tally: Tally,
+	arrangement: Option<d15::Score>,
+	threshold: ConeTest<P>,
+	limits: ConeLimits,
+}
+
+/// The parts of an answer key needed to resume and score a plate test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct KeyLimits {
+	len: usize,
+	normal_errors: u8,
+	deficient_errors: u8,
+}
+
+impl<P: Procedure> Session<P> {
+	/// Creates a new session running the tests of a plan, with the procedures
+	/// of its threshold test
+	pub fn new(plan: Plan, threshold: ConeTest<P>) -> Self {
+		let status = |stage| {
+			if plan.includes(stage) {
+				Status::Pending
+			} else {
+				Status::NotPlanned
+			}
+		};
+		let mut session = Self {
+			plan,
+			stage: Stage::Plates,
+			statuses: [
+				status(Stage::Plates),
+				status(Stage::Arrangement),
+				status(Stage::Threshold),
+			],
+			plates: 0,
+			key: None,
+			tally: Tally::new(),
+			arrangement: None,
+			threshold,
+			limits: ConeLimits::DEFAULT,
+		};
+		if !plan.plates {
+			session.stage = session.next_stage(Stage::Plates);
+		}
+		session
+	}
+
+	/// Sets the limits which map the thresholds to the condition of each cone cell
+	pub fn with_limits(self, limits: ConeLimits) -> Self {
+		Self { limits, ..self }
+	}
+
+	/// The plan of the session
+	pub const fn plan(&self) -> Plan {
+		self.plan
+	}
+
+	/// The current stage
+	pub const fn stage(&self) -> Stage {
+		self.stage
+	}
+
+	/// Whether every test is done
+	pub fn is_finished(&self) -> bool {
+		self.stage == Stage::Finished
+	}
+
+	/// The status of the test of a stage, or [None] for [Stage::Finished]
+	pub const fn status(&self, stage: Stage) -> Option<Status> {
+		match stage {
+			Stage::Plates => Some(self.statuses[0]),
+			Stage::Arrangement => Some(self.statuses[1]),
+			Stage::Threshold => Some(self.statuses[2]),
+			Stage::Finished => None,
+		}
+	}
+
+	/// The number of plates responded to, which is the index of the next plate
+	pub const fn plates_answered(&self) -> u8 {
+		self.plates
+	}
+
+	/// Records the response to the next plate of an answer key, moving on to
+	/// the next stage after the last plate.
+	///
+	/// The same key must be used for every plate of the session, and it must
+	/// have from 1 to 255 plates.
+	pub fn respond_plate(
+		&mut self,
+		key: &Key,
+		response: PlateResponse,
+	) -> Result<(), SessionError> {
+		self.expect(Stage::Plates)?;
+		let limits = KeyLimits {
+			len: key.items.len(),
+			normal_errors: key.normal_errors,
+			deficient_errors: key.deficient_errors,
+		};
+		if self.key.is_some_and(|key| key != limits) || limits.len > u8::MAX as usize {
+			return Err(SessionError::Key);
+		}
+		let Some(item) = key.items.get(self.plates as usize) else {
+			return Err(SessionError::Key);
+		};
+		self.key = Some(limits);
+
+		match response {
+			PlateResponse::Read(answer) => self.tally.record(item, answer),
+			PlateResponse::Skipped => self.tally.skip(),
+			PlateResponse::TimedOut => self.tally.record(item, None),
+		}
+		self.plates += 1;
+		if self.plates as usize >= limits.len {
+			self.advance(Status::Completed);
+		}
+		Ok(())
+	}
+
+	/// Submits the order of the caps of the arrangement test, by their numbers
+	pub fn submit_arrangement(&mut self, order: &[u8]) -> Result<(), SessionError> {
+		self.expect(Stage::Arrangement)?;
+		let panel = self.plan.arrangement.unwrap_or_default();
+		let score = panel.score(order).map_err(SessionError::Arrangement)?;
+		self.arrangement = Some(score);
+		self.advance(Status::Completed);
+		Ok(())
+	}
+
+	/// The next trial of the threshold test, or [None] outside of its stage
+	pub fn next_trial(&mut self) -> Option<Trial> {
+		if self.stage != Stage::Threshold {
+			return None;
+		}
+		self.threshold.next_trial()
+	}
+
+	/// Records the response to a trial of the threshold test, moving on
+	/// once every procedure is finished.
+	///
+	/// A trial without a response in time should be recorded as incorrect.
+	pub fn respond_trial(&mut self, cone: ConeCell, correct: bool) -> Result<(), SessionError> {
+		self.expect(Stage::Threshold)?;
+		self.threshold.respond(cone, correct);
+		if self.threshold.is_finished() {
+			self.advance(Status::Completed);
+		}
+		Ok(())
+	}
+
+	/// Skips the rest of the current test, ignoring its responses
+	pub fn skip(&mut self) -> Result<(), SessionError> {
+		self.end(Status::Skipped)
+	}
+
+	/// Ends the current test as it ran out of time, keeping its responses so far
+	pub fn time_out(&mut self) -> Result<(), SessionError> {
+		self.end(Status::TimedOut)
+	}
+
+	/// The report of a finished session, or [None] while tests remain.
+	///
+	/// The finding follows the most specific test with a result, where the
+	/// threshold test is preferred over the arrangement test, and the
+	/// arrangement test over the plate test. The confidence is high when at
+	/// least two tests agree on the type of color vision and none disagree,
+	/// moderate when a single test has a result or a test didn't complete,
+	/// and low when tests disagree.
+	pub fn report(&self) -> Option<Report> {
+		if !self.is_finished() {
+			return None;
+		}
+
+		let evidence = self.evidence();
+		let arrangement = evidence.arrangement.and_then(|score| score.classify());
+		let sources = [
+			evidence.thresholds
+				.map(|summary| match ColorVision::try_from(summary) {
+					Ok(vision) => Finding::Vision(vision),
+					Err(()) => Finding::Cones(summary),
+				}),
+			arrangement.map(|classification| Finding::Vision(classification.vision)),
+			evidence.plates
+				.and_then(|score| score.suspected)
+				.map(Finding::Vision),
+		];
+
+		let Some(finding) = sources.iter().flatten().copied().next() else {
+			return Some(Report {
+				finding: Finding::Undetermined,
+				severity: None,
+				confidence: Confidence::Low,
+				evidence,
+			});
+		};
+		let (mut agree, mut disagree) = (0, 0);
+		for source in sources.iter().flatten() {
+			if source.family() == finding.family() {
+				agree += 1;
+			} else {
+				disagree += 1;
+			}
+		}
+		let incomplete = evidence
+			.statuses
+			.iter()
+			.any(|&status| status == Status::Skipped || status == Status::TimedOut);
+		let confidence = if disagree > 0 {
+			Confidence::Low
+		} else if agree >= 2 && !incomplete {
+			Confidence::High
+		} else {
+			Confidence::Moderate
+		};
+
+		let severity = match finding {
+			Finding::Vision(ColorVision::Normal) => Some(0.0),
+			Finding::Vision(vision) => arrangement
+				.filter(|classification| {
+					Finding::Vision(classification.vision).family()
+						== finding.family()
+				})
+				.map(|classification| classification.severity)
+				.or((vision.is_dichromacy() || vision.is_monochromacy())
+					.then_some(1.0)),
+			Finding::Cones(_) | Finding::Undetermined => None,
+		};
+		Some(Report {
+			finding,
+			severity,
+			confidence,
+			evidence,
+		})
+	}
+
+	fn evidence(&self) -> Evidence {
+		let plates = match (self.statuses[0], self.key) {
+			(Status::Completed | Status::TimedOut, Some(limits)) if self.plates > 0 => {
+				Some(self
+					.tally
+					.score_with(limits.normal_errors, limits.deficient_errors))
+			}
+			_ => None,
+		};
+		let arrangement = match self.statuses[1] {
+			Status::Completed => self.arrangement,
+			_ => None,
+		};
+		let thresholds = match self.statuses[2] {
+			Status::Completed | Status::TimedOut => {
+				self.threshold.summary(&self.limits)
+			}
+			_ => None,
+		};
+		Evidence {
+			plates,
+			arrangement,
+			thresholds,
+			statuses: self.statuses,
+		}
+	}
+
+	fn expect(&self, stage: Stage) -> Result<(), SessionError> {
+		if self.stage == stage {
+			Ok(())
+		} else {
+			Err(SessionError::Stage(self.stage))
+		}
+	}
+
+	fn end(&mut self, status: Status) -> Result<(), SessionError> {
+		if self.stage == Stage::Finished {
+			return Err(SessionError::Stage(Stage::Finished));
+		}
+		self.advance(status);
+		Ok(())
+	}
+
+	fn advance(&mut self, status: Status) {
+		let index = match self.stage {
+			Stage::Plates => 0,
+			Stage::Arrangement => 1,
+			Stage::Threshold => 2,
+			Stage::Finished => return,
+		};
+		self.statuses[index] = status;
+		self.stage = self.next_stage(self.stage);
+	}
+
+	fn next_stage(&self, stage: Stage) -> Stage {
+		let mut stage = stage;
+		loop {
+			stage = match stage {
+				Stage::Plates => Stage::Arrangement,
+				Stage::Arrangement => Stage::Threshold,
+				Stage::Threshold | Stage::Finished => Stage::Finished,
+			};
+			if self.plan.includes(stage) {
+				return stage;
+			}
+		}
+	}
+}
+
+/// The finding of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Finding {
+	/// A type of color vision
+	Vision(ColorVision),
+	/// The condition of each cone cell, when it doesn't match a type of color vision
+	Cones(ConeCellSummary),
+	/// No test produced a result
+	Undetermined,
+}
+
+impl Finding {
+	/// The family of the finding, where normal, protan, deutan, tritan and
+	/// achromatic color vision each form their own family
+	fn family(&self) -> u8 {
+		match self {
+			Self::Vision(ColorVision::Normal) => 0,
+			Self::Vision(vision) if vision.is_protan() => 1,
+			Self::Vision(vision) if vision.is_deutan() => 2,
+			Self::Vision(vision) if vision.is_tritan() => 3,
+			Self::Vision(_) => 4,
+			Self::Cones(_) => 5,
+			Self::Undetermined => 6,
+		}
+	}
+}
+
+/// The confidence of a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Confidence {
+	/// Tests disagree, or no test produced a result
+	Low,
+	/// A single test produced a result, or a test didn't complete
+	Moderate,
+	/// Several completed tests agree
+	High,
+}
+
+/// The results of each test that contributed to a report
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Evidence {
+	/// The score of the plate test
+	pub plates: Option<plate_test::Score>,
+	/// The score of the arrangement test
+	pub arrangement: Option<d15::Score>,
+	/// The condition of each cone cell from the threshold test
+	pub thresholds: Option<ConeCellSummary>,
+	/// The status of the plate, arrangement and threshold tests
+	pub statuses: [Status; 3],
+}
+
+/// The report of a finished session
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+	/// The finding of the session
+	pub finding: Finding,
+	/// The severity of the finding from 0.0 to 1.0, if measured
+	pub severity: Option<f32>,
+	/// The confidence of the finding
+	pub confidence: Confidence,
+	/// The results of each test
+	pub evidence: Evidence,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PERFECT: [u8; 15] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+	fn session(plan: Plan) -> Session {
+		Session::new(plan, ConeTest::from_fn(|_| Staircase::new(0.1)))
+	}
+
+	fn read_plates(
+		session: &mut Session,
+		key: &Key<'static>,
+		reading: impl Fn(&plate_test::Item<'static>) -> Option<&'static str>,
+	) {
+		while session.stage() == Stage::Plates {
+			let item = &key.items[session.plates_answered() as usize];
+			let response = PlateResponse::Read(reading(item));
+			session.respond_plate(key, response).unwrap();
+		}
+	}
+
+	#[test]
+	fn test_stages_follow_plan() {
+		let mut session = session(Plan::new(false, Some(Panel::Lanthony), false));
+		assert_eq!(session.stage(), Stage::Arrangement);
+		assert_eq!(session.status(Stage::Plates), Some(Status::NotPlanned));
+		assert_eq!(
+			session.respond_plate(&Key::ISHIHARA_24, PlateResponse::Skipped),
+			Err(SessionError::Stage(Stage::Arrangement))
+		);
+		session.submit_arrangement(&PERFECT).unwrap();
+		assert!(session.is_finished());
+		assert_eq!(session.skip(), Err(SessionError::Stage(Stage::Finished)));
+
+		let report = session.report().unwrap();
+		assert_eq!(report.finding, Finding::Vision(ColorVision::Normal));
+		assert_eq!(report.confidence, Confidence::Moderate);
+	}
+
+	#[test]
+	fn test_normal_session() {
+		let key = Key::ISHIHARA_38;
+		let mut session = session(Plan::FULL);
+		read_plates(&mut session, &key, |item| item.normal);
+		assert_eq!(session.report(), None);
+		session.submit_arrangement(&PERFECT).unwrap();
+
+		while let Some(trial) = session.next_trial() {
+			session.respond_trial(trial.cone, trial.contrast >= 0.005)
+				.unwrap();
+		}
+		let report = session.report().unwrap();
+		assert_eq!(report.finding, Finding::Vision(ColorVision::Normal));
+		assert_eq!(report.severity, Some(0.0));
+		assert_eq!(report.confidence, Confidence::High);
+		assert_eq!(report.evidence.thresholds, Some(ConeCellSummary::NORMAL));
+	}
+
+	#[test]
+	fn test_disagreement_lowers_confidence() {
+		let key = Key::ISHIHARA_24;
+		let mut session = session(Plan::SCREENING);
+		read_plates(&mut session, &key, |item| item.protan);
+		session.submit_arrangement(&PERFECT).unwrap();
+
+		let report = session.report().unwrap();
+		assert_eq!(report.finding, Finding::Vision(ColorVision::Normal));
+		assert_eq!(report.confidence, Confidence::Low);
+	}
+
+	#[test]
+	fn test_skips_and_timeouts() {
+		let key = Key::ISHIHARA_24;
+		let mut session = session(Plan::FULL);
+		session.respond_plate(&key, PlateResponse::Read(Some("12")))
+			.unwrap();
+		session.respond_plate(&key, PlateResponse::Skipped).unwrap();
+		session.respond_plate(&key, PlateResponse::TimedOut)
+			.unwrap();
+		assert_eq!(
+			session.respond_plate(&Key::ISHIHARA_38, PlateResponse::Skipped),
+			Err(SessionError::Key)
+		);
+		session.time_out().unwrap();
+		assert_eq!(session.status(Stage::Plates), Some(Status::TimedOut));
+		assert_eq!(
+			session.submit_arrangement(&[1, 2, 3]),
+			Err(SessionError::Arrangement(ArrangementError::Length(3)))
+		);
+		session.skip().unwrap();
+		session.skip().unwrap();
+
+		let report = session.report().unwrap();
+		let plates = report.evidence.plates.unwrap();
+		assert_eq!(plates.skipped, 1);
+		assert_eq!(plates.screening_errors, 1);
+		assert_eq!(report.evidence.arrangement, None);
+		assert_eq!(report.finding, Finding::Vision(ColorVision::Normal));
+		assert_eq!(report.confidence, Confidence::Moderate);
+	}
+
+	#[test]
+	fn test_invalid_keys() {
+		let mut session = session(Plan::SCREENING);
+		let empty = Key::new("empty", &[], 0, 1);
+		assert_eq!(
+			session.respond_plate(&empty, PlateResponse::Skipped),
+			Err(SessionError::Key)
+		);
+
+		let items = [Key::ISHIHARA_24.items[1]; 256];
+		let long = Key::new("long", &items, 0, 1);
+		assert_eq!(
+			session.respond_plate(&long, PlateResponse::Skipped),
+			Err(SessionError::Key)
+		);
+		assert_eq!(session.plates_answered(), 0);
+		assert_eq!(session.stage(), Stage::Plates);
+	}
+
+	#[test]
+	fn test_undetermined() {
+		let mut session = session(Plan::SCREENING);
+		session.skip().unwrap();
+		session.skip().unwrap();
+		let report = session.report().unwrap();
+		assert_eq!(report.finding, Finding::Undetermined);
+		assert_eq!(report.confidence, Confidence::Low);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_resume_serialized_session() {
+		use crate::adaptive::Quest;
+
+		let key = Key::ISHIHARA_24;
+		let mut session = Session::new(Plan::FULL, ConeTest::from_fn(|_| Quest::new(0.05)));
+		for item in &key.items[..5] {
+			session.respond_plate(&key, PlateResponse::Read(item.normal))
+				.unwrap();
+		}
+
+		let json = serde_json::to_string(&session).unwrap();
+		let resumed: Session<Quest> = serde_json::from_str(&json).unwrap();
+		assert_eq!(resumed, session);
+		assert_eq!(resumed.plates_answered(), 5);
+	}
+}
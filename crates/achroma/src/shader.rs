@@ -0,0 +1,291 @@
+//! Shader source generation for simulating and daltonizing color vision
+//!
+//! A [`Shader`] formats into a self-contained snippet of GLSL, WGSL or HLSL
+//! with the matrices of a [`Simulation`] inlined, producing the same results
+//! as the CPU reference in [`crate::simulation`]. The generated entry point
+//! takes and returns an sRGB-encoded color with channels in `0.0..=1.0`,
+//! named either `achroma_simulate` or `achroma_daltonize`.
+//!
+//! ```
+//! use achroma::ColorVision;
+//! use achroma::shader::{Language, Operation, Shader};
+//! use achroma::simulation::Simulation;
+//!
+//! let simulation = Simulation::new(ColorVision::Deuteranomaly, 0.6);
+//! let shader = Shader::new(Language::Wgsl, Operation::Simulate, simulation);
+//!
+//! let source = shader.to_string();
+//! assert!(source.contains("fn achroma_simulate(srgb: vec3<f32>) -> vec3<f32>"));
+//! ```
+
+use crate::simulation::{Matrix3, Simulation};
+use core::fmt;
+
+/// A shading language to generate source code for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+	/// OpenGL Shading Language (GLSL 3.30 / GLSL ES 3.00 and later)
+	Glsl,
+	/// WebGPU Shading Language
+	Wgsl,
+	/// High-Level Shading Language (Shader Model 4 and later)
+	Hlsl,
+}
+
+/// The operation performed by a generated shader
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum Operation {
+	/// Simulate how a color appears with the given color vision
+	#[default]
+	Simulate,
+	/// Correct a color to be easier to distinguish with the given color vision
+	Daltonize,
+}
+
+impl Operation {
+	/// The name of the generated entry point function
+	///
+	/// ```
+	/// use achroma::shader::Operation;
+	///
+	/// assert_eq!(Operation::Simulate.function_name(), "achroma_simulate");
+	/// assert_eq!(Operation::Daltonize.function_name(), "achroma_daltonize");
+	/// ```
+	pub const fn function_name(&self) -> &'static str {
+		match self {
+			Self::Simulate => "achroma_simulate",
+			Self::Daltonize => "achroma_daltonize",
+		}
+	}
+}
+
+/// Shader source for a simulation, which is generated when formatted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shader {
+	language: Language,
+	operation: Operation,
+	simulation: Simulation,
+}
+
+impl Shader {
+	/// Creates a new shader in a given language
+	pub const fn new(language: Language, operation: Operation, simulation: Simulation) -> Self {
+		Self {
+			language,
+			operation,
+			simulation,
+		}
+	}
+
+	/// The language of the shader source
+	pub const fn language(&self) -> Language {
+		self.language
+	}
+
+	/// The operation the shader performs
+	pub const fn operation(&self) -> Operation {
+		self.operation
+	}
+
+	/// The simulation the shader is generated from
+	pub const fn simulation(&self) -> Simulation {
+		self.simulation
+	}
+
+	fn write_header(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let action = match self.operation {
+			Operation::Simulate => "simulation",
+			Operation::Daltonize => "daltonization",
+		};
+		writeln!(
+			f,
+			"// Generated by achroma: {:?} {} at severity {:.2}",
+			self.simulation.vision(),
+			action,
+			self.simulation.severity()
+		)?;
+		writeln!(f, "// Input and output colors are sRGB-encoded in [0, 1].")?;
+		writeln!(f)
+	}
+
+	fn write_glsl(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(concat!(
+			"vec3 achroma_srgb_to_linear(vec3 c) {\n",
+			"\tvec3 lo = c / 12.92;\n",
+			"\tvec3 hi = pow((c + 0.055) / 1.055, vec3(2.4));\n",
+			"\treturn mix(hi, lo, step(c, vec3(0.04045)));\n",
+			"}\n",
+			"\n",
+			"vec3 achroma_linear_to_srgb(vec3 c) {\n",
+			"\tvec3 lo = c * 12.92;\n",
+			"\tvec3 hi = 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055;\n",
+			"\treturn mix(hi, lo, step(c, vec3(0.0031308)));\n",
+			"}\n",
+			"\n",
+		))?;
+
+		let glsl_matrix = |f: &mut fmt::Formatter<'_>, name: &str, m: &Matrix3| {
+			// GLSL matrices are constructed column by column
+			writeln!(f, "const mat3 {} = mat3(", name)?;
+			for (i, col) in transpose(m).iter().enumerate() {
+				let sep = if i < 2 { "," } else { "" };
+				writeln!(
+					f,
+					"\t{:.6}, {:.6}, {:.6}{}",
+					col[0], col[1], col[2], sep
+				)?;
+			}
+			writeln!(f, ");")?;
+			writeln!(f)
+		};
+
+		glsl_matrix(f, "ACHROMA_SIMULATE", &self.simulation.matrix())?;
+		match self.operation {
+			Operation::Simulate => f.write_str(concat!(
+				"vec3 achroma_simulate(vec3 srgb) {\n",
+				"\tvec3 lin = achroma_srgb_to_linear(srgb);\n",
+				"\tvec3 simulated = ACHROMA_SIMULATE * lin;\n",
+				"\treturn achroma_linear_to_srgb(clamp(simulated, 0.0, 1.0));\n",
+				"}\n",
+			)),
+			Operation::Daltonize => {
+				glsl_matrix(f, "ACHROMA_SHIFT", &self.simulation.shift_matrix())?;
+				f.write_str(concat!(
+					"vec3 achroma_daltonize(vec3 srgb) {\n",
+					"\tvec3 lin = achroma_srgb_to_linear(srgb);\n",
+					"\tvec3 error = lin - ACHROMA_SIMULATE * lin;\n",
+					"\tvec3 corrected = lin + ACHROMA_SHIFT * error;\n",
+					"\treturn achroma_linear_to_srgb(clamp(corrected, 0.0, 1.0));\n",
+					"}\n",
+				))
+			}
+		}
+	}
+
+	fn write_wgsl(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(concat!(
+			"fn achroma_srgb_to_linear(c: vec3<f32>) -> vec3<f32> {\n",
+			"\tlet lo = c / 12.92;\n",
+			"\tlet hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));\n",
+			"\treturn select(hi, lo, c <= vec3<f32>(0.04045));\n",
+			"}\n",
+			"\n",
+			"fn achroma_linear_to_srgb(c: vec3<f32>) -> vec3<f32> {\n",
+			"\tlet lo = c * 12.92;\n",
+			"\tlet hi = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;\n",
+			"\treturn select(hi, lo, c <= vec3<f32>(0.0031308));\n",
+			"}\n",
+			"\n",
+		))?;
+
+		let wgsl_matrix = |f: &mut fmt::Formatter<'_>, name: &str, m: &Matrix3| {
+			// WGSL matrices are constructed column by column
+			writeln!(f, "const {} = mat3x3<f32>(", name)?;
+			for (i, col) in transpose(m).iter().enumerate() {
+				let sep = if i < 2 { "," } else { "" };
+				writeln!(
+					f,
+					"\tvec3<f32>({:.6}, {:.6}, {:.6}){}",
+					col[0], col[1], col[2], sep
+				)?;
+			}
+			writeln!(f, ");")?;
+			writeln!(f)
+		};
+
+		wgsl_matrix(f, "ACHROMA_SIMULATE", &self.simulation.matrix())?;
+		match self.operation {
+			Operation::Simulate => f.write_str(concat!(
+				"fn achroma_simulate(srgb: vec3<f32>) -> vec3<f32> {\n",
+				"\tlet lin = achroma_srgb_to_linear(srgb);\n",
+				"\tlet simulated = ACHROMA_SIMULATE * lin;\n",
+				"\treturn achroma_linear_to_srgb(clamp(simulated, vec3<f32>(0.0), vec3<f32>(1.0)));\n",
+				"}\n",
+			)),
+			Operation::Daltonize => {
+				wgsl_matrix(f, "ACHROMA_SHIFT", &self.simulation.shift_matrix())?;
+				f.write_str(concat!(
+					"fn achroma_daltonize(srgb: vec3<f32>) -> vec3<f32> {\n",
+					"\tlet lin = achroma_srgb_to_linear(srgb);\n",
+					"\tlet error = lin - ACHROMA_SIMULATE * lin;\n",
+					"\tlet corrected = lin + ACHROMA_SHIFT * error;\n",
+					"\treturn achroma_linear_to_srgb(clamp(corrected, vec3<f32>(0.0), vec3<f32>(1.0)));\n",
+					"}\n",
+				))
+			}
+		}
+	}
+
+	fn write_hlsl(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(concat!(
+			"float3 achroma_srgb_to_linear(float3 c) {\n",
+			"\tfloat3 lo = c / 12.92;\n",
+			"\tfloat3 hi = pow((c + 0.055) / 1.055, 2.4);\n",
+			"\treturn lerp(hi, lo, step(c, 0.04045));\n",
+			"}\n",
+			"\n",
+			"float3 achroma_linear_to_srgb(float3 c) {\n",
+			"\tfloat3 lo = c * 12.92;\n",
+			"\tfloat3 hi = 1.055 * pow(c, 1.0 / 2.4) - 0.055;\n",
+			"\treturn lerp(hi, lo, step(c, 0.0031308));\n",
+			"}\n",
+			"\n",
+		))?;
+
+		let hlsl_matrix = |f: &mut fmt::Formatter<'_>, name: &str, m: &Matrix3| {
+			// HLSL matrices are constructed row by row
+			writeln!(f, "static const float3x3 {} = float3x3(", name)?;
+			for (i, row) in m.iter().enumerate() {
+				let sep = if i < 2 { "," } else { "" };
+				writeln!(
+					f,
+					"\t{:.6}, {:.6}, {:.6}{}",
+					row[0], row[1], row[2], sep
+				)?;
+			}
+			writeln!(f, ");")?;
+			writeln!(f)
+		};
+
+		hlsl_matrix(f, "ACHROMA_SIMULATE", &self.simulation.matrix())?;
+		match self.operation {
+			Operation::Simulate => f.write_str(concat!(
+				"float3 achroma_simulate(float3 srgb) {\n",
+				"\tfloat3 lin = achroma_srgb_to_linear(srgb);\n",
+				"\tfloat3 simulated = mul(ACHROMA_SIMULATE, lin);\n",
+				"\treturn achroma_linear_to_srgb(saturate(simulated));\n",
+				"}\n",
+			)),
+			Operation::Daltonize => {
+				hlsl_matrix(f, "ACHROMA_SHIFT", &self.simulation.shift_matrix())?;
+				f.write_str(concat!(
+					"float3 achroma_daltonize(float3 srgb) {\n",
+					"\tfloat3 lin = achroma_srgb_to_linear(srgb);\n",
+					"\tfloat3 error = lin - mul(ACHROMA_SIMULATE, lin);\n",
+					"\tfloat3 corrected = lin + mul(ACHROMA_SHIFT, error);\n",
+					"\treturn achroma_linear_to_srgb(saturate(corrected));\n",
+					"}\n",
+				))
+			}
+		}
+	}
+}
+
+impl fmt::Display for Shader {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.write_header(f)?;
+		match self.language {
+			Language::Glsl => self.write_glsl(f),
+			Language::Wgsl => self.write_wgsl(f),
+			Language::Hlsl => self.write_hlsl(f),
+		}
+	}
+}
+
+fn transpose(m: &Matrix3) -> Matrix3 {
+	[
+		[m[0][0], m[1][0], m[2][0]],
+		[m[0][1], m[1][1], m[2][1]],
+		[m[0][2], m[1][2], m[2][2]],
+	]
+}
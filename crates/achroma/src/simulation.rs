@@ -0,0 +1,602 @@
+//! Simulation and correction (daltonization) of color vision deficiency
+//!
+//! Simulation is based on the physiologically-based model by
+//! [Machado, Oliveira & Fernandes (2009)][machado-2009], and correction uses
+//! the error-shifting approach by [Fidaner, Lin & Ozguven (2005)][fidaner-2005].
+//! All matrices operate on **linear** RGB values with sRGB primaries; the
+//! `*_srgb` methods handle the sRGB transfer function for you.
+//!
+//! ```
+//! use achroma::ColorVision;
+//! use achroma::simulation::Simulation;
+//!
+//! let protanopia = Simulation::from(ColorVision::Protanopia);
+//! let red = [255, 0, 0];
+//!
+//! // pure red appears as a dark yellowish-brown to a protanope
+//! let simulated = protanopia.simulate_srgb(red);
+//! assert!(simulated[0] < 255);
+//! assert!(simulated[1] > 0);
+//! ```
+//!
+//! [machado-2009]: <https://doi.org/10.1109/TVCG.2009.113>
+//! [fidaner-2005]: <https://web.stanford.edu/group/vista/cgi-bin/wiki/index.php/Daltonization>
+
+use crate::ColorVision;
+
+/// A 3×3 matrix in row-major order, transforming linear RGB column vectors
+pub type Matrix3 = [[f32; 3]; 3];
+
+/// The identity matrix, which leaves colors unchanged
+pub const IDENTITY: Matrix3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Relative luminance coefficients of linear sRGB (ITU-R BT.709)
+pub const LUMINANCE: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Machado et al. (2009) matrices for protanomaly, from severity 0.0 to 1.0
+/// in steps of 0.1
+const PROTAN: [Matrix3; 11] = [
+	IDENTITY,
+	[
+		[0.856167, 0.182038, -0.038205],
+		[0.029342, 0.955115, 0.015544],
+		[-0.002880, -0.001563, 1.004443],
+	],
+	[
+		[0.734766, 0.334872, -0.069637],
+		[0.051840, 0.919198, 0.028963],
+		[-0.004928, -0.004209, 1.009137],
+	],
+	[
+		[0.630323, 0.465641, -0.095964],
+		[0.069181, 0.890046, 0.040773],
+		[-0.006308, -0.007724, 1.014032],
+	],
+	[
+		[0.539009, 0.579343, -0.118352],
+		[0.082546, 0.866121, 0.051332],
+		[-0.007136, -0.011959, 1.019095],
+	],
+	[
+		[0.458064, 0.679578, -0.137642],
+		[0.092785, 0.846313, 0.060902],
+		[-0.007494, -0.016807, 1.024301],
+	],
+	[
+		[0.385450, 0.769005, -0.154455],
+		[0.100526, 0.829802, 0.069673],
+		[-0.007442, -0.022190, 1.029632],
+	],
+	[
+		[0.319627, 0.849633, -0.169261],
+		[0.106241, 0.815969, 0.077790],
+		[-0.007025, -0.028051, 1.035076],
+	],
+	[
+		[0.259411, 0.923008, -0.182420],
+		[0.110296, 0.804340, 0.085364],
+		[-0.006276, -0.034346, 1.040622],
+	],
+	[
+		[0.203876, 0.990338, -0.194214],
+		[0.112975, 0.794542, 0.092483],
+		[-0.005222, -0.041043, 1.046265],
+	],
+	[
+		[0.152286, 1.052583, -0.204868],
+		[0.114503, 0.786281, 0.099216],
+		[-0.003882, -0.048116, 1.051998],
+	],
+];
+
+/// Machado et al. (2009) matrices for deuteranomaly, from severity 0.0 to 1.0
+/// in steps of 0.1
+const DEUTAN: [Matrix3; 11] = [
+	IDENTITY,
+	[
+		[0.866435, 0.177704, -0.044139],
+		[0.049567, 0.939063, 0.011370],
+		[-0.003453, 0.007233, 0.996220],
+	],
+	[
+		[0.760729, 0.319078, -0.079807],
+		[0.090568, 0.889315, 0.020117],
+		[-0.006027, 0.013325, 0.992702],
+	],
+	[
+		[0.675425, 0.433850, -0.109275],
+		[0.125303, 0.847755, 0.026942],
+		[-0.007950, 0.018572, 0.989378],
+	],
+	[
+		[0.605511, 0.528560, -0.134071],
+		[0.155318, 0.812366, 0.032316],
+		[-0.009376, 0.023176, 0.986200],
+	],
+	[
+		[0.547494, 0.607765, -0.155259],
+		[0.181692, 0.781742, 0.036566],
+		[-0.010410, 0.027275, 0.983136],
+	],
+	[
+		[0.498864, 0.674741, -0.173604],
+		[0.205199, 0.754872, 0.039929],
+		[-0.011131, 0.030969, 0.980162],
+	],
+	[
+		[0.457771, 0.731899, -0.189670],
+		[0.226409, 0.731012, 0.042579],
+		[-0.011595, 0.034333, 0.977261],
+	],
+	[
+		[0.422823, 0.781057, -0.203881],
+		[0.245752, 0.709602, 0.044646],
+		[-0.011843, 0.037423, 0.974421],
+	],
+	[
+		[0.392952, 0.823610, -0.216562],
+		[0.263559, 0.690210, 0.046232],
+		[-0.011910, 0.040281, 0.971630],
+	],
+	[
+		[0.367322, 0.860646, -0.227968],
+		[0.280085, 0.672501, 0.047413],
+		[-0.011820, 0.042940, 0.968881],
+	],
+];
+
+/// Machado et al. (2009) matrices for tritanomaly, from severity 0.0 to 1.0
+/// in steps of 0.1
+const TRITAN: [Matrix3; 11] = [
+	IDENTITY,
+	[
+		[0.926670, 0.092514, -0.019184],
+		[0.021191, 0.964503, 0.014306],
+		[0.008437, 0.054813, 0.936750],
+	],
+	[
+		[0.895720, 0.133330, -0.029050],
+		[0.029997, 0.945400, 0.024603],
+		[0.013027, 0.104707, 0.882266],
+	],
+	[
+		[0.905871, 0.127791, -0.033662],
+		[0.026856, 0.941251, 0.031893],
+		[0.013410, 0.148296, 0.838294],
+	],
+	[
+		[0.948035, 0.089490, -0.037526],
+		[0.014364, 0.946792, 0.038844],
+		[0.010853, 0.193991, 0.795156],
+	],
+	[
+		[1.017277, 0.027029, -0.044306],
+		[-0.006113, 0.958479, 0.047634],
+		[0.006379, 0.248708, 0.744913],
+	],
+	[
+		[1.104996, -0.046633, -0.058363],
+		[-0.032137, 0.971635, 0.060503],
+		[0.001336, 0.317922, 0.680742],
+	],
+	[
+		[1.193214, -0.109812, -0.083402],
+		[-0.058496, 0.979410, 0.079086],
+		[-0.002346, 0.403492, 0.598854],
+	],
+	[
+		[1.257728, -0.139648, -0.118081],
+		[-0.078003, 0.975409, 0.102594],
+		[-0.003316, 0.501214, 0.502102],
+	],
+	[
+		[1.278864, -0.125333, -0.153531],
+		[-0.084748, 0.957674, 0.127074],
+		[-0.000989, 0.601151, 0.399838],
+	],
+	[
+		[1.255528, -0.076749, -0.178779],
+		[-0.078411, 0.930809, 0.147602],
+		[0.004733, 0.691367, 0.303900],
+	],
+];
+
+/// Viénot, Brettel & Mollon (1999) projection for protanopia
+const VIENOT_PROTAN: Matrix3 = [
+	[0.11238, 0.88762, 0.0],
+	[0.11238, 0.88762, 0.0],
+	[0.00401, -0.00401, 1.0],
+];
+
+/// Viénot, Brettel & Mollon (1999) projection for deuteranopia
+const VIENOT_DEUTAN: Matrix3 = [
+	[0.29275, 0.70725, 0.0],
+	[0.29275, 0.70725, 0.0],
+	[-0.02234, 0.02234, 1.0],
+];
+
+/// Viénot, Brettel & Mollon (1999) projection for tritanopia
+const VIENOT_TRITAN: Matrix3 = [
+	[1.0, 0.14461, -0.14461],
+	[0.0, 0.85924, 0.14076],
+	[0.0, 0.85924, 0.14076],
+];
+
+/// Grayscale projection onto relative luminance
+const ACHROMA: Matrix3 = [LUMINANCE, LUMINANCE, LUMINANCE];
+
+/// Error-shifting matrix for daltonizing red-green deficiencies, which
+/// moves lost red/green information into the green and blue channels
+const SHIFT_RED_GREEN: Matrix3 = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+
+/// Error-shifting matrix for daltonizing blue-yellow deficiencies, which
+/// moves lost blue information into the red and green channels
+const SHIFT_BLUE_YELLOW: Matrix3 = [[1.0, 0.0, 0.7], [0.0, 1.0, 0.7], [0.0, 0.0, 0.0]];
+
+/// An algorithm for simulating color vision deficiency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum Algorithm {
+	/// The model by [Machado, Oliveira & Fernandes (2009)][machado-2009],
+	/// which is tabulated for severities in steps of 0.1
+	///
+	/// [machado-2009]: <https://doi.org/10.1109/TVCG.2009.113>
+	#[default]
+	Machado2009,
+	/// The dichromat projection by [Viénot, Brettel & Mollon (1999)][vienot-1999].
+	/// Anomalous trichromacy is approximated by blending the projection with
+	/// the original color by severity. The model was not designed for tritanopia,
+	/// and is less accurate for it.
+	///
+	/// [vienot-1999]: <https://doi.org/10.1002/(SICI)1520-6378(199908)24:4%3C243::AID-COL5%3E3.0.CO;2-3>
+	Vienot1999,
+}
+
+/// A simulation of a type of color vision at a given severity
+///
+/// The severity ranges from `0.0` (normal color vision) to `1.0`
+/// (the full deficiency). An anomalous trichromacy at severity `1.0` is
+/// simulated identically to its dichromacy, e.g. protanomaly at `1.0`
+/// looks like protanopia.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Simulation {
+	vision: ColorVision,
+	severity: f32,
+	algorithm: Algorithm,
+}
+
+impl Simulation {
+	/// Creates a new simulation of a given color vision and severity.
+	/// The severity is clamped to the range of `0.0..=1.0` when used.
+	pub const fn new(vision: ColorVision, severity: f32) -> Self {
+		Self {
+			vision,
+			severity,
+			algorithm: Algorithm::Machado2009,
+		}
+	}
+
+	/// Uses a different algorithm for the simulation
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::simulation::{Algorithm, Simulation};
+	///
+	/// let simulation = Simulation::from(ColorVision::Protanopia)
+	///     .with_algorithm(Algorithm::Vienot1999);
+	/// assert_eq!(simulation.algorithm(), Algorithm::Vienot1999);
+	/// ```
+	pub const fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+		self.algorithm = algorithm;
+		self
+	}
+
+	/// The algorithm used for the simulation
+	pub const fn algorithm(&self) -> Algorithm {
+		self.algorithm
+	}
+
+	/// The type of color vision being simulated
+	pub const fn vision(&self) -> ColorVision {
+		self.vision
+	}
+
+	/// The severity of the simulation, clamped between `0.0` and `1.0`
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::simulation::Simulation;
+	///
+	/// assert_eq!(Simulation::new(ColorVision::Protanomaly, 1.5).severity(), 1.0);
+	/// assert_eq!(Simulation::new(ColorVision::Protanomaly, -1.0).severity(), 0.0);
+	/// ```
+	pub fn severity(&self) -> f32 {
+		if self.severity.is_nan() {
+			return 0.0;
+		}
+		self.severity.clamp(0.0, 1.0)
+	}
+
+	/// The matrix transforming linear RGB into the simulated linear RGB
+	///
+	/// Severities between the tabulated steps of 0.1 are linearly interpolated.
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::simulation::{Simulation, IDENTITY};
+	///
+	/// assert_eq!(Simulation::new(ColorVision::Normal, 1.0).matrix(), IDENTITY);
+	/// assert_eq!(Simulation::new(ColorVision::Deuteranomaly, 0.0).matrix(), IDENTITY);
+	/// ```
+	pub fn matrix(&self) -> Matrix3 {
+		if self.algorithm == Algorithm::Vienot1999 {
+			let projection = match self.vision {
+				ColorVision::Normal => return IDENTITY,
+				ColorVision::Protanomaly | ColorVision::Protanopia => {
+					&VIENOT_PROTAN
+				}
+				ColorVision::Deuteranomaly | ColorVision::Deuteranopia => {
+					&VIENOT_DEUTAN
+				}
+				ColorVision::Tritanomaly | ColorVision::Tritanopia => {
+					&VIENOT_TRITAN
+				}
+				ColorVision::Achromatomaly | ColorVision::Achromatopsia => &ACHROMA,
+			};
+			return lerp_matrix(&IDENTITY, projection, self.severity());
+		}
+
+		let table = match self.vision {
+			ColorVision::Normal => return IDENTITY,
+			ColorVision::Protanomaly | ColorVision::Protanopia => &PROTAN,
+			ColorVision::Deuteranomaly | ColorVision::Deuteranopia => &DEUTAN,
+			ColorVision::Tritanomaly | ColorVision::Tritanopia => &TRITAN,
+			ColorVision::Achromatomaly | ColorVision::Achromatopsia => {
+				return lerp_matrix(&IDENTITY, &ACHROMA, self.severity());
+			}
+		};
+
+		let scaled = self.severity() * 10.0;
+		let lower = libm::floorf(scaled) as usize;
+		if lower >= 10 {
+			return table[10];
+		}
+		lerp_matrix(&table[lower], &table[lower + 1], scaled - lower as f32)
+	}
+
+	/// The matrix which shifts the simulation error into visible channels
+	/// when daltonizing
+	pub const fn shift_matrix(&self) -> Matrix3 {
+		match self.vision {
+			ColorVision::Tritanomaly | ColorVision::Tritanopia => SHIFT_BLUE_YELLOW,
+			_ => SHIFT_RED_GREEN,
+		}
+	}
+
+	/// Simulates a color given in linear RGB, with each channel in `0.0..=1.0`
+	pub fn simulate_linear(&self, rgb: [f32; 3]) -> [f32; 3] {
+		clamp_rgb(mul(&self.matrix(), rgb))
+	}
+
+	/// Simulates an 8-bit sRGB color
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::simulation::Simulation;
+	///
+	/// let achromatopsia = Simulation::from(ColorVision::Achromatopsia);
+	/// let [r, g, b] = achromatopsia.simulate_srgb([40, 180, 90]);
+	/// assert_eq!(r, g);
+	/// assert_eq!(g, b);
+	/// ```
+	pub fn simulate_srgb(&self, rgb: [u8; 3]) -> [u8; 3] {
+		encode_srgb8(self.simulate_linear(decode_srgb8(rgb)))
+	}
+
+	/// Daltonizes a color given in linear RGB, with each channel in `0.0..=1.0`,
+	/// so that it's easier to distinguish for the simulated color vision
+	pub fn daltonize_linear(&self, rgb: [f32; 3]) -> [f32; 3] {
+		let simulated = mul(&self.matrix(), rgb);
+		let error = [
+			rgb[0] - simulated[0],
+			rgb[1] - simulated[1],
+			rgb[2] - simulated[2],
+		];
+		let shifted = mul(&self.shift_matrix(), error);
+		clamp_rgb([
+			rgb[0] + shifted[0],
+			rgb[1] + shifted[1],
+			rgb[2] + shifted[2],
+		])
+	}
+
+	/// Daltonizes an 8-bit sRGB color
+	///
+	/// ```
+	/// use achroma::ColorVision;
+	/// use achroma::simulation::Simulation;
+	///
+	/// // daltonizing for normal color vision does nothing
+	/// let normal = Simulation::from(ColorVision::Normal);
+	/// assert_eq!(normal.daltonize_srgb([200, 30, 60]), [200, 30, 60]);
+	/// ```
+	pub fn daltonize_srgb(&self, rgb: [u8; 3]) -> [u8; 3] {
+		encode_srgb8(self.daltonize_linear(decode_srgb8(rgb)))
+	}
+}
+
+impl From<ColorVision> for Simulation {
+	/// Creates a simulation of the given color vision at full severity
+	fn from(vision: ColorVision) -> Self {
+		Self::new(vision, 1.0)
+	}
+}
+
+/// Multiplies a row-major matrix with a column vector
+pub fn mul(m: &Matrix3, v: [f32; 3]) -> [f32; 3] {
+	[
+		m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+		m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+		m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+	]
+}
+
+/// Converts a single sRGB-encoded channel in `0.0..=1.0` into linear light
+///
+/// ```
+/// use achroma::simulation::srgb_to_linear;
+///
+/// assert_eq!(srgb_to_linear(0.0), 0.0);
+/// assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+/// ```
+pub fn srgb_to_linear(c: f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		libm::powf((c + 0.055) / 1.055, 2.4)
+	}
+}
+
+/// Converts a single linear light channel in `0.0..=1.0` into sRGB encoding
+///
+/// ```
+/// use achroma::simulation::linear_to_srgb;
+///
+/// assert_eq!(linear_to_srgb(0.0), 0.0);
+/// assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+/// ```
+pub fn linear_to_srgb(c: f32) -> f32 {
+	if c <= 0.0031308 {
+		c * 12.92
+	} else {
+		1.055 * libm::powf(c, 1.0 / 2.4) - 0.055
+	}
+}
+
+/// Decodes an 8-bit sRGB color into linear RGB
+pub fn decode_srgb8(rgb: [u8; 3]) -> [f32; 3] {
+	rgb.map(|c| srgb_to_linear(c as f32 / 255.0))
+}
+
+/// Encodes a linear RGB color into 8-bit sRGB, clamping out-of-gamut values
+pub fn encode_srgb8(rgb: [f32; 3]) -> [u8; 3] {
+	clamp_rgb(rgb).map(|c| libm::roundf(linear_to_srgb(c) * 255.0) as u8)
+}
+
+pub(crate) fn clamp_rgb(rgb: [f32; 3]) -> [f32; 3] {
+	rgb.map(|c| c.clamp(0.0, 1.0))
+}
+
+pub(crate) fn lerp_matrix(a: &Matrix3, b: &Matrix3, t: f32) -> Matrix3 {
+	let mut out = [[0.0; 3]; 3];
+	for (row, out_row) in out.iter_mut().enumerate() {
+		for (col, out_value) in out_row.iter_mut().enumerate() {
+			*out_value = a[row][col] + (b[row][col] - a[row][col]) * t;
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_tables_preserve_white() {
+		for table in [
+			&PROTAN[..],
+			&DEUTAN,
+			&TRITAN,
+			&[VIENOT_PROTAN, VIENOT_DEUTAN, VIENOT_TRITAN],
+		] {
+			for matrix in table.iter() {
+				for row in matrix.iter() {
+					let sum: f32 = row.iter().sum();
+					assert!(
+						libm::fabsf(sum - 1.0) < 1e-4,
+						"row {:?} sums to {}",
+						row,
+						sum
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn test_matrix_severity_steps() {
+		let sim = Simulation::new(ColorVision::Protanomaly, 0.5);
+		assert_eq!(sim.matrix(), PROTAN[5]);
+
+		let sim = Simulation::new(ColorVision::Tritanopia, 1.0);
+		assert_eq!(sim.matrix(), TRITAN[10]);
+	}
+
+	#[test]
+	fn test_matrix_severity_interpolated() {
+		let sim = Simulation::new(ColorVision::Deuteranomaly, 0.55);
+		let m = sim.matrix();
+		let expected = (DEUTAN[5][0][0] + DEUTAN[6][0][0]) / 2.0;
+		assert!(libm::fabsf(m[0][0] - expected) < 1e-6);
+	}
+
+	#[test]
+	fn test_vienot_projects_confusion_pairs() {
+		let sim = Simulation::from(ColorVision::Protanopia)
+			.with_algorithm(Algorithm::Vienot1999);
+		let [r, g, _] = sim.simulate_linear([0.8, 0.2, 0.3]);
+		assert!(libm::fabsf(r - g) < 1e-6);
+
+		let half = Simulation::new(ColorVision::Deuteranomaly, 0.5)
+			.with_algorithm(Algorithm::Vienot1999)
+			.matrix();
+		assert!(libm::fabsf(half[0][0] - (1.0 + VIENOT_DEUTAN[0][0]) / 2.0) < 1e-6);
+	}
+
+	#[test]
+	fn test_matrix_nan_severity() {
+		let sim = Simulation::new(ColorVision::Protanopia, f32::NAN);
+		assert_eq!(sim.matrix(), IDENTITY);
+	}
+
+	#[test]
+	fn test_srgb_roundtrip() {
+		for c in 0..=255u8 {
+			let [r, g, b] = encode_srgb8(decode_srgb8([c, c, c]));
+			assert_eq!((r, g, b), (c, c, c));
+		}
+	}
+
+	#[test]
+	fn test_simulate_gray_is_unchanged() {
+		for vision in [
+			ColorVision::Protanopia,
+			ColorVision::Deuteranopia,
+			ColorVision::Tritanopia,
+			ColorVision::Achromatopsia,
+		] {
+			let sim = Simulation::from(vision);
+			let [r, g, b] = sim.simulate_srgb([128, 128, 128]);
+			assert!(r.abs_diff(128) <= 1
+				&& g.abs_diff(128) <= 1 && b.abs_diff(128) <= 1);
+		}
+	}
+
+	#[test]
+	fn test_simulate_red_green_confusion() {
+		let sim = Simulation::from(ColorVision::Deuteranopia);
+		let red = sim.simulate_linear([1.0, 0.0, 0.0]);
+		let green = sim.simulate_linear([0.0, 1.0, 0.0]);
+
+		// both primaries collapse onto a yellow-ish axis with little blue
+		assert!(red[2] < 0.01);
+		assert!(green[2] < 0.05);
+	}
+
+	#[test]
+	fn test_daltonize_shifts_error() {
+		let sim = Simulation::from(ColorVision::Protanopia);
+		let daltonized = sim.daltonize_linear([1.0, 0.0, 0.0]);
+		assert_eq!(daltonized[0], 1.0);
+		assert!(daltonized[2] > 0.0);
+	}
+}
@@ -0,0 +1,403 @@
+//! Photoreceptors of other species, and false color views of how they see
+//!
+//! A [ReceptorSet] holds the peak wavelengths of any number of classes of
+//! cone cells, up to [MAX_RECEPTORS], each with a pigment described by the
+//! [Govardovskii template][govardovskii_a1]. Presets cover the dichromacy of
+//! dogs and cats, the tetrachromacy of birds with ultraviolet sensitive cones,
+//! and the trichromacy of honeybees, which is shifted towards the ultraviolet.
+//!
+//! A [SpeciesSimulation] views an sRGB image through a receptor set in false
+//! color, where each class of cone cells drives a color from blue for the
+//! shortest wavelength to red for the longest, and white stays white. As
+//! displays don't emit ultraviolet light, ultraviolet cones only respond to
+//! the blue primary, and the view can't show ultraviolet patterns that the
+//! animal would see in the real scene.
+//!
+//! ```
+//! use achroma::species::{ReceptorSet, SpeciesSimulation};
+//!
+//! let dog = SpeciesSimulation::new(&ReceptorSet::DOG);
+//!
+//! // dogs confuse red and green, which both look yellowish
+//! let red = dog.simulate_srgb([255, 0, 0]);
+//! let green = dog.simulate_srgb([0, 255, 0]);
+//! assert!(red[0] > red[2] && green[0] > green[2]);
+//! assert_eq!(dog.simulate_srgb([255, 255, 255]), [255, 255, 255]);
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::simulation::{decode_srgb8, encode_srgb8, mul, Matrix3};
+use crate::spectral::{govardovskii_a1, L_PEAK, M_PEAK, S_PEAK};
+
+/// The largest number of classes of cone cells in a set
+pub const MAX_RECEPTORS: usize = 6;
+
+/// The emission spectra of the sRGB primaries, approximated as Gaussians typical
+/// of LCD displays, given as the peak wavelength and the standard deviation in nanometers
+pub const PRIMARIES: [(f32, f32); 3] = [(610.0, 15.0), (545.0, 20.0), (450.0, 12.0)];
+
+/// A set of classes of cone cells, ordered by their peak wavelengths
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawReceptorSet"))]
+pub struct ReceptorSet {
+	peaks: [f32; MAX_RECEPTORS],
+	len: usize,
+}
+
+/// The fields of a [ReceptorSet] before they're validated
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawReceptorSet {
+	peaks: [f32; MAX_RECEPTORS],
+	len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawReceptorSet> for ReceptorSet {
+	type Error = &'static str;
+
+	fn try_from(raw: RawReceptorSet) -> Result<Self, Self::Error> {
+		raw.peaks
+			.get(..raw.len)
+			.and_then(Self::new)
+			.ok_or("expected from 1 to 6 peaks")
+	}
+}
+
+impl ReceptorSet {
+	/// The S, M and L cone cells of humans
+	pub const HUMAN: Self = Self::from_sorted(&[S_PEAK, M_PEAK, L_PEAK]);
+
+	/// The dichromacy of dogs, following Neitz, Geist & Jacobs, "Color vision
+	/// in the dog", Visual Neuroscience 3 (1989)
+	pub const DOG: Self = Self::from_sorted(&[429.0, 555.0]);
+
+	/// The dichromacy of cats, with approximate peaks of their S and L cone cells
+	pub const CAT: Self = Self::from_sorted(&[450.0, 556.0]);
+
+	/// The tetrachromacy of birds with ultraviolet sensitive cones, such as
+	/// songbirds, with approximate peaks following the review by Hart,
+	/// "The visual ecology of avian photoreceptors", Progress in Retinal and
+	/// Eye Research 20 (2001)
+	pub const BIRD: Self = Self::from_sorted(&[370.0, 445.0, 508.0, 565.0]);
+
+	/// The ultraviolet, blue and green receptors of honeybees, following
+	/// Peitsch et al., "The spectral input systems of hymenopteran insects
+	/// and their receptor-based colour vision", Journal of Comparative
+	/// Physiology A 170 (1992)
+	pub const HONEYBEE: Self = Self::from_sorted(&[344.0, 436.0, 544.0]);
+
+	const fn from_sorted(peaks: &[f32]) -> Self {
+		let mut set = [0.0; MAX_RECEPTORS];
+		let mut i = 0;
+		while i < peaks.len() {
+			set[i] = peaks[i];
+			i += 1;
+		}
+		Self {
+			peaks: set,
+			len: peaks.len(),
+		}
+	}
+
+	/// Creates a new set from the peak wavelengths in nanometers of its
+	/// classes of cone cells in any order, or [None] when there are none or
+	/// more than [MAX_RECEPTORS]
+	///
+	/// ```
+	/// use achroma::species::ReceptorSet;
+	///
+	/// let set = ReceptorSet::new(&[555.0, 429.0]).unwrap();
+	/// assert_eq!(set, ReceptorSet::DOG);
+	/// assert_eq!(ReceptorSet::new(&[]), None);
+	/// ```
+	pub fn new(peaks: &[f32]) -> Option<Self> {
+		if peaks.is_empty() || peaks.len() > MAX_RECEPTORS {
+			return None;
+		}
+		let mut set = Self::from_sorted(peaks);
+		set.peaks[..set.len].sort_unstable_by(f32::total_cmp);
+		Some(set)
+	}
+
+	/// The peak wavelengths in nanometers, from the shortest to the longest
+	pub fn peaks(&self) -> &[f32] {
+		&self.peaks[..self.len]
+	}
+
+	/// The number of classes of cone cells
+	pub const fn dimensions(&self) -> usize {
+		self.len
+	}
+
+	/// Reports if any class of cone cells peaks in the ultraviolet, below 400 nm
+	pub fn has_ultraviolet(&self) -> bool {
+		self.peaks().iter().any(|&peak| peak < 400.0)
+	}
+
+	/// The relative sensitivity of each class of cone cells at a wavelength in nanometers
+	///
+	/// ```
+	/// use achroma::species::ReceptorSet;
+	///
+	/// let mut sensitivities = ReceptorSet::HONEYBEE.sensitivities(350.0);
+	/// // the ultraviolet receptor responds most
+	/// assert!(sensitivities.next().unwrap() > 0.9);
+	/// ```
+	pub fn sensitivities(&self, wavelength: f32) -> impl Iterator<Item = f32> + '_ {
+		self.peaks()
+			.iter()
+			.map(move |&peak| govardovskii_a1(peak, wavelength))
+	}
+}
+
+impl Default for ReceptorSet {
+	fn default() -> Self {
+		Self::HUMAN
+	}
+}
+
+/// A species with a preset receptor set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Species {
+	/// Humans, see [ReceptorSet::HUMAN]
+	Human,
+	/// Dogs, see [ReceptorSet::DOG]
+	Dog,
+	/// Cats, see [ReceptorSet::CAT]
+	Cat,
+	/// Birds, see [ReceptorSet::BIRD]
+	Bird,
+	/// Honeybees, see [ReceptorSet::HONEYBEE]
+	Honeybee,
+}
+
+impl Species {
+	/// All species with a preset
+	pub const ALL: [Self; 5] = [
+		Self::Human,
+		Self::Dog,
+		Self::Cat,
+		Self::Bird,
+		Self::Honeybee,
+	];
+
+	/// Gets the lowercase name of the species, such as `"honeybee"`
+	pub const fn name(&self) -> &'static str {
+		match self {
+			Self::Human => "human",
+			Self::Dog => "dog",
+			Self::Cat => "cat",
+			Self::Bird => "bird",
+			Self::Honeybee => "honeybee",
+		}
+	}
+
+	/// The preset receptor set of the species
+	pub const fn receptors(&self) -> ReceptorSet {
+		match self {
+			Self::Human => ReceptorSet::HUMAN,
+			Self::Dog => ReceptorSet::DOG,
+			Self::Cat => ReceptorSet::CAT,
+			Self::Bird => ReceptorSet::BIRD,
+			Self::Honeybee => ReceptorSet::HONEYBEE,
+		}
+	}
+}
+
+impl fmt::Display for Species {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Species {
+	type Err = ();
+	/// Attempt to parse the name of a species, ignoring ASCII case
+	///
+	/// ```
+	/// use achroma::species::Species;
+	///
+	/// assert_eq!("Dog".parse(), Ok(Species::Dog));
+	/// assert_eq!("cuttlefish".parse::<Species>(), Err(()));
+	/// ```
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|species| species.name().eq_ignore_ascii_case(s.trim()))
+			.ok_or(())
+	}
+}
+
+impl From<Species> for ReceptorSet {
+	fn from(species: Species) -> Self {
+		species.receptors()
+	}
+}
+
+/// A false color simulation of how a receptor set sees an sRGB image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeciesSimulation {
+	matrix: Matrix3,
+}
+
+impl SpeciesSimulation {
+	/// Creates a new simulation of a receptor set
+	pub fn new(set: &ReceptorSet) -> Self {
+		let peaks = set.peaks();
+
+		// the catch of each primary by each class, normalized to the catch of white
+		let mut catches = [[0.0; 3]; MAX_RECEPTORS];
+		for (catch, &peak) in catches.iter_mut().zip(peaks) {
+			for (value, (center, width)) in catch.iter_mut().zip(PRIMARIES) {
+				let mut wavelength = 300.0;
+				while wavelength <= 800.0 {
+					let distance = (wavelength - center) / width;
+					*value += libm::expf(-0.5 * distance * distance)
+						* govardovskii_a1(peak, wavelength);
+					wavelength += 2.0;
+				}
+			}
+			let white: f32 = catch.iter().sum();
+			*catch = catch.map(|value| value / white);
+		}
+
+		let colors = false_colors(peaks.len());
+		let mut matrix = [[0.0; 3]; 3];
+		for (channel, row) in matrix.iter_mut().enumerate() {
+			for (primary, value) in row.iter_mut().enumerate() {
+				*value = (0..peaks.len())
+					.map(|receptor| {
+						colors[receptor][channel]
+							* catches[receptor][primary]
+					})
+					.sum();
+			}
+		}
+		Self { matrix }
+	}
+
+	/// The matrix transforming linear RGB into the simulated linear RGB
+	pub const fn matrix(&self) -> Matrix3 {
+		self.matrix
+	}
+
+	/// Simulates a linear RGB color
+	pub fn simulate_linear(&self, rgb: [f32; 3]) -> [f32; 3] {
+		mul(&self.matrix, rgb)
+	}
+
+	/// Simulates an 8-bit sRGB color
+	pub fn simulate_srgb(&self, rgb: [u8; 3]) -> [u8; 3] {
+		encode_srgb8(self.simulate_linear(decode_srgb8(rgb)))
+	}
+}
+
+impl From<ReceptorSet> for SpeciesSimulation {
+	fn from(set: ReceptorSet) -> Self {
+		Self::new(&set)
+	}
+}
+
+impl From<Species> for SpeciesSimulation {
+	fn from(species: Species) -> Self {
+		Self::new(&species.receptors())
+	}
+}
+
+/// The linear RGB color shown for each class of cone cells, from the shortest
+/// to the longest wavelength, where all colors add up to white
+fn false_colors(len: usize) -> [[f32; 3]; MAX_RECEPTORS] {
+	let mut colors = [[0.0; 3]; MAX_RECEPTORS];
+	match len {
+		0 => return colors,
+		1 => colors[0] = [1.0; 3],
+		// blue and yellow, as in the views of dichromats
+		2 => colors[..2].copy_from_slice(&[[0.0, 0.0, 1.0], [1.0, 1.0, 0.0]]),
+		_ => {
+			// a ramp from blue through green to red, with each channel normalized
+			for (index, color) in colors[..len].iter_mut().enumerate() {
+				let t = index as f32 / (len - 1) as f32;
+				*color = [
+					(2.0 * t - 1.0).max(0.0),
+					1.0 - libm::fabsf(2.0 * t - 1.0),
+					(1.0 - 2.0 * t).max(0.0),
+				];
+			}
+			for channel in 0..3 {
+				let total: f32 =
+					colors[..len].iter().map(|color| color[channel]).sum();
+				for color in &mut colors[..len] {
+					color[channel] /= total;
+				}
+			}
+		}
+	}
+	colors
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_presets_preserve_white() {
+		for set in [
+			ReceptorSet::HUMAN,
+			ReceptorSet::DOG,
+			ReceptorSet::CAT,
+			ReceptorSet::BIRD,
+			ReceptorSet::HONEYBEE,
+			ReceptorSet::new(&[400.0]).unwrap(),
+			ReceptorSet::new(&[350.0, 400.0, 450.0, 500.0, 550.0, 600.0]).unwrap(),
+		] {
+			let simulation = SpeciesSimulation::new(&set);
+			for value in simulation.simulate_linear([1.0; 3]) {
+				assert!((value - 1.0).abs() < 1e-4, "{:?}", set);
+			}
+			assert_eq!(simulation.simulate_srgb([0; 3]), [0; 3]);
+		}
+	}
+
+	#[test]
+	fn test_monochromat_is_gray() {
+		let simulation = SpeciesSimulation::new(&ReceptorSet::new(&[500.0]).unwrap());
+		let [r, g, b] = simulation.simulate_srgb([200, 30, 90]);
+		assert_eq!((r, g), (g, b));
+	}
+
+	#[test]
+	fn test_false_colors() {
+		let colors = false_colors(3);
+		assert_eq!(
+			&colors[..3],
+			&[[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]
+		);
+		assert!(ReceptorSet::BIRD.has_ultraviolet());
+		assert!(!ReceptorSet::DOG.has_ultraviolet());
+		assert_eq!(ReceptorSet::new(&[500.0; MAX_RECEPTORS + 1]), None);
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn test_deserialize_receptor_set() {
+		let json = serde_json::to_string(&ReceptorSet::BIRD).unwrap();
+		let set: ReceptorSet = serde_json::from_str(&json).unwrap();
+		assert_eq!(set, ReceptorSet::BIRD);
+
+		let json = r#"{"peaks":[560,420,0,0,0,0],"len":2}"#;
+		let set: ReceptorSet = serde_json::from_str(json).unwrap();
+		assert_eq!(set.peaks(), &[420.0, 560.0]);
+
+		for json in [
+			r#"{"peaks":[1,2,3,4,5,6],"len":0}"#,
+			r#"{"peaks":[1,2,3,4,5,6],"len":9}"#,
+		] {
+			assert!(serde_json::from_str::<ReceptorSet>(json).is_err());
+		}
+	}
+}
@@ -0,0 +1,102 @@
+//! Spectral sensitivity of photopigments
+//!
+//! The sensitivity of a visual pigment is well described by a template
+//! that only depends on the wavelength of its peak sensitivity (λmax),
+//! such as the nomogram by Govardovskii et al., "In search of the visual
+//! pigment template", Visual Neuroscience 17 (2000).
+//!
+//! ```
+//! use achroma::spectral::{govardovskii_a1, L_PEAK, M_PEAK};
+//!
+//! // at 670 nm, long cones are far more sensitive than medium cones
+//! let l = govardovskii_a1(L_PEAK, 670.0);
+//! let m = govardovskii_a1(M_PEAK, 670.0);
+//! assert!(l > 5.0 * m);
+//! ```
+
+/// The peak wavelength in nanometers of a normal long (L) cone photopigment
+pub const L_PEAK: f32 = 559.0;
+
+/// The peak wavelength in nanometers of a normal medium (M) cone photopigment
+pub const M_PEAK: f32 = 530.0;
+
+/// The peak wavelength in nanometers of a normal short (S) cone photopigment
+pub const S_PEAK: f32 = 420.0;
+
+/// The relative sensitivity of a vitamin A1 based photopigment with a given
+/// peak wavelength, at a wavelength in nanometers, normalized to 1.0 at the peak.
+///
+/// This combines the α-band and β-band of the template, and doesn't include
+/// any filtering by the lens or macular pigment.
+///
+/// ```
+/// use achroma::spectral::{govardovskii_a1, S_PEAK};
+///
+/// assert!((govardovskii_a1(S_PEAK, S_PEAK) - 1.0).abs() < 0.01);
+/// assert!(govardovskii_a1(S_PEAK, 600.0) < 0.01);
+/// ```
+pub fn govardovskii_a1(peak: f32, wavelength: f32) -> f32 {
+	let x = peak / wavelength;
+	let a = 0.8795 + 0.0459 * libm::expf(-(peak - 300.0) * (peak - 300.0) / 11940.0);
+	let alpha = 1.0
+		/ (libm::expf(69.7 * (a - x))
+			+ libm::expf(28.0 * (0.922 - x))
+			+ libm::expf(-14.9 * (1.104 - x))
+			+ 0.674);
+
+	let beta_peak = 189.0 + 0.315 * peak;
+	let beta_width = -40.5 + 0.195 * peak;
+	let beta = 0.26 * libm::expf(-libm::powf((wavelength - beta_peak) / beta_width, 2.0));
+	alpha + beta
+}
+
+/// The optical density of the ocular media, mostly the lens, for an observer
+/// of an age in years at a wavelength in nanometers, following van de Kraats
+/// & van Norren, "Optical density of the aging human ocular media", Journal
+/// of the Optical Society of America A 24 (2007)
+///
+/// The lens yellows with age, and absorbs more short wavelength light.
+///
+/// ```
+/// use achroma::spectral::lens_density;
+///
+/// assert!(lens_density(32.0, 400.0) > lens_density(32.0, 500.0));
+/// assert!(lens_density(70.0, 420.0) > lens_density(32.0, 420.0));
+/// ```
+pub fn lens_density(age: f32, wavelength: f32) -> f32 {
+	let band = |center: f32, width: f32| {
+		let distance = width * (wavelength - center);
+		libm::expf(-distance * distance)
+	};
+	let age2 = age * age;
+	(0.446 + 0.000031 * age2) * libm::powf(400.0 / wavelength, 4.0)
+		+ 14.19 * 10.68 * band(273.0, 0.057)
+		+ (0.998 - 0.000063 * age2) * 2.13 * band(370.0, 0.029)
+		+ (0.059 + 0.000186 * age2) * 11.95 * band(325.0, 0.021)
+		+ (0.016 + 0.000132 * age2) * 1.43 * band(325.0, 0.008)
+		+ 0.111
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_peak_is_maximum() {
+		for peak in [S_PEAK, M_PEAK, L_PEAK] {
+			let at_peak = govardovskii_a1(peak, peak);
+			assert!(libm::fabsf(at_peak - 1.0) < 0.01);
+			for offset in [-20.0, -5.0, 5.0, 20.0] {
+				assert!(govardovskii_a1(peak, peak + offset) < at_peak);
+			}
+		}
+	}
+
+	#[test]
+	fn test_half_width() {
+		// the α-band of an L cone pigment is roughly 100 nm wide at half maximum
+		let half = |wavelength| govardovskii_a1(L_PEAK, wavelength) - 0.5;
+		assert!(half(510.0) > 0.0 && half(495.0) < 0.0);
+		assert!(half(600.0) > 0.0 && half(615.0) < 0.0);
+	}
+}
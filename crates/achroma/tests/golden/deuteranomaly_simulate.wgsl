@@ -0,0 +1,26 @@
+// Generated by achroma: Deuteranomaly simulation at severity 0.60
+// Input and output colors are sRGB-encoded in [0, 1].
+
+fn achroma_srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+	let lo = c / 12.92;
+	let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+	return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+fn achroma_linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+	let lo = c * 12.92;
+	let hi = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+	return select(hi, lo, c <= vec3<f32>(0.0031308));
+}
+
+const ACHROMA_SIMULATE = mat3x3<f32>(
+	vec3<f32>(0.498864, 0.205199, -0.011131),
+	vec3<f32>(0.674741, 0.754872, 0.030969),
+	vec3<f32>(-0.173604, 0.039929, 0.980162)
+);
+
+fn achroma_simulate(srgb: vec3<f32>) -> vec3<f32> {
+	let lin = achroma_srgb_to_linear(srgb);
+	let simulated = ACHROMA_SIMULATE * lin;
+	return achroma_linear_to_srgb(clamp(simulated, vec3<f32>(0.0), vec3<f32>(1.0)));
+}
@@ -0,0 +1,33 @@
+// Generated by achroma: Deuteranopia daltonization at severity 1.00
+// Input and output colors are sRGB-encoded in [0, 1].
+
+vec3 achroma_srgb_to_linear(vec3 c) {
+	vec3 lo = c / 12.92;
+	vec3 hi = pow((c + 0.055) / 1.055, vec3(2.4));
+	return mix(hi, lo, step(c, vec3(0.04045)));
+}
+
+vec3 achroma_linear_to_srgb(vec3 c) {
+	vec3 lo = c * 12.92;
+	vec3 hi = 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055;
+	return mix(hi, lo, step(c, vec3(0.0031308)));
+}
+
+const mat3 ACHROMA_SIMULATE = mat3(
+	0.367322, 0.280085, -0.011820,
+	0.860646, 0.672501, 0.042940,
+	-0.227968, 0.047413, 0.968881
+);
+
+const mat3 ACHROMA_SHIFT = mat3(
+	0.000000, 0.700000, 0.700000,
+	0.000000, 1.000000, 0.000000,
+	0.000000, 0.000000, 1.000000
+);
+
+vec3 achroma_daltonize(vec3 srgb) {
+	vec3 lin = achroma_srgb_to_linear(srgb);
+	vec3 error = lin - ACHROMA_SIMULATE * lin;
+	vec3 corrected = lin + ACHROMA_SHIFT * error;
+	return achroma_linear_to_srgb(clamp(corrected, 0.0, 1.0));
+}
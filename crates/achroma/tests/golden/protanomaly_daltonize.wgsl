@@ -0,0 +1,33 @@
+// Generated by achroma: Protanomaly daltonization at severity 0.30
+// Input and output colors are sRGB-encoded in [0, 1].
+
+fn achroma_srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+	let lo = c / 12.92;
+	let hi = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+	return select(hi, lo, c <= vec3<f32>(0.04045));
+}
+
+fn achroma_linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+	let lo = c * 12.92;
+	let hi = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+	return select(hi, lo, c <= vec3<f32>(0.0031308));
+}
+
+const ACHROMA_SIMULATE = mat3x3<f32>(
+	vec3<f32>(0.630323, 0.069181, -0.006308),
+	vec3<f32>(0.465641, 0.890046, -0.007724),
+	vec3<f32>(-0.095964, 0.040773, 1.014032)
+);
+
+const ACHROMA_SHIFT = mat3x3<f32>(
+	vec3<f32>(0.000000, 0.700000, 0.700000),
+	vec3<f32>(0.000000, 1.000000, 0.000000),
+	vec3<f32>(0.000000, 0.000000, 1.000000)
+);
+
+fn achroma_daltonize(srgb: vec3<f32>) -> vec3<f32> {
+	let lin = achroma_srgb_to_linear(srgb);
+	let error = lin - ACHROMA_SIMULATE * lin;
+	let corrected = lin + ACHROMA_SHIFT * error;
+	return achroma_linear_to_srgb(clamp(corrected, vec3<f32>(0.0), vec3<f32>(1.0)));
+}
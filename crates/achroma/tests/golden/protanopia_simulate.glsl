@@ -0,0 +1,26 @@
+// Generated by achroma: Protanopia simulation at severity 1.00
+// Input and output colors are sRGB-encoded in [0, 1].
+
+vec3 achroma_srgb_to_linear(vec3 c) {
+	vec3 lo = c / 12.92;
+	vec3 hi = pow((c + 0.055) / 1.055, vec3(2.4));
+	return mix(hi, lo, step(c, vec3(0.04045)));
+}
+
+vec3 achroma_linear_to_srgb(vec3 c) {
+	vec3 lo = c * 12.92;
+	vec3 hi = 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055;
+	return mix(hi, lo, step(c, vec3(0.0031308)));
+}
+
+const mat3 ACHROMA_SIMULATE = mat3(
+	0.152286, 0.114503, -0.003882,
+	1.052583, 0.786281, -0.048116,
+	-0.204868, 0.099216, 1.051998
+);
+
+vec3 achroma_simulate(vec3 srgb) {
+	vec3 lin = achroma_srgb_to_linear(srgb);
+	vec3 simulated = ACHROMA_SIMULATE * lin;
+	return achroma_linear_to_srgb(clamp(simulated, 0.0, 1.0));
+}
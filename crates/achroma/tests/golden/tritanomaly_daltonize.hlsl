@@ -0,0 +1,33 @@
+// Generated by achroma: Tritanomaly daltonization at severity 0.50
+// Input and output colors are sRGB-encoded in [0, 1].
+
+float3 achroma_srgb_to_linear(float3 c) {
+	float3 lo = c / 12.92;
+	float3 hi = pow((c + 0.055) / 1.055, 2.4);
+	return lerp(hi, lo, step(c, 0.04045));
+}
+
+float3 achroma_linear_to_srgb(float3 c) {
+	float3 lo = c * 12.92;
+	float3 hi = 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+	return lerp(hi, lo, step(c, 0.0031308));
+}
+
+static const float3x3 ACHROMA_SIMULATE = float3x3(
+	1.017277, 0.027029, -0.044306,
+	-0.006113, 0.958479, 0.047634,
+	0.006379, 0.248708, 0.744913
+);
+
+static const float3x3 ACHROMA_SHIFT = float3x3(
+	1.000000, 0.000000, 0.700000,
+	0.000000, 1.000000, 0.700000,
+	0.000000, 0.000000, 0.000000
+);
+
+float3 achroma_daltonize(float3 srgb) {
+	float3 lin = achroma_srgb_to_linear(srgb);
+	float3 error = lin - mul(ACHROMA_SIMULATE, lin);
+	float3 corrected = lin + mul(ACHROMA_SHIFT, error);
+	return achroma_linear_to_srgb(saturate(corrected));
+}
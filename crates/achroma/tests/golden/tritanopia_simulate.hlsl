@@ -0,0 +1,26 @@
+// Generated by achroma: Tritanopia simulation at severity 1.00
+// Input and output colors are sRGB-encoded in [0, 1].
+
+float3 achroma_srgb_to_linear(float3 c) {
+	float3 lo = c / 12.92;
+	float3 hi = pow((c + 0.055) / 1.055, 2.4);
+	return lerp(hi, lo, step(c, 0.04045));
+}
+
+float3 achroma_linear_to_srgb(float3 c) {
+	float3 lo = c * 12.92;
+	float3 hi = 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+	return lerp(hi, lo, step(c, 0.0031308));
+}
+
+static const float3x3 ACHROMA_SIMULATE = float3x3(
+	1.255528, -0.076749, -0.178779,
+	-0.078411, 0.930809, 0.147602,
+	0.004733, 0.691367, 0.303900
+);
+
+float3 achroma_simulate(float3 srgb) {
+	float3 lin = achroma_srgb_to_linear(srgb);
+	float3 simulated = mul(ACHROMA_SIMULATE, lin);
+	return achroma_linear_to_srgb(saturate(simulated));
+}
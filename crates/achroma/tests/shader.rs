@@ -0,0 +1,79 @@
+use achroma::shader::{Language, Operation, Shader};
+use achroma::simulation::Simulation;
+use achroma::ColorVision;
+
+fn assert_golden(shader: Shader, golden: &str) {
+	assert_eq!(shader.to_string(), golden);
+}
+
+#[test]
+fn test_glsl_simulate_protanopia() {
+	assert_golden(
+		Shader::new(
+			Language::Glsl,
+			Operation::Simulate,
+			Simulation::from(ColorVision::Protanopia),
+		),
+		include_str!("golden/protanopia_simulate.glsl"),
+	);
+}
+
+#[test]
+fn test_wgsl_simulate_deuteranomaly() {
+	assert_golden(
+		Shader::new(
+			Language::Wgsl,
+			Operation::Simulate,
+			Simulation::new(ColorVision::Deuteranomaly, 0.6),
+		),
+		include_str!("golden/deuteranomaly_simulate.wgsl"),
+	);
+}
+
+#[test]
+fn test_hlsl_simulate_tritanopia() {
+	assert_golden(
+		Shader::new(
+			Language::Hlsl,
+			Operation::Simulate,
+			Simulation::from(ColorVision::Tritanopia),
+		),
+		include_str!("golden/tritanopia_simulate.hlsl"),
+	);
+}
+
+#[test]
+fn test_glsl_daltonize_deuteranopia() {
+	assert_golden(
+		Shader::new(
+			Language::Glsl,
+			Operation::Daltonize,
+			Simulation::from(ColorVision::Deuteranopia),
+		),
+		include_str!("golden/deuteranopia_daltonize.glsl"),
+	);
+}
+
+#[test]
+fn test_wgsl_daltonize_protanomaly() {
+	assert_golden(
+		Shader::new(
+			Language::Wgsl,
+			Operation::Daltonize,
+			Simulation::new(ColorVision::Protanomaly, 0.3),
+		),
+		include_str!("golden/protanomaly_daltonize.wgsl"),
+	);
+}
+
+#[test]
+fn test_hlsl_daltonize_tritanomaly() {
+	assert_golden(
+		Shader::new(
+			Language::Hlsl,
+			Operation::Daltonize,
+			Simulation::new(ColorVision::Tritanomaly, 0.5),
+		),
+		include_str!("golden/tritanomaly_daltonize.hlsl"),
+	);
+}